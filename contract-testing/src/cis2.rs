@@ -0,0 +1,287 @@
+//! A client for testing contracts implementing the [CIS-2 token standard](https://proposals.concordium.software/CIS/cis-2.html).
+//!
+//! The client serializes the parameters of the standard entrypoints and
+//! parses their responses, so that tests can work with token IDs and token
+//! amounts directly.
+use crate::{
+    Chain, ContractDoesNotExist, ContractInvokeError, ContractInvokeSuccess, ParameterBuilder,
+    ParameterTooLarge, Signer,
+};
+use concordium_rust_sdk::{
+    base::{
+        base::Energy,
+        contracts_common::{
+            constants::MAX_PARAMETER_LEN, AccountAddress, Address, Amount, ContractAddress, Cursor,
+            Deserial, OwnedEntrypointName, OwnedParameter, OwnedReceiveName,
+        },
+        transactions::UpdateContractPayload,
+    },
+    cis2::{TokenAmount, TokenId},
+};
+use thiserror::Error;
+
+/// The energy reserved for each call made by a [`Cis2Client`], unless set
+/// with [`Cis2Client::with_energy`].
+const DEFAULT_ENERGY: Energy = Energy {
+    energy: 100_000,
+};
+
+/// The receiver of a CIS-2 transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cis2Receiver {
+    /// An account.
+    Account(AccountAddress),
+    /// A contract, together with the entrypoint the token contract calls on
+    /// the receiving contract to notify it of the transfer.
+    Contract(ContractAddress, OwnedEntrypointName),
+}
+
+/// A transfer made with [`Cis2Client::transfer`].
+#[derive(Debug, Clone)]
+pub struct Cis2Transfer {
+    /// The token to transfer.
+    pub token_id: TokenId,
+    /// The amount of tokens to transfer.
+    pub amount:   TokenAmount,
+    /// The owner of the tokens.
+    pub from:     Address,
+    /// The receiver of the tokens.
+    pub to:       Cis2Receiver,
+    /// Additional data passed on to the receiving contract, if any.
+    pub data:     Vec<u8>,
+}
+
+/// An error that occurred in a [`Cis2Client`] call.
+#[derive(Debug, Error)]
+pub enum Cis2Error {
+    /// The parameter for the call would be too large.
+    #[error("{0}")]
+    ParameterTooLarge(#[from] ParameterTooLarge),
+    /// The contract does not exist, so its entrypoints cannot be named.
+    #[error("{0}")]
+    ContractDoesNotExist(#[from] ContractDoesNotExist),
+    /// The contract call failed, for example because the contract rejected.
+    #[error("The CIS-2 call failed: {0}")]
+    Invoke(#[from] ContractInvokeError),
+    /// The contract returned a value which is not a valid response to the
+    /// query.
+    #[error("The contract returned an invalid CIS-2 response.")]
+    InvalidResponse,
+}
+
+/// A client for calling the CIS-2 entrypoints of contracts on a [`Chain`].
+///
+/// All the calls are made by the `invoker` account, which is also used as
+/// the sender.
+///
+/// # Example
+///
+/// ```no_run
+/// # use concordium_smart_contract_testing::*;
+/// # fn example(chain: &mut Chain, contract: ContractAddress) -> Result<(), Cis2Error> {
+/// let owner = AccountAddress([0; 32]);
+/// let token_id = TokenId::new_unchecked(Vec::new());
+/// let balance =
+///     Cis2Client::new(chain, owner).balance_of(contract, &token_id, Address::Account(owner))?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Cis2Client<'a> {
+    chain:   &'a mut Chain,
+    invoker: AccountAddress,
+    energy:  Energy,
+}
+
+impl<'a> Cis2Client<'a> {
+    /// Create a client which makes calls from `invoker`, reserving 100000
+    /// energy for each call.
+    pub fn new(chain: &'a mut Chain, invoker: AccountAddress) -> Self {
+        Self {
+            chain,
+            invoker,
+            energy: DEFAULT_ENERGY,
+        }
+    }
+
+    /// Set the energy reserved for each call.
+    pub fn with_energy(mut self, energy: Energy) -> Self {
+        self.energy = energy;
+        self
+    }
+
+    /// Query the balance of `address` for the token `token_id` with the
+    /// `balanceOf` entrypoint.
+    ///
+    /// The query is made with [`Chain::contract_invoke`], so it does not
+    /// change the chain.
+    pub fn balance_of(
+        &self,
+        contract: ContractAddress,
+        token_id: &TokenId,
+        address: Address,
+    ) -> Result<TokenAmount, Cis2Error> {
+        let parameter =
+            ParameterBuilder::new().append(&1u16)?.append(token_id)?.append(&address)?;
+        let success = self.invoke(contract, "balanceOf", parameter.build())?;
+        parse_single_response(&success.return_value)
+    }
+
+    /// Query whether `address` is an operator of `owner` with the
+    /// `operatorOf` entrypoint.
+    ///
+    /// The query is made with [`Chain::contract_invoke`], so it does not
+    /// change the chain.
+    pub fn operator_of(
+        &self,
+        contract: ContractAddress,
+        owner: Address,
+        address: Address,
+    ) -> Result<bool, Cis2Error> {
+        let parameter = ParameterBuilder::new().append(&1u16)?.append(&owner)?.append(&address)?;
+        let success = self.invoke(contract, "operatorOf", parameter.build())?;
+        parse_single_response(&success.return_value)
+    }
+
+    /// Make a single transfer with the `transfer` entrypoint.
+    ///
+    /// The transfer is made with [`Chain::contract_update`], so the changes
+    /// are saved if it succeeds.
+    pub fn transfer(
+        &mut self,
+        contract: ContractAddress,
+        transfer: Cis2Transfer,
+    ) -> Result<ContractInvokeSuccess, Cis2Error> {
+        let parameter = transfer_parameter(&transfer)?;
+        let payload = self.payload(contract, "transfer", parameter)?;
+        let success = self.chain.contract_update(
+            Signer::with_one_key(),
+            self.invoker,
+            Address::Account(self.invoker),
+            self.energy,
+            payload,
+        )?;
+        Ok(success)
+    }
+
+    /// Invoke an entrypoint of `contract` without saving the changes.
+    fn invoke(
+        &self,
+        contract: ContractAddress,
+        entrypoint: &str,
+        parameter: OwnedParameter,
+    ) -> Result<ContractInvokeSuccess, Cis2Error> {
+        let payload = self.payload(contract, entrypoint, parameter)?;
+        let success = self.chain.contract_invoke(
+            self.invoker,
+            Address::Account(self.invoker),
+            self.energy,
+            payload,
+        )?;
+        Ok(success)
+    }
+
+    /// The payload for calling an entrypoint of `contract`.
+    ///
+    /// Returns an error if the contract does not exist, since the receive
+    /// name includes the name of the contract.
+    fn payload(
+        &self,
+        contract: ContractAddress,
+        entrypoint: &str,
+        parameter: OwnedParameter,
+    ) -> Result<UpdateContractPayload, ContractDoesNotExist> {
+        let instance = self.chain.get_contract(contract).ok_or(ContractDoesNotExist {
+            address: contract,
+        })?;
+        let contract_name = instance.contract_name.as_contract_name().contract_name();
+        Ok(UpdateContractPayload {
+            amount:       Amount::zero(),
+            address:      contract,
+            receive_name: OwnedReceiveName::new_unchecked(format!("{contract_name}.{entrypoint}")),
+            message:      parameter,
+        })
+    }
+}
+
+/// Serialize the parameter for a `transfer` call with a single transfer.
+fn transfer_parameter(transfer: &Cis2Transfer) -> Result<OwnedParameter, ParameterTooLarge> {
+    let mut parameter = ParameterBuilder::new()
+        .append(&1u16)?
+        .append(&transfer.token_id)?
+        .append(&transfer.amount)?
+        .append(&transfer.from)?;
+    parameter = match &transfer.to {
+        Cis2Receiver::Account(address) => parameter.append(&0u8)?.append(address)?,
+        Cis2Receiver::Contract(address, entrypoint) => {
+            parameter.append(&1u8)?.append(address)?.append(entrypoint)?
+        }
+    };
+    // The additional data is prefixed by its length as two bytes. Longer data
+    // cannot fit in a parameter either.
+    let data_len = u16::try_from(transfer.data.len()).map_err(|_| ParameterTooLarge {
+        size:     parameter.len() + 2 + transfer.data.len(),
+        max_size: MAX_PARAMETER_LEN,
+    })?;
+    Ok(parameter.append(&data_len)?.append_bytes(&transfer.data)?.build())
+}
+
+/// Parse a response to a query with a single query, which is a list of one
+/// result prefixed by its length as two bytes.
+fn parse_single_response<T: Deserial>(return_value: &[u8]) -> Result<T, Cis2Error> {
+    let mut cursor = Cursor::new(return_value);
+    let len = u16::deserial(&mut cursor).map_err(|_| Cis2Error::InvalidResponse)?;
+    if len != 1 {
+        return Err(Cis2Error::InvalidResponse);
+    }
+    let result = T::deserial(&mut cursor).map_err(|_| Cis2Error::InvalidResponse)?;
+    if cursor.offset != return_value.len() {
+        return Err(Cis2Error::InvalidResponse);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+
+    /// Test that a transfer of a token with an empty ID is serialized as
+    /// described in the standard.
+    #[test]
+    fn test_transfer_parameter() {
+        let transfer = Cis2Transfer {
+            token_id: TokenId::new_unchecked(Vec::new()),
+            amount:   TokenAmount::from(BigUint::from(300u32)),
+            from:     Address::Account(AccountAddress([1; 32])),
+            to:       Cis2Receiver::Contract(
+                ContractAddress::new(2, 0),
+                OwnedEntrypointName::new_unchecked("hook".into()),
+            ),
+            data:     vec![7],
+        };
+        let mut expected = vec![1, 0, 0, 0xac, 0x02, 0];
+        expected.extend_from_slice(&[1; 32]);
+        expected.push(1);
+        expected.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend_from_slice(&[4, 0]);
+        expected.extend_from_slice(b"hook");
+        expected.extend_from_slice(&[1, 0, 7]);
+        let parameter = transfer_parameter(&transfer).expect("The parameter is small");
+        assert_eq!(parameter.as_ref(), &expected[..]);
+    }
+
+    /// Test that responses with exactly one result are accepted.
+    #[test]
+    fn test_parse_single_response() {
+        let amount: TokenAmount = parse_single_response(&[1, 0, 0xac, 0x02]).expect("Valid");
+        assert_eq!(amount, TokenAmount::from(BigUint::from(300u32)));
+        let amount: TokenAmount = parse_single_response(&[1, 0, 0]).expect("Valid");
+        assert_eq!(amount, TokenAmount::from(BigUint::from(0u32)));
+        assert!(matches!(parse_single_response::<bool>(&[1, 0, 1]), Ok(true)));
+
+        // No results, two results and trailing bytes.
+        assert!(parse_single_response::<bool>(&[0, 0]).is_err());
+        assert!(parse_single_response::<bool>(&[2, 0, 1, 1]).is_err());
+        assert!(parse_single_response::<bool>(&[1, 0, 1, 1]).is_err());
+    }
+}
@@ -1,6 +1,19 @@
 //! Various constants.
 
-use concordium_rust_sdk::base::base::Energy;
+use concordium_rust_sdk::base::{
+    base::{Energy, ProtocolVersion},
+    contracts_common::Amount,
+};
+
+/// The reject code that `concordium-std` uses for a
+/// `TransferError::AmountTooLarge`, i.e., when a contract rejects because it
+/// tried to transfer more CCD to an account than it had.
+pub(crate) const REJECT_TRANSFER_AMOUNT_TOO_LARGE: i32 = -2147483635;
+
+/// The reject code that `concordium-std` uses for a
+/// `CallContractError::AmountTooLarge`, i.e., when a contract rejects because
+/// it tried to send more CCD to another contract than it had.
+pub(crate) const REJECT_CALL_AMOUNT_TOO_LARGE: i32 = -2147483633;
 
 // Energy constants from Cost.hs in concordium-base.
 
@@ -80,3 +93,22 @@ pub(crate) const UPDATE_CONTRACT_INSTANCE_BASE_COST: Energy = Energy {
 /// module file. To load the module, use the
 /// [`module_load_output`](crate::module_load_output) function.
 pub const CONTRACT_MODULE_OUTPUT_PATH_ENV_VAR: &str = "CARGO_CONCORDIUM_TEST_MODULE_OUTPUT_PATH";
+
+/// The default maximum length of a key in the state of a smart contract
+/// instance, in bytes. Contracts that write a longer key to their state trap.
+/// The limit can be lowered with
+/// [`ChainBuilder::max_state_key_length`](crate::ChainBuilder::max_state_key_length).
+pub const MAX_STATE_KEY_LENGTH: usize = 65535;
+
+/// The default balance of the accounts created with
+/// [`Chain::create_funded_account`](crate::Chain::create_funded_account),
+/// which is one million CCD. The balance can be changed with
+/// [`ChainBuilder::default_account_balance`](crate::ChainBuilder::default_account_balance).
+pub const DEFAULT_ACCOUNT_BALANCE: Amount = Amount {
+    micro_ccd: 1_000_000_000_000,
+};
+
+/// The protocol version whose Wasm validation rules and cost model are used
+/// by default. It can be changed with
+/// [`ChainBuilder::protocol_version`](crate::ChainBuilder::protocol_version).
+pub const DEFAULT_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::P7;
@@ -0,0 +1,173 @@
+//! Support for fuzz testing smart contracts.
+//!
+//! This module is only available with the `arbitrary` feature enabled.
+use crate::{Chain, ContractInvokeError, ContractInvokeErrorKind, ContractInvokeSuccess, Signer};
+use arbitrary::{Arbitrary, Unstructured};
+use concordium_rust_sdk::{
+    base::{
+        base::Energy,
+        contracts_common::{
+            constants::MAX_PARAMETER_LEN, AccountAddress, Address, Amount, ContractAddress,
+            OwnedParameter, OwnedReceiveName,
+        },
+        transactions::UpdateContractPayload,
+    },
+    smart_contracts::engine::v1::{self, ReturnValue},
+};
+
+/// A parameter which can be generated with [`Arbitrary`].
+///
+/// The generated parameters never exceed the maximum parameter size allowed
+/// by the protocol, i.e., [`MAX_PARAMETER_LEN`] bytes. If more bytes are
+/// requested, the parameter is clamped to the maximum size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbitraryParameter(pub OwnedParameter);
+
+impl ArbitraryParameter {
+    /// Get the inner [`OwnedParameter`].
+    pub fn into_inner(self) -> OwnedParameter { self.0 }
+}
+
+impl From<ArbitraryParameter> for OwnedParameter {
+    fn from(value: ArbitraryParameter) -> Self { value.0 }
+}
+
+impl AsRef<[u8]> for ArbitraryParameter {
+    fn as_ref(&self) -> &[u8] { self.0.as_ref() }
+}
+
+impl<'a> Arbitrary<'a> for ArbitraryParameter {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.arbitrary_len::<u8>()?.min(MAX_PARAMETER_LEN);
+        let bytes = u.bytes(len)?;
+        Ok(Self(OwnedParameter::new_unchecked(bytes.to_vec())))
+    }
+
+    fn arbitrary_take_rest(u: Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut bytes = u.take_rest().to_vec();
+        bytes.truncate(MAX_PARAMETER_LEN);
+        Ok(Self(OwnedParameter::new_unchecked(bytes)))
+    }
+}
+
+/// The outcome of [`Chain::fuzz_update`].
+///
+/// All the ways an update can fail are represented as values, so that a
+/// fuzzer can inspect the outcome and continue.
+#[derive(Debug)]
+pub enum FuzzOutcome {
+    /// The update succeeded.
+    Success(ContractInvokeSuccess),
+    /// The contract rejected with the given code and return value.
+    Reject {
+        /// The reject code.
+        code:         i32,
+        /// The return value the contract produced when rejecting.
+        return_value: ReturnValue,
+    },
+    /// The contract trapped, for example by panicking.
+    Trap,
+    /// The update ran out of energy.
+    OutOfEnergy,
+    /// The update failed for another reason, for example because the
+    /// contract or entrypoint does not exist.
+    Failure(ContractInvokeError),
+}
+
+impl FuzzOutcome {
+    /// Whether the update succeeded.
+    pub fn is_success(&self) -> bool { matches!(self, Self::Success(_)) }
+
+    /// Whether the contract trapped.
+    pub fn is_trap(&self) -> bool { matches!(self, Self::Trap) }
+}
+
+impl From<Result<ContractInvokeSuccess, ContractInvokeError>> for FuzzOutcome {
+    fn from(result: Result<ContractInvokeSuccess, ContractInvokeError>) -> Self {
+        let error = match result {
+            Ok(success) => return Self::Success(success),
+            Err(error) => error,
+        };
+        match error.kind {
+            ContractInvokeErrorKind::ExecutionError {
+                failure_kind:
+                    v1::InvokeFailure::ContractReject {
+                        code,
+                        data,
+                    },
+            } => Self::Reject {
+                code,
+                return_value: data,
+            },
+            ContractInvokeErrorKind::ExecutionError {
+                failure_kind: v1::InvokeFailure::RuntimeError,
+            } => Self::Trap,
+            ContractInvokeErrorKind::OutOfEnergy {
+                ..
+            } => Self::OutOfEnergy,
+            _ => Self::Failure(error),
+        }
+    }
+}
+
+impl Chain {
+    /// Update a contract with a generated parameter and classify the outcome.
+    ///
+    /// This is a wrapper around [`Chain::contract_update`], which uses
+    /// `invoker` as both the invoker and the sender and signs with one key.
+    /// Traps and rejects are returned as a [`FuzzOutcome`] instead of an
+    /// error, which makes the method convenient to use in fuzz targets.
+    ///
+    /// As with [`Chain::contract_update`], the changes are only saved if the
+    /// update succeeds.
+    pub fn fuzz_update(
+        &mut self,
+        invoker: AccountAddress,
+        address: ContractAddress,
+        receive_name: OwnedReceiveName,
+        amount: Amount,
+        energy_reserved: Energy,
+        parameter: ArbitraryParameter,
+    ) -> FuzzOutcome {
+        self.contract_update(
+            Signer::with_one_key(),
+            invoker,
+            Address::Account(invoker),
+            energy_reserved,
+            UpdateContractPayload {
+                amount,
+                address,
+                receive_name,
+                message: parameter.into_inner(),
+            },
+        )
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the generated parameters are clamped to the maximum size.
+    #[test]
+    fn test_arbitrary_parameter_is_clamped() {
+        let data = vec![0xffu8; 3 * MAX_PARAMETER_LEN];
+        let mut u = Unstructured::new(&data);
+        let parameter = ArbitraryParameter::arbitrary(&mut u).expect("Generation succeeds");
+        assert!(parameter.as_ref().len() <= MAX_PARAMETER_LEN);
+
+        let parameter = ArbitraryParameter::arbitrary_take_rest(Unstructured::new(&data))
+            .expect("Generation succeeds");
+        assert_eq!(parameter.as_ref().len(), MAX_PARAMETER_LEN);
+    }
+
+    /// Test that small inputs produce parameters containing the input bytes.
+    #[test]
+    fn test_arbitrary_parameter_small() {
+        let data = [1u8, 2, 3];
+        let parameter = ArbitraryParameter::arbitrary_take_rest(Unstructured::new(&data))
+            .expect("Generation succeeds");
+        assert_eq!(parameter.as_ref(), &data);
+    }
+}
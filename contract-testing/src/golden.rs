@@ -0,0 +1,458 @@
+//! Golden testing of contract updates.
+//!
+//! A [`GoldenResult`] contains the parts of a [`ContractInvokeSuccess`] which
+//! describe the behavior of an update, such as the return value and the
+//! events logged. It can be saved as JSON with [`GoldenResult::to_json`],
+//! committed, and compared with the result of later runs with
+//! [`GoldenResult::assert_matches`].
+//!
+//! The golden result records the changes to the states of the updated
+//! contracts key by key, as in [`ContractInvokeSuccess::state_diffs`], so a
+//! change in how an update writes to the state is reported like any other
+//! change in its behavior.
+use crate::{impls::hex_encode, ContractInvokeSuccess, StateDiff, StateEntryChange};
+use concordium_rust_sdk::base::{
+    base::Energy,
+    contracts_common::{AccountAddress, Address, Amount, ContractAddress},
+    smart_contracts::{ContractEvent, ContractTraceElement},
+};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+/// The behavior of a successful contract update, see
+/// [`ContractInvokeSuccess::to_golden`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenResult {
+    /// The energy used.
+    pub energy_used:       Energy,
+    /// The transaction fee.
+    pub transaction_fee:   Amount,
+    /// The return value of the contract called.
+    pub return_value:      Vec<u8>,
+    /// The events logged, paired with the contract which logged them, in the
+    /// order of [`ContractInvokeSuccess::events`].
+    pub events:            Vec<(ContractAddress, ContractEvent)>,
+    /// The contracts updated, in the order their entrypoints finished.
+    pub updated_contracts: Vec<ContractAddress>,
+    /// The transfers of CCD made by contracts, see
+    /// [`ContractInvokeSuccess::transfers`].
+    pub transfers:         Vec<(ContractAddress, Address, Amount)>,
+    /// The changes to the states of the updated contracts, see
+    /// [`ContractInvokeSuccess::state_diffs`].
+    pub state_diffs:       Vec<(ContractAddress, StateDiff)>,
+    /// The new balance of the contract called.
+    pub new_balance:       Amount,
+}
+
+/// The parts of a [`GoldenResult`] to ignore in
+/// [`GoldenResult::assert_matches`].
+///
+/// The return value, events, updated contracts, transfers and state diffs are
+/// always compared, since they describe the behavior of the contracts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GoldenMask {
+    /// Ignore the energy used and the transaction fee, e.g., so that the
+    /// golden result does not have to be updated when the cost of the
+    /// contract changes.
+    pub energy:      bool,
+    /// Ignore the new balance of the contract called.
+    pub new_balance: bool,
+}
+
+impl GoldenMask {
+    /// Ignore the energy used and the transaction fee.
+    pub const ENERGY: Self = Self {
+        energy:      true,
+        new_balance: false,
+    };
+    /// Compare all the parts.
+    pub const NONE: Self = Self {
+        energy:      false,
+        new_balance: false,
+    };
+}
+
+/// An error that occurs while reading a [`GoldenResult`] with
+/// [`GoldenResult::from_json`].
+#[derive(Debug, Error)]
+pub enum GoldenError {
+    /// The input is not valid JSON.
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// A field of the golden result is missing or invalid.
+    #[error("Invalid golden result: could not read {0}")]
+    Malformed(&'static str),
+}
+
+impl ContractInvokeSuccess {
+    /// Extract the behavior of the update as a [`GoldenResult`], which can be
+    /// saved and compared with the results of later runs.
+    pub fn to_golden(&self) -> GoldenResult {
+        GoldenResult {
+            energy_used:       self.energy_used,
+            transaction_fee:   self.transaction_fee,
+            return_value:      self.return_value.clone(),
+            events:            self
+                .events()
+                .flat_map(|(address, events)| {
+                    events.iter().map(move |event| (address, event.clone()))
+                })
+                .collect(),
+            updated_contracts: self
+                .effective_trace_elements()
+                .filter_map(|cte| match cte {
+                    ContractTraceElement::Updated {
+                        data,
+                    } => Some(data.address),
+                    _ => None,
+                })
+                .collect(),
+            transfers:         self.transfers(),
+            state_diffs:       self.state_diffs.clone(),
+            new_balance:       self.new_balance,
+        }
+    }
+}
+
+impl GoldenResult {
+    /// Assert that `actual` matches this golden result, except for the parts
+    /// ignored by `ignore`.
+    ///
+    /// Panics with a description of all the parts that differ.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use concordium_smart_contract_testing::*;
+    /// # fn example(success: ContractInvokeSuccess) {
+    /// let json = std::fs::read_to_string("update.golden.json").expect("The file exists");
+    /// let golden = GoldenResult::from_json(&json).expect("The golden file is valid");
+    /// golden.assert_matches(&success.to_golden(), GoldenMask::ENERGY);
+    /// # }
+    /// ```
+    pub fn assert_matches(&self, actual: &GoldenResult, ignore: GoldenMask) {
+        let mut differences = Vec::new();
+        let mut compare = |name: &str, expected: String, actual: String| {
+            if expected != actual {
+                differences.push(format!("  {name}: expected {expected}, got {actual}"));
+            }
+        };
+        if !ignore.energy {
+            compare(
+                "energy_used",
+                self.energy_used.energy.to_string(),
+                actual.energy_used.energy.to_string(),
+            );
+            compare(
+                "transaction_fee",
+                self.transaction_fee.to_string(),
+                actual.transaction_fee.to_string(),
+            );
+        }
+        compare("return_value", hex_encode(&self.return_value), hex_encode(&actual.return_value));
+        compare("events", format!("{:?}", self.events), format!("{:?}", actual.events));
+        compare(
+            "updated_contracts",
+            format!("{:?}", self.updated_contracts),
+            format!("{:?}", actual.updated_contracts),
+        );
+        compare("transfers", format!("{:?}", self.transfers), format!("{:?}", actual.transfers));
+        compare(
+            "state_diffs",
+            state_diffs_to_json(&self.state_diffs).to_string(),
+            state_diffs_to_json(&actual.state_diffs).to_string(),
+        );
+        if !ignore.new_balance {
+            compare("new_balance", self.new_balance.to_string(), actual.new_balance.to_string());
+        }
+        if !differences.is_empty() {
+            panic!("The result does not match the golden result:\n{}", differences.join("\n"));
+        }
+    }
+
+    /// Serialize the golden result as pretty-printed JSON, with byte strings
+    /// encoded as hex and amounts in microCCD.
+    pub fn to_json(&self) -> String {
+        let events: Vec<_> = self
+            .events
+            .iter()
+            .map(|(address, event)| {
+                json!({
+                    "address": contract_to_json(*address),
+                    "event": hex_encode(event.as_ref()),
+                })
+            })
+            .collect();
+        let updated_contracts: Vec<_> =
+            self.updated_contracts.iter().map(|address| contract_to_json(*address)).collect();
+        let transfers: Vec<_> = self
+            .transfers
+            .iter()
+            .map(|(from, to, amount)| {
+                let to = match to {
+                    Address::Account(address) => json!({ "account": address.to_string() }),
+                    Address::Contract(address) => json!({ "contract": contract_to_json(*address) }),
+                };
+                json!({ "from": contract_to_json(*from), "to": to, "amount": amount.micro_ccd })
+            })
+            .collect();
+        let value = json!({
+            "energy_used": self.energy_used.energy,
+            "transaction_fee": self.transaction_fee.micro_ccd,
+            "return_value": hex_encode(&self.return_value),
+            "events": events,
+            "updated_contracts": updated_contracts,
+            "transfers": transfers,
+            "state_diffs": state_diffs_to_json(&self.state_diffs),
+            "new_balance": self.new_balance.micro_ccd,
+        });
+        serde_json::to_string_pretty(&value).expect("Serializing JSON values succeeds.")
+    }
+
+    /// Read a golden result written by [`GoldenResult::to_json`].
+    pub fn from_json(json: &str) -> Result<Self, GoldenError> {
+        let value: Value = serde_json::from_str(json)?;
+        let u64_field = |value: &Value, name: &'static str| {
+            value.get(name).and_then(Value::as_u64).ok_or(GoldenError::Malformed(name))
+        };
+        let array_field = |value: &Value, name: &'static str| {
+            value.get(name).and_then(Value::as_array).cloned().ok_or(GoldenError::Malformed(name))
+        };
+        let events = array_field(&value, "events")?
+            .iter()
+            .map(|event| {
+                let address = contract_from_json(event.get("address"))
+                    .ok_or(GoldenError::Malformed("events"))?;
+                let bytes = event
+                    .get("event")
+                    .and_then(Value::as_str)
+                    .and_then(hex_decode)
+                    .ok_or(GoldenError::Malformed("events"))?;
+                Ok((address, ContractEvent::from(bytes)))
+            })
+            .collect::<Result<_, GoldenError>>()?;
+        let updated_contracts = array_field(&value, "updated_contracts")?
+            .iter()
+            .map(|address| {
+                contract_from_json(Some(address)).ok_or(GoldenError::Malformed("updated_contracts"))
+            })
+            .collect::<Result<_, GoldenError>>()?;
+        let transfers = array_field(&value, "transfers")?
+            .iter()
+            .map(|transfer| {
+                let from = contract_from_json(transfer.get("from"));
+                let to = transfer.get("to").and_then(|to| {
+                    if let Some(account) = to.get("account") {
+                        let address: AccountAddress = account.as_str()?.parse().ok()?;
+                        Some(Address::Account(address))
+                    } else {
+                        contract_from_json(to.get("contract")).map(Address::Contract)
+                    }
+                });
+                let amount = transfer.get("amount").and_then(Value::as_u64);
+                match (from, to, amount) {
+                    (Some(from), Some(to), Some(amount)) => {
+                        Ok((from, to, Amount::from_micro_ccd(amount)))
+                    }
+                    _ => Err(GoldenError::Malformed("transfers")),
+                }
+            })
+            .collect::<Result<_, GoldenError>>()?;
+        Ok(Self {
+            energy_used: Energy::from(u64_field(&value, "energy_used")?),
+            transaction_fee: Amount::from_micro_ccd(u64_field(&value, "transaction_fee")?),
+            return_value: value
+                .get("return_value")
+                .and_then(Value::as_str)
+                .and_then(hex_decode)
+                .ok_or(GoldenError::Malformed("return_value"))?,
+            events,
+            updated_contracts,
+            transfers,
+            state_diffs: state_diffs_from_json(value.get("state_diffs"))
+                .ok_or(GoldenError::Malformed("state_diffs"))?,
+            new_balance: Amount::from_micro_ccd(u64_field(&value, "new_balance")?),
+        })
+    }
+}
+
+/// Represent a contract address as a JSON object with its index and
+/// subindex.
+fn contract_to_json(address: ContractAddress) -> Value {
+    json!({ "index": address.index, "subindex": address.subindex })
+}
+
+/// Read a contract address written by [`contract_to_json`].
+fn contract_from_json(value: Option<&Value>) -> Option<ContractAddress> {
+    let value = value?;
+    Some(ContractAddress::new(value.get("index")?.as_u64()?, value.get("subindex")?.as_u64()?))
+}
+
+/// Represent the state diffs of contracts as a JSON array with an object for
+/// each contract, in which keys and values are encoded as hex.
+fn state_diffs_to_json(state_diffs: &[(ContractAddress, StateDiff)]) -> Value {
+    let entries = |entries: &[(Vec<u8>, Vec<u8>)]| -> Vec<Value> {
+        entries
+            .iter()
+            .map(|(key, value)| json!({ "key": hex_encode(key), "value": hex_encode(value) }))
+            .collect()
+    };
+    state_diffs
+        .iter()
+        .map(|(address, diff)| {
+            let changed: Vec<_> = diff
+                .changed
+                .iter()
+                .map(|change| {
+                    json!({
+                        "key": hex_encode(&change.key),
+                        "before": hex_encode(&change.before),
+                        "after": hex_encode(&change.after),
+                    })
+                })
+                .collect();
+            json!({
+                "address": contract_to_json(*address),
+                "added": entries(&diff.added),
+                "removed": entries(&diff.removed),
+                "changed": changed,
+            })
+        })
+        .collect()
+}
+
+/// Read the state diffs written by [`state_diffs_to_json`].
+fn state_diffs_from_json(value: Option<&Value>) -> Option<Vec<(ContractAddress, StateDiff)>> {
+    let hex_field = |value: &Value, name: &str| value.get(name)?.as_str().and_then(hex_decode);
+    let entries = |value: &Value, name: &str| -> Option<Vec<(Vec<u8>, Vec<u8>)>> {
+        value
+            .get(name)?
+            .as_array()?
+            .iter()
+            .map(|entry| Some((hex_field(entry, "key")?, hex_field(entry, "value")?)))
+            .collect()
+    };
+    value?
+        .as_array()?
+        .iter()
+        .map(|diff| {
+            let changed = diff
+                .get("changed")?
+                .as_array()?
+                .iter()
+                .map(|change| {
+                    Some(StateEntryChange {
+                        key:    hex_field(change, "key")?,
+                        before: hex_field(change, "before")?,
+                        after:  hex_field(change, "after")?,
+                    })
+                })
+                .collect::<Option<_>>()?;
+            let state = StateDiff {
+                added: entries(diff, "added")?,
+                removed: entries(diff, "removed")?,
+                changed,
+            };
+            Some((contract_from_json(diff.get("address"))?, state))
+        })
+        .collect()
+}
+
+/// Decode a string of hex digits written by [`hex_encode`].
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A golden result with all kinds of values.
+    fn golden() -> GoldenResult {
+        GoldenResult {
+            energy_used:       Energy::from(1234),
+            transaction_fee:   Amount::from_micro_ccd(5678),
+            return_value:      vec![0, 1, 0xff],
+            events:            vec![(ContractAddress::new(1, 0), ContractEvent::from(vec![7, 8]))],
+            updated_contracts: vec![ContractAddress::new(2, 0), ContractAddress::new(1, 0)],
+            transfers:         vec![
+                (
+                    ContractAddress::new(1, 0),
+                    Address::Account(AccountAddress([3; 32])),
+                    Amount::from_ccd(1),
+                ),
+                (
+                    ContractAddress::new(1, 0),
+                    Address::Contract(ContractAddress::new(2, 0)),
+                    Amount::from_micro_ccd(5),
+                ),
+            ],
+            state_diffs:       vec![(ContractAddress::new(1, 0), StateDiff {
+                added:   vec![(vec![1], vec![2, 3])],
+                removed: vec![(vec![4], Vec::new())],
+                changed: vec![StateEntryChange {
+                    key:    Vec::new(),
+                    before: vec![5],
+                    after:  vec![6],
+                }],
+            })],
+            new_balance:       Amount::from_ccd(10),
+        }
+    }
+
+    /// Test that a golden result is read back unchanged from its JSON.
+    #[test]
+    fn test_json_round_trip() {
+        let golden = golden();
+        let read = GoldenResult::from_json(&golden.to_json()).expect("The JSON is valid");
+        assert_eq!(read, golden);
+        assert!(matches!(GoldenResult::from_json("{}"), Err(GoldenError::Malformed(_))));
+        assert!(matches!(GoldenResult::from_json("{"), Err(GoldenError::Json(_))));
+    }
+
+    /// Test that a change to the value written to the state is reported, even
+    /// if the same keys are written.
+    #[test]
+    fn test_assert_matches_state_diffs() {
+        let expected = golden();
+        let mut actual = golden();
+        actual.state_diffs[0].1.changed[0].after = vec![7];
+        let message =
+            std::panic::catch_unwind(|| expected.assert_matches(&actual, GoldenMask::ENERGY))
+                .expect_err("The state diffs differ")
+                .downcast::<String>()
+                .expect("The panic message is a string");
+        assert!(message.contains("state_diffs"));
+        assert!(!message.contains("return_value"));
+    }
+
+    /// Test that the mask only ignores the energy and the balance.
+    #[test]
+    fn test_assert_matches_mask() {
+        let expected = golden();
+        let actual = GoldenResult {
+            energy_used: Energy::from(1),
+            transaction_fee: Amount::zero(),
+            ..golden()
+        };
+        expected.assert_matches(&actual, GoldenMask::ENERGY);
+        let result =
+            std::panic::catch_unwind(|| expected.assert_matches(&actual, GoldenMask::NONE));
+        assert!(result.is_err());
+
+        let actual = GoldenResult {
+            return_value: Vec::new(),
+            ..golden()
+        };
+        let result = std::panic::catch_unwind(|| {
+            expected.assert_matches(&actual, GoldenMask {
+                energy:      true,
+                new_balance: true,
+            })
+        });
+        assert!(result.is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     constants,
     invocation::{ChangeSet, EntrypointInvocationHandler, TestConfigurationError},
+    replay::Transaction,
     types::*,
     CONTRACT_MODULE_OUTPUT_PATH_ENV_VAR,
 };
@@ -8,14 +9,16 @@ use anyhow::anyhow;
 use concordium_rust_sdk::{
     self as sdk, base,
     base::{
-        base::{AccountThreshold, Energy, InsufficientEnergy},
+        base::{AccountThreshold, Energy, InsufficientEnergy, ProtocolVersion},
         constants::MAX_WASM_MODULE_SIZE,
         contracts_common::{
-            self, AccountAddress, AccountBalance, Address, Amount, ChainMetadata, ContractAddress,
-            Deserial, Duration, ExchangeRate, ExchangeRates, ModuleReference, OwnedPolicy,
-            ParseResult, SlotTime, Timestamp,
+            self, schema, AccountAddress, AccountBalance, Address, Amount, ChainMetadata,
+            ContractAddress, Deserial, Duration, EntrypointName, ExchangeRate, ExchangeRates,
+            ModuleReference, OwnedParameter, OwnedPolicy, OwnedReceiveName, ParseResult,
+            ReceiveName, SlotTime, Timestamp,
         },
         hashes::BlockHash,
+        id::types::AccountKeys,
         smart_contracts::{ContractEvent, ModuleSource, WasmModule, WasmVersion},
         transactions::{
             self, cost, AccountAccessStructure, InitContractPayload, UpdateContractPayload,
@@ -23,9 +26,11 @@ use concordium_rust_sdk::{
     },
     smart_contracts::engine::{
         v0,
-        v1::{self, DebugTracker, InvalidReturnCodeError, InvokeResponse},
+        v1::{
+            self, CommonFunc, DebugTracker, HostFunctionV1, InvalidReturnCodeError, InvokeResponse,
+        },
         wasm,
-        wasm::validate::ValidationConfig,
+        wasm::validate::{ValidateImportExport, ValidationConfig},
         DebugInfo, InterpreterEnergy,
     },
     v2::Endpoint,
@@ -33,9 +38,10 @@ use concordium_rust_sdk::{
 use num_bigint::BigUint;
 use num_integer::Integer;
 use sdk::{
-    smart_contracts::engine::wasm::CostConfigurationV1,
+    smart_contracts::engine::wasm::{CostConfigurationV0, CostConfigurationV1},
     types::smart_contracts::InvokeContractResult,
 };
+use sha2::{Digest, Sha256};
 use std::{
     collections::{BTreeMap, BTreeSet},
     env,
@@ -51,6 +57,35 @@ const EXTERNAL_NODE_QUERY_TIMEOUT: tokio::time::Duration = tokio::time::Duration
 /// The timeout duration set for connecting to an external node.
 const EXTERNAL_NODE_CONNECT_TIMEOUT: tokio::time::Duration = tokio::time::Duration::from_secs(3);
 
+/// The `type` of an account export from the browser wallet.
+const WALLET_EXPORT_TYPE: &str = "concordium-browser-wallet-account";
+
+/// The version of the wallet export format supported by
+/// [`WalletExport::from_json`].
+const WALLET_EXPORT_VERSION: u64 = 0;
+
+/// The result of [`Chain::contract_invocation_worker`] when the invocation
+/// could be run.
+struct InvocationWorkerSuccess {
+    /// The response of the invoked entrypoint.
+    result:              InvokeResponse,
+    /// The changes which should be persisted if the invocation succeeded.
+    changeset:           ChangeSet,
+    /// The trace elements produced by the invocation.
+    trace_elements:      Vec<DebugTraceElement>,
+    /// The energy charged for loading modules.
+    module_load_energy:  Energy,
+    /// The largest amount of energy used in a single uninterrupted execution
+    /// of contract code.
+    peak_segment_energy: Energy,
+    /// The modification index checks made when contracts resumed.
+    modification_checks: Vec<ModificationCheck>,
+    /// The invocations made, with the energy used by each of them.
+    call_frames:         Vec<CallFrame>,
+    /// The state entries written by the invocation.
+    state_writes:        StateWrites,
+}
+
 impl Default for Chain {
     fn default() -> Self { Self::new() }
 }
@@ -139,6 +174,9 @@ impl ChainBuilder {
             euro_per_energy_from_external: false,
             block_time: None,
             block_time_from_external: false,
+            max_state_key_length: None,
+            default_account_balance: None,
+            protocol_version: None,
         }
     }
 
@@ -311,6 +349,76 @@ impl ChainBuilder {
         self
     }
 
+    /// Configure the maximum length of a key in the state of a contract
+    /// instance.
+    ///
+    /// An update or initialization in which a contract creates a longer key in
+    /// its state fails with [`ContractInvokeErrorKind::StateKeyTooLarge`] or
+    /// [`ContractInitErrorKind::StateKeyTooLarge`], and none of its changes
+    /// are persisted. Since the keys are created by the engine, they are
+    /// checked when the contract returns or is interrupted, so the energy used
+    /// until then is charged.
+    ///
+    /// Defaults to [`MAX_STATE_KEY_LENGTH`](crate::MAX_STATE_KEY_LENGTH).
+    ///
+    /// # Example
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let chain = ChainBuilder::new().max_state_key_length(32).build().unwrap();
+    /// assert_eq!(chain.max_state_key_length(), 32);
+    /// ```
+    pub fn max_state_key_length(mut self, max_length: usize) -> Self {
+        self.max_state_key_length = Some(max_length);
+        self
+    }
+
+    /// Configure the balance of the accounts created with
+    /// [`Chain::create_funded_account`].
+    ///
+    /// Defaults to [`DEFAULT_ACCOUNT_BALANCE`](crate::DEFAULT_ACCOUNT_BALANCE).
+    ///
+    /// # Example
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain =
+    ///     ChainBuilder::new().default_account_balance(Amount::from_ccd(10)).build().unwrap();
+    /// let alice = chain.create_funded_account("alice");
+    /// assert_eq!(chain.account_balance_available(alice), Some(Amount::from_ccd(10)));
+    /// ```
+    pub fn default_account_balance(mut self, balance: Amount) -> Self {
+        self.default_account_balance = Some(balance);
+        self
+    }
+
+    /// Configure the protocol version whose rules are used when deploying
+    /// modules.
+    ///
+    /// The protocol version determines which Wasm features a module may use,
+    /// and the cost model used for metering the execution of its functions.
+    /// A module that is not valid in the configured protocol version is
+    /// rejected by [`Chain::module_deploy_v1`] with a
+    /// [`ModuleDeployErrorKind::InvalidModule`] error, which names the
+    /// protocol version. Smart contract upgrades are only supported from
+    /// protocol version 5, and version 1 modules only from protocol
+    /// version 4.
+    ///
+    /// The ratio between [`Energy`] and [`InterpreterEnergy`] is the same in
+    /// all protocol versions, see [`energy_to_interpreter_energy`].
+    ///
+    /// Defaults to
+    /// [`DEFAULT_PROTOCOL_VERSION`](crate::DEFAULT_PROTOCOL_VERSION).
+    ///
+    /// # Example
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let chain = ChainBuilder::new().protocol_version(ProtocolVersion::P6).build().unwrap();
+    /// assert_eq!(chain.protocol_version(), ProtocolVersion::P6);
+    /// ```
+    pub fn protocol_version(mut self, protocol_version: ProtocolVersion) -> Self {
+        self.protocol_version = Some(protocol_version);
+        self
+    }
+
     /// Build the [`Chain`] with the configured options.
     ///
     /// # Example
@@ -384,6 +492,18 @@ impl ChainBuilder {
             chain.parameters.block_time = block_time;
         }
 
+        if let Some(max_state_key_length) = self.max_state_key_length {
+            chain.max_state_key_length = max_state_key_length;
+        }
+
+        if let Some(default_account_balance) = self.default_account_balance {
+            chain.default_account_balance = default_account_balance;
+        }
+
+        if let Some(protocol_version) = self.protocol_version {
+            chain.protocol_version = protocol_version;
+        }
+
         Ok(chain)
     }
 }
@@ -434,6 +554,27 @@ impl Chain {
             contracts:                BTreeMap::new(),
             next_contract_index:      0,
             external_node_connection: None,
+            max_state_key_length:     constants::MAX_STATE_KEY_LENGTH,
+            max_call_depth:           None,
+            max_state_writes:         None,
+            strict_logs:              None,
+            default_account_balance:  constants::DEFAULT_ACCOUNT_BALANCE,
+            mocked_account_balances:  BTreeMap::new(),
+            account_creation_times:   BTreeMap::new(),
+            protocol_version:         constants::DEFAULT_PROTOCOL_VERSION,
+            debug_output:             false,
+            scheduled_exchange_rates: BTreeMap::new(),
+            checkpoints:              BTreeMap::new(),
+            block_energy_limit:       None,
+            block_energy_used:        Energy::from(0),
+            block_height:             0,
+            module_tags:              BTreeMap::new(),
+            event_log:                Vec::new(),
+            transaction_count:        0,
+            fees_collected:           Amount::zero(),
+            hooks:                    ChainHooks::default(),
+            reentrancy_log:           None,
+            recording:                None,
         })
     }
 
@@ -450,6 +591,17 @@ impl Chain {
         }
     }
 
+    /// Create a new [`Chain`](Self) at the block time `block_time`, which is a
+    /// shorthand for [`Chain::new_with_time`].
+    ///
+    /// # Example
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let chain = Chain::new_at(Timestamp::from_timestamp_millis(1000));
+    /// assert_eq!(chain.block_time(), Timestamp::from_timestamp_millis(1000));
+    /// ```
+    pub fn new_at(block_time: Timestamp) -> Self { Self::new_with_time(block_time) }
+
     /// Create a new [`Chain`](Self) where
     ///  - `block_time` defaults to `0`,
     ///  - `micro_ccd_per_euro` defaults to `50000 / 1`
@@ -474,6 +626,22 @@ impl Chain {
         self.parameters.calculate_energy_cost(energy)
     }
 
+    /// Estimate the fee of a transaction that uses `energy`, with the current
+    /// exchange rates.
+    ///
+    /// The fee is rounded up to the nearest microCCD, as in the node, so it
+    /// matches the `transaction_fee` charged for a transaction that uses
+    /// exactly `energy`, as long as the exchange rates do not change in the
+    /// meantime.
+    ///
+    /// # Example
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let chain = Chain::new();
+    /// assert_eq!(chain.estimate_fee(Energy::from(0)), Amount::zero());
+    /// ```
+    pub fn estimate_fee(&self, energy: Energy) -> Amount { self.calculate_energy_cost(energy) }
+
     /// Get the state of the contract if it exists in the [`Chain`](Self).
     pub fn get_contract(&self, address: ContractAddress) -> Option<&Contract> {
         self.contracts.get(&address)
@@ -496,6 +664,10 @@ impl Chain {
     /// The `WasmModule` can be loaded from disk with either
     /// [`module_load_v1`] or [`module_load_v1_raw`].
     ///
+    /// Modules that use the debug host functions, e.g., via
+    /// `concordium_dbg!`, are only allowed if debug output has been enabled
+    /// with [`Chain::enable_debug_output`].
+    ///
     /// Parameters:
     ///  - `signer`: the signer with a number of keys, which affects the cost.
     ///  - `sender`: the sender account.
@@ -506,7 +678,236 @@ impl Chain {
         sender: AccountAddress,
         wasm_module: WasmModule,
     ) -> Result<ModuleDeploySuccess, ModuleDeployError> {
-        self.module_deploy_v1_debug(signer, sender, wasm_module, false)
+        self.module_deploy_v1_debug(signer, sender, wasm_module, self.debug_output)
+    }
+
+    /// Deploy a smart contract module from its serialized bytes.
+    ///
+    /// The bytes should be the module as it is output from `cargo concordium
+    /// build`, i.e. **including** the prefix of 4 version bytes and 4 module
+    /// length bytes. See [`module_load_v1_from_bytes`] for details.
+    ///
+    /// Other than the parsing of the bytes, this behaves exactly like
+    /// [`module_deploy_v1`](Self::module_deploy_v1), and deploying the same
+    /// module from bytes or from a file results in the same module reference.
+    /// If the bytes cannot be parsed, no fee is charged.
+    pub fn module_deploy_v1_from_bytes(
+        &mut self,
+        signer: Signer,
+        sender: AccountAddress,
+        module_bytes: &[u8],
+    ) -> Result<ModuleDeploySuccess, ModuleDeployError> {
+        let wasm_module =
+            module_load_v1_from_bytes(module_bytes).map_err(|kind| ModuleDeployError {
+                kind:            kind.into(),
+                energy_used:     0.into(),
+                transaction_fee: Amount::zero(),
+            })?;
+        self.module_deploy_v1(signer, sender, wasm_module)
+    }
+
+    /// Validate a raw wasm module, i.e., one **without** the prefix of 4
+    /// version bytes and 4 module length bytes, as a V1 smart contract module.
+    ///
+    /// The module goes through the same validation as when it is deployed
+    /// with [`module_deploy_v1`](Self::module_deploy_v1), using the rules of
+    /// the [protocol version](Self::protocol_version) of the chain, but it is
+    /// not deployed and no energy is charged. On success, a report of the
+    /// module is returned.
+    ///
+    /// Modules which use the debug host functions are invalid, as when they
+    /// are deployed without debugging enabled.
+    pub fn validate_module_bytes(
+        &self,
+        module_bytes: &[u8],
+    ) -> Result<ModuleValidationReport, ModuleInvalidError> {
+        let artifact = instantiate_module(self.protocol_version, false, module_bytes)?;
+        let wasm_module = WasmModule {
+            version: WasmVersion::V1,
+            source:  ModuleSource::from(module_bytes.to_vec()),
+        };
+        let exports = artifact
+            .artifact
+            .export
+            .keys()
+            .map(|name| std::borrow::Borrow::<str>::borrow(name).to_string())
+            .collect();
+        Ok(ModuleValidationReport {
+            module_reference: wasm_module.get_module_ref(),
+            size: wasm_module.source.size(),
+            custom_sections_size: artifact.custom_sections_size,
+            exports,
+            imports: wasm_imports(module_bytes),
+        })
+    }
+
+    /// Deploy a smart contract module from a file, with some of the checks
+    /// changed as specified by `options`.
+    ///
+    /// The module is loaded with [`module_load_v1`], and deployed with
+    /// [`module_deploy_v1_with_options`](Self::module_deploy_v1_with_options).
+    /// If the module cannot be loaded, no fee is charged.
+    pub fn module_deploy_wasm_v1_with_options(
+        &mut self,
+        signer: Signer,
+        sender: AccountAddress,
+        module_path: impl AsRef<Path>,
+        options: DeployOptions,
+    ) -> Result<ModuleDeployWithOptionsSuccess, ModuleDeployError> {
+        let wasm_module = module_load_v1(module_path).map_err(|err| ModuleDeployError {
+            kind:            err.kind.into(),
+            energy_used:     0.into(),
+            transaction_fee: Amount::zero(),
+        })?;
+        self.module_deploy_v1_with_options(signer, sender, wasm_module, options)
+    }
+
+    /// Deploy a smart contract module with [`Chain::module_deploy_v1`] and
+    /// register it under `tag`, such that contracts can be initialized from
+    /// it with [`Chain::contract_init_tagged`].
+    ///
+    /// This is convenient when testing several versions of a contract, e.g.,
+    /// with the tags `"v1"` and `"v2"`, as the module references do not have
+    /// to be kept around.
+    ///
+    /// If the tag is already used for a different module, the deployment
+    /// fails with [`ModuleDeployErrorKind::TagInUse`] and no fee is charged.
+    /// Deploying the same module again with its tag fails with
+    /// [`ModuleDeployErrorKind::DuplicateModule`], like any other duplicate
+    /// deployment, so an already deployed module is given another tag with
+    /// [`Chain::tag_module`] instead.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// let account = AccountAddress([0; 32]);
+    /// chain.create_account(Account::new(account, Amount::from_ccd(10000)));
+    /// let module = module_load_v1("my_module_v1.wasm.v1").unwrap();
+    /// chain.deploy_tagged(Signer::with_one_key(), account, "v1", module).unwrap();
+    /// chain
+    ///     .contract_init_tagged(
+    ///         Signer::with_one_key(),
+    ///         account,
+    ///         Energy::from(10000),
+    ///         InitContractTaggedPayload {
+    ///             amount:    Amount::zero(),
+    ///             tag:       "v1".into(),
+    ///             init_name: OwnedContractName::new_unchecked("init_my_contract".into()),
+    ///             param:     OwnedParameter::empty(),
+    ///         },
+    ///     )
+    ///     .unwrap();
+    /// ```
+    pub fn deploy_tagged(
+        &mut self,
+        signer: Signer,
+        sender: AccountAddress,
+        tag: impl Into<String>,
+        wasm_module: WasmModule,
+    ) -> Result<ModuleDeploySuccess, ModuleDeployError> {
+        let tag = tag.into();
+        if let Some(&module) = self.module_tags.get(&tag) {
+            if module != wasm_module.get_module_ref() {
+                return Err(ModuleDeployError {
+                    kind:            ModuleDeployErrorKind::TagInUse {
+                        tag,
+                        module,
+                    },
+                    energy_used:     0.into(),
+                    transaction_fee: Amount::zero(),
+                });
+            }
+        }
+        let res = self.module_deploy_v1(signer, sender, wasm_module)?;
+        self.module_tags.insert(tag, res.module_reference);
+        Ok(res)
+    }
+
+    /// Register the already deployed `module` under `tag`, such that
+    /// contracts can be initialized from it with
+    /// [`Chain::contract_init_tagged`]. A module can have several tags.
+    ///
+    /// Returns an error if the module does not exist or if the tag is already
+    /// used for a different module. Tagging a module again with one of its
+    /// tags has no effect.
+    pub fn tag_module(
+        &mut self,
+        tag: impl Into<String>,
+        module: ModuleReference,
+    ) -> Result<(), TagModuleError> {
+        if !self.modules.contains_key(&module) {
+            return Err(ModuleDoesNotExist {
+                module_reference: module,
+            }
+            .into());
+        }
+        let tag = tag.into();
+        if let Some(&existing) = self.module_tags.get(&tag) {
+            if existing != module {
+                return Err(TagModuleError::TagInUse {
+                    tag,
+                    module: existing,
+                });
+            }
+        }
+        self.module_tags.insert(tag, module);
+        Ok(())
+    }
+
+    /// The module deployed with `tag` via [`Chain::deploy_tagged`] or
+    /// registered with [`Chain::tag_module`], if any.
+    pub fn module_tag(&self, tag: &str) -> Option<ModuleReference> {
+        self.module_tags.get(tag).copied()
+    }
+
+    /// Like [`module_deploy_v1`](Self::module_deploy_v1), except that the
+    /// restriction on using the debug host functions can be relaxed, and that a
+    /// schema can be required, with `options`. With
+    /// [`DeployOptions::default()`], the module is deployed exactly like with
+    /// [`module_deploy_v1`](Self::module_deploy_v1).
+    ///
+    /// The checks that were relaxed and would otherwise have failed are
+    /// returned in
+    /// [`suppressed_warnings`](ModuleDeployWithOptionsSuccess::suppressed_warnings).
+    /// The module is always validated like by the node, so a module with, e.g.,
+    /// invalid imports or instructions cannot be deployed.
+    ///
+    /// If a schema is required but the module does not embed one, the
+    /// deployment fails with [`ModuleDeployErrorKind::MissingSchema`] and no
+    /// fee is charged.
+    pub fn module_deploy_v1_with_options(
+        &mut self,
+        signer: Signer,
+        sender: AccountAddress,
+        wasm_module: WasmModule,
+        options: DeployOptions,
+    ) -> Result<ModuleDeployWithOptionsSuccess, ModuleDeployError> {
+        let module_bytes = wasm_module.source.as_ref();
+        let mut suppressed_warnings = Vec::new();
+        let has_schema = wasm_custom_sections(module_bytes)
+            .iter()
+            .any(|name| SCHEMA_SECTION_NAMES.contains(&name.as_str()));
+        if options.require_schema && !has_schema {
+            return Err(ModuleDeployError {
+                kind:            ModuleDeployErrorKind::MissingSchema,
+                energy_used:     0.into(),
+                transaction_fee: Amount::zero(),
+            });
+        }
+        let uses_debug = wasm_imports(module_bytes)
+            .iter()
+            .any(|(module, name)| module == "concordium" && name == "debug_print");
+        if uses_debug && !self.debug_output && options.allow_debug {
+            suppressed_warnings.push(DeployWarning::DebugHostFunctions);
+        }
+        let enable_debug = self.debug_output || options.allow_debug;
+        let deployment = self.module_deploy_v1_debug(signer, sender, wasm_module, enable_debug)?;
+        Ok(ModuleDeployWithOptionsSuccess {
+            deployment,
+            suppressed_warnings,
+        })
     }
 
     /// Like [`module_deploy_v1`](Self::module_deploy_v1)
@@ -518,6 +919,12 @@ impl Chain {
         wasm_module: WasmModule,
         enable_debug: bool,
     ) -> Result<ModuleDeploySuccess, ModuleDeployError> {
+        self.record(|| Transaction::ModuleDeploy {
+            signer,
+            sender,
+            module: wasm_module.clone(),
+            enable_debug,
+        });
         // For maintainers:
         //
         // This function does not correspond exactly to what happens in the node.
@@ -529,6 +936,7 @@ impl Chain {
         // If users use our tools to deploy modules the costs are calculated for them so
         // that deployment should never fail with out of energy. Not requiring energy
         // provides a more ergonomic experience.
+        let block_energy_remaining = self.block_energy_remaining();
         let Ok(sender_account) = self.accounts
             .get_mut(&sender.into())
             .ok_or(AccountDoesNotExist { address: sender }) else {
@@ -570,6 +978,15 @@ impl Chain {
         let energy_used = check_header_energy + deploy_module_energy;
         let transaction_fee = parameters.calculate_energy_cost(energy_used);
 
+        // Ensure the deployment fits in the current block.
+        if block_energy_remaining.is_some_and(|remaining| energy_used > remaining) {
+            return Err(ModuleDeployError {
+                kind:            ModuleDeployErrorKind::OutOfBlockEnergy,
+                energy_used:     0.into(),
+                transaction_fee: Amount::zero(),
+            });
+        }
+
         // Check if the account has sufficient balance to cover the transaction fee.
         // This fee corresponds to the energy_reserved that our tools calculate when
         // sending the transaction to the node. The account is not charged in the node
@@ -585,21 +1002,18 @@ impl Chain {
 
         // Charge the account.
         sender_account.balance.total -= transaction_fee;
+        self.include_transaction(sender, energy_used, transaction_fee);
 
         // Construct the artifact.
-        let artifact = match wasm::utils::instantiate_with_metering::<v1::ProcessedImports>(
-            ValidationConfig::V1,
-            CostConfigurationV1,
-            &v1::ConcordiumAllowedImports {
-                support_upgrade: true,
-                enable_debug,
-            },
+        let artifact = match instantiate_module(
+            self.protocol_version,
+            enable_debug,
             wasm_module.source.as_ref(),
         ) {
             Ok(artifact) => artifact,
             Err(err) => {
                 return Err(ModuleDeployError {
-                    kind: ModuleInvalidError(err).into(),
+                    kind: err.into(),
                     energy_used,
                     transaction_fee,
                 })
@@ -609,7 +1023,7 @@ impl Chain {
         let module_reference: ModuleReference = wasm_module.get_module_ref();
 
         // Ensure module hasn't been deployed before.
-        if self.modules.contains_key(&module_reference) {
+        if self.module_exists(module_reference) {
             return Err(ModuleDeployError {
                 kind: ModuleDeployErrorKind::DuplicateModule(module_reference),
                 energy_used,
@@ -651,6 +1065,12 @@ impl Chain {
         energy_reserved: Energy,
         payload: InitContractPayload,
     ) -> Result<ContractInitSuccess, ContractInitError> {
+        self.record(|| Transaction::ContractInit {
+            signer,
+            sender,
+            energy_reserved,
+            payload: payload.clone(),
+        });
         let mut remaining_energy = energy_reserved;
         if !self.account_exists(sender) {
             return Err(self.convert_to_init_error(
@@ -662,6 +1082,25 @@ impl Chain {
             ));
         }
 
+        // Ensure the transaction fits in the current block before executing it.
+        if !self.fits_in_block(energy_reserved) {
+            return Err(self.convert_to_init_error(
+                ContractInitErrorKind::OutOfBlockEnergy,
+                energy_reserved,
+                remaining_energy,
+            ));
+        }
+
+        // The last index is never used, such that the next index can always be
+        // represented.
+        if self.next_contract_index == u64::MAX {
+            return Err(self.convert_to_init_error(
+                ContractInitErrorKind::ContractIndexOverflow,
+                energy_reserved,
+                remaining_energy,
+            ));
+        }
+
         let res = self.contract_init_worker(
             signer,
             sender,
@@ -670,24 +1109,61 @@ impl Chain {
             &mut remaining_energy,
         );
 
-        let (res, transaction_fee) = match res {
+        let (res, transaction_fee, energy_used) = match res {
             Ok(s) => {
-                let transaction_fee = s.transaction_fee;
-                (Ok(s), transaction_fee)
+                let (transaction_fee, energy_used) = (s.transaction_fee, s.energy_used);
+                (Ok(s), transaction_fee, energy_used)
             }
             Err(e) => {
                 let err = self.convert_to_init_error(e, energy_reserved, remaining_energy);
-                let transaction_fee = err.transaction_fee;
-                (Err(err), transaction_fee)
+                let (transaction_fee, energy_used) = (err.transaction_fee, err.energy_used);
+                (Err(err), transaction_fee, energy_used)
             }
         };
 
         // Charge the account.
         self.account_mut(sender).expect("existence already checked").balance.total -=
             transaction_fee;
+        let transaction_index = self.include_transaction(sender, energy_used, transaction_fee);
+        if let Ok(success) = &res {
+            let address = success.contract_address;
+            self.event_log.extend(success.events.iter().map(|event| ChainEvent {
+                transaction_index,
+                address,
+                event: event.clone(),
+            }));
+        }
         res
     }
 
+    /// Initialize a contract from the module deployed with the tag
+    /// `payload.tag` via [`Chain::deploy_tagged`].
+    ///
+    /// Otherwise this is the same as [`Chain::contract_init`]. If no module
+    /// has been deployed with the tag, the initialization fails with
+    /// [`ContractInitErrorKind::UnknownModuleTag`] and no fee is charged.
+    pub fn contract_init_tagged(
+        &mut self,
+        signer: Signer,
+        sender: AccountAddress,
+        energy_reserved: Energy,
+        payload: InitContractTaggedPayload,
+    ) -> Result<ContractInitSuccess, ContractInitError> {
+        let Some(mod_ref) = self.module_tag(&payload.tag) else {
+            return Err(ContractInitError {
+                energy_used:     0.into(),
+                transaction_fee: Amount::zero(),
+                kind:            ContractInitErrorKind::UnknownModuleTag(payload.tag),
+            });
+        };
+        self.contract_init(signer, sender, energy_reserved, InitContractPayload {
+            amount: payload.amount,
+            mod_ref,
+            init_name: payload.init_name,
+            param: payload.param,
+        })
+    }
+
     /// Helper method for initializing contracts, which does most of the actual
     /// work.
     ///
@@ -804,11 +1280,6 @@ impl Chain {
                 mut state,
                 trace,
             }) => {
-                let contract_address = self.create_contract_address();
-                let mut collector = v1::trie::SizeCollector::default();
-
-                let persisted_state = state.freeze(&mut loader, &mut collector);
-
                 // Perform the subtraction in the more finegrained (*1000) `InterpreterEnergy`,
                 // and *then* convert to `Energy`. This is how it is done in the node, and if we
                 // swap the operations, it can result in a small discrepancy due to rounding.
@@ -817,6 +1288,15 @@ impl Chain {
                 );
                 exit_ooe!(remaining_energy.tick_energy(energy_used_in_interpreter), trace);
 
+                if let Some(error) = oversized_state_key(&state, &trace, self.max_state_key_length)
+                {
+                    return Err(ContractInitErrorKind::StateKeyTooLarge(error));
+                }
+
+                let mut collector = v1::trie::SizeCollector::default();
+
+                let persisted_state = state.freeze(&mut loader, &mut collector);
+
                 // Charge one energy per stored state byte.
                 let energy_for_state_storage = Energy::from(collector.collect());
                 exit_ooe!(remaining_energy.tick_energy(energy_for_state_storage), trace);
@@ -828,6 +1308,12 @@ impl Chain {
                     trace
                 );
 
+                // Only create the address once the initialization can no longer fail, such
+                // that failed initializations do not consume an address.
+                let contract_address = self
+                    .create_contract_address()
+                    .expect("The index was checked before the initialization.");
+                let initial_state = StateView(persisted_state.clone());
                 let contract = Contract {
                     module_reference: payload.mod_ref,
                     contract_name:    payload.init_name,
@@ -853,6 +1339,7 @@ impl Chain {
                     energy_used,
                     transaction_fee,
                     debug_trace: trace,
+                    initial_state,
                 })
             }
             Ok(v1::InitResult::Reject {
@@ -924,8 +1411,7 @@ impl Chain {
         amount_reserved_for_energy: Amount,
         payload: UpdateContractPayload,
         remaining_energy: &mut Energy,
-    ) -> Result<(InvokeResponse, ChangeSet, Vec<DebugTraceElement>, Energy), ContractInvokeError>
-    {
+    ) -> Result<InvocationWorkerSuccess, ContractInvokeError> {
         // Check if the contract to invoke exists.
         if !self.contract_exists(payload.address) {
             return Err(self.convert_to_invoke_error(
@@ -980,16 +1466,24 @@ impl Chain {
             // transaction.
             next_contract_modification_index: 1,
             module_load_energy: 0.into(),
+            peak_segment_energy: 0.into(),
+            modification_checks: Vec::new(),
+            call_frames: Vec::new(),
+            state_writes: StateWrites::default(),
         };
         let module_load_energy = contract_invocation.module_load_energy;
         let res = contract_invocation.invoke_entrypoint(invoker, sender, payload);
         match res {
-            Ok((result, trace_elements)) => Ok((
+            Ok((result, trace_elements)) => Ok(InvocationWorkerSuccess {
                 result,
-                contract_invocation.changeset,
+                changeset: contract_invocation.changeset,
                 trace_elements,
-                contract_invocation.module_load_energy,
-            )),
+                module_load_energy: contract_invocation.module_load_energy,
+                peak_segment_energy: contract_invocation.peak_segment_energy,
+                modification_checks: contract_invocation.modification_checks,
+                call_frames: contract_invocation.call_frames,
+                state_writes: contract_invocation.state_writes,
+            }),
             Err(err) => Err(self.convert_to_invoke_error(
                 err.into(),
                 Vec::new(),
@@ -1012,6 +1506,11 @@ impl Chain {
         state_energy: Energy,
         state_changed: bool,
         module_load_energy: Energy,
+        peak_segment_energy: Energy,
+        modification_checks: Vec<ModificationCheck>,
+        mut call_frames: Vec<CallFrame>,
+        state_writes: StateWrites,
+        state_diffs: Vec<(ContractAddress, StateDiff)>,
     ) -> Result<ContractInvokeSuccess, ContractInvokeError> {
         match result {
             v1::InvokeResponse::Success {
@@ -1020,16 +1519,50 @@ impl Chain {
             } => {
                 let energy_used = energy_reserved - remaining_energy;
                 let transaction_fee = self.parameters.calculate_energy_cost(energy_used);
-                Ok(ContractInvokeSuccess {
+                // Attribute the energy used outside of the invocations to the first one.
+                let frames_energy =
+                    call_frames.iter().fold(Energy::from(0), |sum, frame| sum + frame.energy);
+                if let Some(first) = call_frames.first_mut() {
+                    first.energy.energy += energy_used.energy.saturating_sub(frames_energy.energy);
+                }
+                let mut success = ContractInvokeSuccess {
                     trace_elements,
                     energy_used,
+                    remaining_energy,
                     transaction_fee,
                     return_value: data.unwrap_or_default(),
                     state_changed,
                     new_balance,
                     storage_energy: state_energy,
                     module_load_energy,
-                })
+                    peak_segment_energy,
+                    modification_checks,
+                    call_frames,
+                    state_writes,
+                    state_diffs,
+                    log_warnings: Vec::new(),
+                };
+                if let Some(event_schema) = &self.strict_logs {
+                    success.log_warnings = success
+                        .parse_events(event_schema)
+                        .into_iter()
+                        .filter_map(|event| match event {
+                            DecodedEvent::Decoded {
+                                ..
+                            } => None,
+                            DecodedEvent::Raw {
+                                address,
+                                event,
+                                error,
+                            } => Some(LogWarning {
+                                address,
+                                event,
+                                error,
+                            }),
+                        })
+                        .collect();
+                }
+                Ok(success)
             }
             v1::InvokeResponse::Failure {
                 kind,
@@ -1057,10 +1590,18 @@ impl Chain {
     ///    For top-level invocations, such as those caused by sending a contract
     ///    update transaction on the chain, the `sender` is always the
     ///    `invoker`. Here we provide extra freedom for testing invocations
-    ///    where the sender differs.
+    ///    where the sender differs. A contract sender must exist, otherwise
+    ///    [`ContractInvokeErrorKind::SenderDoesNotExist`] is returned without
+    ///    charging the `invoker`. The energy is always paid by the `invoker`,
+    ///    also when the sender is a contract.
     ///  - `energy_reserved`: the maximum energy that can be used in the update.
     ///  - `payload`: The data detailing which contract and receive method to
-    ///    call etc.
+    ///    call etc. If the contract does not exist, e.g., because the address
+    ///    is stale, [`ContractInvokeErrorKind::ContractDoesNotExist`] is
+    ///    returned before any contract code is executed. This is distinct from
+    ///    a contract calling a missing contract during the update, which is
+    ///    reported to the calling contract, see
+    ///    [`InvokeFailureKind::MissingContract`].
     pub fn contract_update(
         &mut self,
         signer: Signer,
@@ -1069,6 +1610,13 @@ impl Chain {
         energy_reserved: Energy,
         payload: UpdateContractPayload,
     ) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+        self.record(|| Transaction::ContractUpdate {
+            signer,
+            invoker,
+            sender,
+            energy_reserved,
+            payload: payload.clone(),
+        });
         // Ensure the sender exists.
         if !self.address_exists(sender) {
             // This situation never happens on the chain since to send a message the sender
@@ -1096,6 +1644,17 @@ impl Chain {
             });
         };
 
+        // Ensure the transaction fits in the current block before executing it.
+        if !self.fits_in_block(energy_reserved) {
+            return Err(ContractInvokeError {
+                energy_used:        Energy::from(0),
+                transaction_fee:    Amount::zero(),
+                trace_elements:     Vec::new(),
+                kind:               ContractInvokeErrorKind::OutOfBlockEnergy,
+                module_load_energy: 0.into(),
+            });
+        }
+
         // Compute the base cost for checking the transaction header.
         let check_header_cost = {
             // 1 byte for the tag.
@@ -1141,11 +1700,21 @@ impl Chain {
             &mut remaining_energy,
         );
         let res = match res {
-            Ok((result, changeset, trace_elements, module_load_energy)) => {
+            Ok(InvocationWorkerSuccess {
+                result,
+                changeset,
+                trace_elements,
+                module_load_energy,
+                peak_segment_energy,
+                modification_checks,
+                call_frames,
+                state_writes,
+            }) => {
                 // Charge energy for contract storage. Or return an error if out
                 // of energy.
-                let (state_energy, state_changed) =
+                let (state_energy, state_changed, state_diffs) =
                     if matches!(result, v1::InvokeResponse::Success { .. }) {
+                        let state_diffs = changeset.state_diffs(&self.contracts);
                         let energy_before = remaining_energy;
                         let res = changeset.persist(
                             &mut remaining_energy,
@@ -1155,7 +1724,7 @@ impl Chain {
                         );
                         let state_energy = energy_before.checked_sub(remaining_energy).unwrap();
                         if let Ok(res) = res {
-                            (state_energy, res)
+                            (state_energy, res, state_diffs)
                         } else {
                             // the error happens when storing the state, so there are no trace
                             // elements associated with it. The trace is
@@ -1168,7 +1737,7 @@ impl Chain {
                         }
                     } else {
                         // An error occurred, so state hasn't changed.
-                        (0.into(), false)
+                        (0.into(), false, Vec::new())
                     };
                 self.contract_invocation_process_response(
                     result,
@@ -1178,56 +1747,232 @@ impl Chain {
                     state_energy,
                     state_changed,
                     module_load_energy,
+                    peak_segment_energy,
+                    modification_checks,
+                    call_frames,
+                    state_writes,
+                    state_diffs,
                 )
             }
             Err(e) => Err(e),
         };
 
-        let transaction_fee = match &res {
-            Ok(s) => s.transaction_fee,
-            Err(e) => e.transaction_fee,
+        let (transaction_fee, energy_used) = match &res {
+            Ok(s) => (s.transaction_fee, s.energy_used),
+            Err(e) => (e.transaction_fee, e.energy_used),
         };
         // Charge for execution.
         self.account_mut(invoker).expect("existence already checked").balance.total -=
             transaction_fee;
+        let transaction_index = self.include_transaction(invoker, energy_used, transaction_fee);
+        if let Ok(success) = &res {
+            for (address, events) in success.events() {
+                self.event_log.extend(events.iter().map(|event| ChainEvent {
+                    transaction_index,
+                    address,
+                    event: event.clone(),
+                }));
+            }
+            if let Some(log) = &mut self.reentrancy_log {
+                log.extend(success.modification_checks.iter().cloned());
+            }
+        }
         res
     }
 
-    /// Invoke a contract by calling an entrypoint.
+    /// Update a contract by calling one of its entrypoints with a parameter
+    /// provided as JSON.
     ///
-    /// Similar to [`Chain::contract_update`](Self::contract_update) except that
-    /// all changes are discarded afterwards. Typically used for "view"
-    /// functions.
+    /// The parameter is serialized using the parameter schema for the receive
+    /// function found in `schema`, after which the update is performed with
+    /// [`Chain::contract_update`](Self::contract_update). If the contract
+    /// rejects, [`ContractInvokeError::reject_reason_pretty`] can be used
+    /// with the same schema to get the name of the error.
+    ///
+    /// Returns an error without executing the update if the schema has no
+    /// parameter schema for the receive function or the parameter could not
+    /// be serialized.
     ///
     /// **Parameters:**
-    ///  - `invoker`: the account used as invoker. Since this isn't a
-    ///    transaction, it won't be charged.
-    ///  - `sender`: the sender. Can be either a contract address or an account
-    ///    address.
+    ///  - `signer`: a [`Signer`] with a number of keys. The number of keys
+    ///    affects the cost of the transaction.
+    ///  - `invoker`: the account paying for the transaction.
+    ///  - `sender`: the sender of the message, can be an account or contract.
     ///  - `energy_reserved`: the maximum energy that can be used in the update.
+    ///  - `schema`: the schema of the module containing the contract.
     ///  - `payload`: The data detailing which contract and receive method to
     ///    call etc.
-    pub fn contract_invoke(
-        &self,
+    pub fn contract_update_json(
+        &mut self,
+        signer: Signer,
+        invoker: AccountAddress,
+        sender: Address,
+        energy_reserved: Energy,
+        schema: &schema::VersionedModuleSchema,
+        payload: UpdateContractJsonPayload,
+    ) -> Result<ContractInvokeSuccess, ContractUpdateJsonError> {
+        let (contract_name, function_name) =
+            split_receive_name(payload.receive_name.as_receive_name());
+        let parameter_schema = schema
+            .get_receive_param_schema(contract_name, function_name)
+            .map_err(ContractUpdateJsonError::MissingParameterSchema)?;
+        let parameter = parameter_schema
+            .serial_value(&payload.parameter)
+            .map_err(|e| ContractUpdateJsonError::InvalidParameter(e.to_string()))?;
+        let message = OwnedParameter::try_from(parameter).map_err(|_| {
+            ContractUpdateJsonError::InvalidParameter(
+                "the serialized parameter exceeds the maximum size allowed".into(),
+            )
+        })?;
+        let res = self.contract_update(
+            signer,
+            invoker,
+            sender,
+            energy_reserved,
+            UpdateContractPayload {
+                amount: payload.amount,
+                address: payload.address,
+                receive_name: payload.receive_name,
+                message,
+            },
+        )?;
+        Ok(res)
+    }
+
+    /// Update a contract and check that the energy used stays within a
+    /// budget.
+    ///
+    /// This behaves like [`Chain::contract_update`], but returns
+    /// [`ContractUpdateBudgetError::BudgetExceeded`] if the update succeeds
+    /// while using more than `max_energy_used`. Note that the budget is
+    /// checked against the energy actually used, and is unrelated to
+    /// `energy_reserved`, which limits the execution. Together with a
+    /// recorded baseline, this makes it possible to catch regressions in the
+    /// cost of a contract in tests.
+    ///
+    /// The update is saved even if it exceeds the budget. The
+    /// [`ContractInvokeSuccess`] is then part of the error.
+    pub fn contract_update_with_budget(
+        &mut self,
+        signer: Signer,
         invoker: AccountAddress,
         sender: Address,
         energy_reserved: Energy,
         payload: UpdateContractPayload,
-    ) -> Result<ContractInvokeSuccess, ContractInvokeError> {
-        // Ensure the sender exists.
-        if !self.address_exists(sender) {
-            return Err(ContractInvokeError {
-                energy_used:        Energy::from(0),
-                transaction_fee:    Amount::zero(),
-                trace_elements:     Vec::new(),
-                kind:               ContractInvokeErrorKind::SenderDoesNotExist(sender),
-                module_load_energy: 0.into(),
+        max_energy_used: Energy,
+    ) -> Result<ContractInvokeSuccess, ContractUpdateBudgetError> {
+        let success = self.contract_update(signer, invoker, sender, energy_reserved, payload)?;
+        if success.energy_used > max_energy_used {
+            return Err(ContractUpdateBudgetError::BudgetExceeded {
+                energy_used: success.energy_used,
+                max_energy_used,
+                success: Box::new(success),
             });
         }
+        Ok(success)
+    }
 
-        let Some(account_info) = self.accounts.get(&invoker.into()) else {
-            return Err(ContractInvokeError {
-                energy_used:     Energy::from(0),
+    /// Deploy `module`, initialize a contract from it as described by `init`,
+    /// and run `seed_updates` on the new contract, in order.
+    ///
+    /// This is useful for tests which need a contract with substantial state
+    /// before the actual scenario begins. All the transactions are sent by
+    /// `sender`, including the amounts of the initialization and the updates.
+    ///
+    /// Returns the addresses and the total energy and fees of all the
+    /// transactions. If a step fails, an error naming it is returned, and the
+    /// steps before it remain in effect.
+    pub fn deploy_init_and_seed(
+        &mut self,
+        signer: Signer,
+        sender: AccountAddress,
+        module: WasmModule,
+        init: SeedInit,
+        seed_updates: &[SeedUpdate],
+    ) -> Result<SeededContract, SeedError> {
+        let deployment = self.module_deploy_v1(signer, sender, module)?;
+        let contract_name = init.contract_name.clone();
+        let init = self.contract_init(signer, sender, init.energy, InitContractPayload {
+            amount:    init.amount,
+            mod_ref:   deployment.module_reference,
+            init_name: init.contract_name,
+            param:     init.parameter,
+        })?;
+        let mut seeded = SeededContract {
+            module_reference: deployment.module_reference,
+            contract_address: init.contract_address,
+            energy_used:      deployment.energy_used + init.energy_used,
+            transaction_fee:  deployment.transaction_fee + init.transaction_fee,
+        };
+        for (index, update) in seed_updates.iter().enumerate() {
+            let success = self
+                .contract_update(
+                    signer,
+                    sender,
+                    Address::Account(sender),
+                    update.energy,
+                    UpdateContractPayload {
+                        amount:       update.amount,
+                        address:      init.contract_address,
+                        receive_name: OwnedReceiveName::construct_unchecked(
+                            contract_name.as_contract_name(),
+                            update.entrypoint.as_entrypoint_name(),
+                        ),
+                        message:      update.parameter.clone(),
+                    },
+                )
+                .map_err(|error| SeedError::Update {
+                    index,
+                    entrypoint: update.entrypoint.clone(),
+                    error,
+                })?;
+            seeded.energy_used = seeded.energy_used + success.energy_used;
+            seeded.transaction_fee = seeded.transaction_fee + success.transaction_fee;
+        }
+        Ok(seeded)
+    }
+
+    /// Invoke a contract by calling an entrypoint.
+    ///
+    /// Similar to [`Chain::contract_update`](Self::contract_update) except that
+    /// all changes are discarded afterwards. Typically used for "view"
+    /// functions.
+    ///
+    /// The invocation runs on a copy of the affected state and never modifies
+    /// the chain, which is why it only needs `&self`. A chain can therefore be
+    /// shared in an `Arc<Chain>` by several threads answering queries
+    /// concurrently, as long as updates are made elsewhere, e.g. on a
+    /// [fork](Self::fork).
+    ///
+    /// **Parameters:**
+    ///  - `invoker`: the account used as invoker. Since this isn't a
+    ///    transaction, it won't be charged.
+    ///  - `sender`: the sender. Can be either a contract address or an account
+    ///    address.
+    ///  - `energy_reserved`: the maximum energy that can be used in the update.
+    ///  - `payload`: The data detailing which contract and receive method to
+    ///    call etc.
+    pub fn contract_invoke(
+        &self,
+        invoker: AccountAddress,
+        sender: Address,
+        energy_reserved: Energy,
+        payload: UpdateContractPayload,
+    ) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+        // Ensure the sender exists.
+        if !self.address_exists(sender) {
+            return Err(ContractInvokeError {
+                energy_used:        Energy::from(0),
+                transaction_fee:    Amount::zero(),
+                trace_elements:     Vec::new(),
+                kind:               ContractInvokeErrorKind::SenderDoesNotExist(sender),
+                module_load_energy: 0.into(),
+            });
+        }
+
+        let Some(account_info) = self.accounts.get(&invoker.into()) else {
+            return Err(ContractInvokeError {
+                energy_used:     Energy::from(0),
                 transaction_fee: Amount::zero(),
                 trace_elements:  Vec::new(),
                 kind:            ContractInvokeErrorKind::InvokerDoesNotExist(
@@ -1264,17 +2009,27 @@ impl Chain {
             &mut remaining_energy,
         );
         match res {
-            Ok((result, changeset, trace_elements, module_load_energy)) => {
+            Ok(InvocationWorkerSuccess {
+                result,
+                changeset,
+                trace_elements,
+                module_load_energy,
+                peak_segment_energy,
+                modification_checks,
+                call_frames,
+                state_writes,
+            }) => {
                 // Charge energy for contract storage. Or return an error if out
                 // of energy.
-                let (state_energy, state_changed) =
+                let (state_energy, state_changed, state_diffs) =
                     if matches!(result, v1::InvokeResponse::Success { .. }) {
+                        let state_diffs = changeset.state_diffs(&self.contracts);
                         let energy_before = remaining_energy;
                         if let Ok(state_changed) = changeset
                             .collect_energy_for_state(&mut remaining_energy, contract_address)
                         {
                             let state_energy = energy_before.checked_sub(remaining_energy).unwrap();
-                            (state_energy, state_changed)
+                            (state_energy, state_changed, state_diffs)
                         } else {
                             // the error happens when storing the state, so there are no trace
                             // elements associated with it. The trace is
@@ -1287,7 +2042,7 @@ impl Chain {
                         }
                     } else {
                         // An error occurred, so state hasn't changed.
-                        (0.into(), false)
+                        (0.into(), false, Vec::new())
                     };
                 self.contract_invocation_process_response(
                     result,
@@ -1297,6 +2052,11 @@ impl Chain {
                     state_energy,
                     state_changed,
                     module_load_energy,
+                    peak_segment_energy,
+                    modification_checks,
+                    call_frames,
+                    state_writes,
+                    state_diffs,
                 )
             }
             Err(e) => Err(e),
@@ -1409,6 +2169,10 @@ impl Chain {
     /// If an account with a matching address already exists this method will
     /// replace it and return the old account.
     ///
+    /// The account is recorded as created at the current block time, see
+    /// [`Chain::account_created_at`]. Use [`Chain::create_account_at_block`]
+    /// for another creation time.
+    ///
     /// Note that if the first 29-bytes of an account are identical, then
     /// they are *considered aliases* on each other in all methods.
     /// See the example below:
@@ -1432,9 +2196,164 @@ impl Chain {
     /// );
     /// ```
     pub fn create_account(&mut self, account: Account) -> Option<Account> {
+        let block_time = self.parameters.block_time;
+        self.create_account_at_block(account, block_time)
+    }
+
+    /// Create an account as with [`Chain::create_account`], but record it as
+    /// created at `block_time` instead of at the current block time.
+    ///
+    /// This is useful for setting up accounts which existed before the
+    /// scenario being tested, without moving the block time back.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new_at(Timestamp::from_timestamp_millis(1000));
+    /// let account = AccountAddress([0; 32]);
+    /// chain.create_account_at_block(
+    ///     Account::new(account, Amount::from_ccd(10)),
+    ///     Timestamp::from_timestamp_millis(10),
+    /// );
+    /// assert_eq!(chain.account_created_at(account), Some(Timestamp::from_timestamp_millis(10)));
+    /// ```
+    pub fn create_account_at_block(
+        &mut self,
+        mut account: Account,
+        block_time: Timestamp,
+    ) -> Option<Account> {
+        // Only the default policy is given the creation time, so that the
+        // policies set up by the caller are queried by contracts as they are.
+        if account.has_empty_policy() {
+            account.policy.created_at = block_time;
+        }
+        self.account_creation_times.insert(account.address.into(), block_time);
         self.accounts.insert(account.address.into(), account)
     }
 
+    /// Return the block time at which the account `address` was created, or
+    /// `None` if the account does not exist.
+    ///
+    /// Accounts created with [`Chain::create_account`] are created at the
+    /// block time of the chain at the time, and the time is reset if the
+    /// account is replaced. Accounts added from an external node do not have
+    /// a creation time.
+    ///
+    /// For accounts with the default policy, see [`Account::new`], this is
+    /// also the `created_at` time of the policy, which contracts see when
+    /// querying the account. Other policies are kept as they are given.
+    pub fn account_created_at(&self, address: AccountAddress) -> Option<Timestamp> {
+        if !self.account_exists(address) {
+            return None;
+        }
+        self.account_creation_times.get(&address.into()).copied()
+    }
+
+    /// Create an account with the default balance and return its address.
+    ///
+    /// The address is derived from the `label` with
+    /// [`account_address_from_label`], so the same label always gives the
+    /// same address, also across chains. If the account already exists, it is
+    /// left unchanged. The default balance is
+    /// [`DEFAULT_ACCOUNT_BALANCE`](crate::DEFAULT_ACCOUNT_BALANCE) unless
+    /// configured with [`ChainBuilder::default_account_balance`].
+    ///
+    /// # Example
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// let alice = chain.create_funded_account("alice");
+    /// assert_eq!(alice, account_address_from_label("alice"));
+    /// assert_eq!(chain.account_balance_available(alice), Some(DEFAULT_ACCOUNT_BALANCE));
+    /// ```
+    pub fn create_funded_account(&mut self, label: &str) -> AccountAddress {
+        let address = account_address_from_label(label);
+        if !self.account_exists(address) {
+            self.create_account(Account::new(address, self.default_account_balance));
+        }
+        address
+    }
+
+    /// Transfer CCD from one account to another, as with a simple transfer
+    /// transaction on the chain.
+    ///
+    /// The sender is charged the cost of a simple transfer in addition to the
+    /// `amount`. If the transfer fails, neither account is changed.
+    ///
+    /// **Parameters:**
+    ///  - `signer`: a [`Signer`] with a number of keys. The number of keys
+    ///    affects the cost of the transaction.
+    ///  - `from`: the account sending the CCD and paying for the transaction.
+    ///  - `to`: the account receiving the CCD.
+    ///  - `amount`: the amount to transfer.
+    ///
+    /// Returns an error if either account does not exist, or if the sender
+    /// cannot pay for both the amount and the transaction fee.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// let alice = AccountAddress([0; 32]);
+    /// let bob = AccountAddress([1; 32]);
+    /// chain.create_account(Account::new(alice, Amount::from_ccd(100)));
+    /// chain.create_account(Account::new(bob, Amount::from_ccd(100)));
+    ///
+    /// let res = chain.transfer(Signer::with_one_key(), alice, bob, Amount::from_ccd(10)).unwrap();
+    /// assert_eq!(
+    ///     chain.account_balance_available(alice),
+    ///     Some(Amount::from_ccd(90) - res.transaction_fee)
+    /// );
+    /// assert_eq!(chain.account_balance_available(bob), Some(Amount::from_ccd(110)));
+    /// ```
+    pub fn transfer(
+        &mut self,
+        signer: Signer,
+        from: AccountAddress,
+        to: AccountAddress,
+        amount: Amount,
+    ) -> Result<AccountTransferSuccess, AccountTransferError> {
+        self.record(|| Transaction::Transfer {
+            signer,
+            from,
+            to,
+            amount,
+        });
+        let sender_available = self.account(from)?.balance.available();
+        let receiver_total = self.account(to)?.balance.total;
+
+        let check_header_energy = {
+            // +1 for the tag, +32 for the receiver and +8 for the amount.
+            let payload_size = 1 + 32 + 8 + transactions::construct::TRANSACTION_HEADER_SIZE;
+            cost::base_cost(payload_size, signer.num_keys)
+        };
+        let energy_used = check_header_energy + cost::SIMPLE_TRANSFER;
+        let transaction_fee = self.parameters.calculate_energy_cost(energy_used);
+        if !self.fits_in_block(energy_used) {
+            return Err(AccountTransferError::OutOfBlockEnergy);
+        }
+
+        let total_cost =
+            amount.checked_add(transaction_fee).ok_or(AccountTransferError::InsufficientFunds)?;
+        if sender_available < total_cost {
+            return Err(AccountTransferError::InsufficientFunds);
+        }
+        // The receiver only gains the amount if it is not also the sender.
+        if !from.is_alias(&to) && receiver_total.checked_add(amount).is_none() {
+            return Err(AccountTransferError::BalanceOverflow);
+        }
+
+        self.account_mut(from).expect("Account known to exist").balance.total -= total_cost;
+        self.account_mut(to).expect("Account known to exist").balance.total += amount;
+        self.include_transaction(from, energy_used, transaction_fee);
+        Ok(AccountTransferSuccess {
+            energy_used,
+            transaction_fee,
+        })
+    }
+
     /// Add an external account from a connected external node.
     ///
     /// If the account exists on the external node at the time of the
@@ -1501,93 +2420,691 @@ impl Chain {
     }
 
     /// Create a contract address by giving it the next available index.
-    fn create_contract_address(&mut self) -> ContractAddress {
+    ///
+    /// Returns `None` if all indices have been used. The last index is never
+    /// used, such that the next index can always be represented.
+    fn create_contract_address(&mut self) -> Option<ContractAddress> {
         let index = self.next_contract_index;
         let subindex = 0;
-        self.next_contract_index += 1;
-        ContractAddress::new(index, subindex)
+        self.next_contract_index = index.checked_add(1)?;
+        Some(ContractAddress::new(index, subindex))
     }
 
-    /// Returns the balance of an account if it exists.
-    pub fn account_balance(&self, address: AccountAddress) -> Option<AccountBalance> {
-        self.accounts.get(&address.into()).map(|ai| ai.balance)
+    /// Return the net change in the balance of an account or contract caused
+    /// by running `f` on the chain.
+    ///
+    /// For accounts, the total balance is used, so any transaction fees paid
+    /// by the account are included in the change. An account or contract
+    /// that does not exist is considered to have a balance of zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// let account = AccountAddress([0; 32]);
+    /// let delta = chain.track_balance(Address::Account(account), |chain| {
+    ///     chain.create_account(Account::new(account, Amount::from_ccd(10)));
+    /// });
+    /// assert_eq!(delta, AmountDelta::Positive(Amount::from_ccd(10)));
+    /// ```
+    pub fn track_balance(&mut self, address: Address, f: impl FnOnce(&mut Self)) -> AmountDelta {
+        let before = self.total_balance(address);
+        f(self);
+        AmountDelta::between(before, self.total_balance(address))
     }
 
-    /// Returns the available balance of an account if it exists.
-    pub fn account_balance_available(&self, address: AccountAddress) -> Option<Amount> {
-        self.accounts.get(&address.into()).map(|ai| ai.balance.available())
+    /// Return the total balance of an account or the balance of a contract
+    /// if it exists, and zero otherwise.
+    pub(crate) fn total_balance(&self, address: Address) -> Amount {
+        match address {
+            Address::Account(address) => self.account_balance(address).map(|b| b.total),
+            Address::Contract(address) => self.contract_balance(address),
+        }
+        .unwrap_or_else(Amount::zero)
     }
 
-    /// Returns the balance of an contract if it exists.
-    pub fn contract_balance(&self, address: ContractAddress) -> Option<Amount> {
-        self.contracts.get(&address).map(|ci| ci.self_balance)
+    /// The total amount of CCD on the chain, i.e., the sum of the total
+    /// balances of all the accounts and the balances of all the contracts.
+    ///
+    /// Mocked account balances and the balances of external accounts and
+    /// contracts are not included.
+    pub fn total_ccd(&self) -> Amount {
+        let accounts = self.accounts.values().map(|account| account.balance.total);
+        let contracts = self.contracts.values().map(|contract| contract.self_balance);
+        accounts.chain(contracts).fold(Amount::zero(), |sum, amount| sum + amount)
     }
 
-    /// Helper method for looking up part of the state of a smart contract,
-    /// which is a key-value store.
-    pub fn contract_state_lookup(&self, address: ContractAddress, key: &[u8]) -> Option<Vec<u8>> {
-        let mut loader = v1::trie::Loader::new(&[][..]);
-        self.contracts.get(&address)?.state.lookup(&mut loader, key)
+    /// The transaction fees paid by all the transactions executed on the
+    /// chain, including those that failed after being charged.
+    ///
+    /// The fees are not paid to any account or contract on the chain, so they
+    /// leave the [total supply](Self::total_ccd). In the node, they are
+    /// distributed to the bakers and the foundation, which is not simulated.
+    /// The fees collected are part of [checkpoints](Self::checkpoint) and
+    /// forks, but are not persisted with [`Chain::persist`].
+    pub fn fees_collected(&self) -> Amount { self.fees_collected }
+
+    /// Assert that no CCD was created or destroyed since the total supply was
+    /// `initial_supply`, except for the transaction fees paid.
+    ///
+    /// That is, the [total supply](Self::total_ccd) plus the
+    /// [fees collected](Self::fees_collected) must equal `initial_supply`,
+    /// which is typically the same sum computed after setting up the
+    /// scenario. Note that creating accounts and contracts after that adds
+    /// their balances to the supply.
+    ///
+    /// # Panics
+    /// Panics if CCD was created or destroyed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// chain.create_account(Account::new(AccountAddress([0; 32]), Amount::from_ccd(10)));
+    /// chain.create_account(Account::new(AccountAddress([1; 32]), Amount::from_ccd(10)));
+    /// let initial_supply = chain.total_ccd() + chain.fees_collected();
+    ///
+    /// let res = chain
+    ///     .transfer(
+    ///         Signer::with_one_key(),
+    ///         AccountAddress([0; 32]),
+    ///         AccountAddress([1; 32]),
+    ///         Amount::from_ccd(1),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(chain.total_ccd(), Amount::from_ccd(20) - res.transaction_fee);
+    /// chain.assert_supply_conserved(initial_supply);
+    /// ```
+    pub fn assert_supply_conserved(&self, initial_supply: Amount) {
+        let total = self.total_ccd();
+        let fees = self.fees_collected;
+        let supply = total + fees;
+        if supply != initial_supply {
+            panic!(
+                "The supply of CCD was not conserved: the chain has {} CCD and {} CCD was paid in \
+                 fees, which is {} CCD in total, but the initial supply was {} CCD.",
+                total.to_ccd_string(),
+                fees.to_ccd_string(),
+                supply.to_ccd_string(),
+                initial_supply.to_ccd_string()
+            );
+        }
     }
 
-    /// Return a clone of the [`ContractModule`] (which has an `Arc` around the
-    /// artifact so cloning is cheap).
-    fn contract_module(
-        &self,
-        module_ref: ModuleReference,
-    ) -> Result<ContractModule, ModuleDoesNotExist> {
-        let module = self.modules.get(&module_ref).ok_or(ModuleDoesNotExist {
-            module_reference: module_ref,
-        })?;
-        Ok(module.clone())
+    /// Assert that the states of the contracts `address_a` and `address_b`
+    /// have the same entries.
+    ///
+    /// This is useful for checking that a contract which was cloned or
+    /// migrated ended up with the same state as a reference contract. To
+    /// compare the same contract on two chains, use [`Chain::diff`] instead.
+    ///
+    /// # Panics
+    /// Panics if either contract does not exist, or if the states differ. In
+    /// the latter case, the message contains the number of differing keys and
+    /// the smallest of them, with its value in each state, or with a note
+    /// that it is missing from one of them.
+    pub fn assert_states_equal(&self, address_a: ContractAddress, address_b: ContractAddress) {
+        let entries = |address: ContractAddress| {
+            let contract = self
+                .contracts
+                .get(&address)
+                .unwrap_or_else(|| panic!("The contract {address} does not exist."));
+            state_entries(&contract.state.thaw())
+        };
+        let diff = StateDiff::between(entries(address_a), entries(address_b));
+        let differences = diff.removed.len() + diff.added.len() + diff.changed.len();
+        let first = diff
+            .removed
+            .iter()
+            .map(|(key, value)| (key, Some(value), None))
+            .chain(diff.added.iter().map(|(key, value)| (key, None, Some(value))))
+            .chain(
+                diff.changed
+                    .iter()
+                    .map(|change| (&change.key, Some(&change.before), Some(&change.after))),
+            )
+            .min_by_key(|(key, ..)| *key);
+        let Some((key, value_a, value_b)) = first else {
+            return;
+        };
+        let describe = |value: Option<&Vec<u8>>| match value {
+            Some(value) => format!("has the value 0x{}", hex_encode(value)),
+            None => "is missing".to_string(),
+        };
+        panic!(
+            "The states of the contracts {address_a} and {address_b} differ in {differences} \
+             key(s). The first differing key 0x{} {} in {address_a} and {} in {address_b}.",
+            hex_encode(key),
+            describe(value_a),
+            describe(value_b)
+        );
     }
 
-    /// Returns an immutable reference to an [`Account`].
-    pub fn account(&self, address: AccountAddress) -> Result<&Account, AccountDoesNotExist> {
-        self.accounts.get(&address.into()).ok_or(AccountDoesNotExist {
-            address,
-        })
+    /// Run [`Chain::contract_update`] and assert that it fails because a
+    /// contract tried to send more CCD than its balance, and return the
+    /// error.
+    ///
+    /// See [`ContractInvokeError::is_insufficient_funds_reject`] for how the
+    /// failure is recognized.
+    ///
+    /// # Panics
+    /// Panics with the actual outcome if the update does not fail in this way.
+    /// In particular, it panics if the `invoker` cannot pay for the energy or
+    /// the amount of the update, since the contract is then never run, and
+    /// if the update succeeds because the contract handled the failed
+    /// transfer itself.
+    pub fn assert_rejects_insufficient_funds(
+        &mut self,
+        signer: Signer,
+        invoker: AccountAddress,
+        sender: Address,
+        energy_reserved: Energy,
+        payload: UpdateContractPayload,
+    ) -> ContractInvokeError {
+        let error = match self.contract_update(signer, invoker, sender, energy_reserved, payload) {
+            Ok(success) => {
+                let failure = success.invoke_response_code().and_then(|code| code.failure_kind());
+                if failure == Some(InvokeFailureKind::AmountTooLarge) {
+                    panic!(
+                        "Expected the update to reject with insufficient funds, but it succeeded. \
+                         The contract returned the response code of a transfer with insufficient \
+                         funds, so it handled the failure instead of rejecting."
+                    );
+                }
+                panic!("Expected the update to reject with insufficient funds, but it succeeded.");
+            }
+            Err(error) => error,
+        };
+        match error.kind {
+            ContractInvokeErrorKind::InsufficientFunds
+            | ContractInvokeErrorKind::AmountTooLarge => {
+                panic!(
+                    "Expected a contract to reject with insufficient funds, but the invoker {} \
+                     could not pay for the update, so no contract was run: {}",
+                    invoker, error.kind
+                );
+            }
+            _ if error.is_insufficient_funds_reject() => error,
+            _ => match error.reject_code() {
+                Some(code) => panic!(
+                    "Expected the update to reject with insufficient funds, but it rejected with \
+                     code {code}."
+                ),
+                None => panic!(
+                    "Expected the update to reject with insufficient funds, but it failed with: {}",
+                    error.kind
+                ),
+            },
+        }
     }
 
-    /// Returns a mutable reference to [`Account`].
-    fn account_mut(
-        &mut self,
-        address: AccountAddress,
-    ) -> Result<&mut Account, AccountDoesNotExist> {
-        self.accounts.get_mut(&address.into()).ok_or(AccountDoesNotExist {
-            address,
-        })
+    /// Return the address that the next successfully initialized contract
+    /// instance will get.
+    ///
+    /// Failed initializations do not consume an address, so the address
+    /// returned only changes when a contract is initialized successfully. This
+    /// makes it possible to know the address of a contract before it is
+    /// initialized, for example to include it in the parameter of another
+    /// contract.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use concordium_smart_contract_testing::*;
+    /// # let mut chain = Chain::new();
+    /// # let account = AccountAddress([0; 32]);
+    /// # chain.create_account(Account::new(account, Amount::from_ccd(10000)));
+    /// # let module = module_load_v1("my_module_v1.wasm.v1").unwrap();
+    /// # let res_deploy = chain.module_deploy_v1(Signer::with_one_key(), account, module).unwrap();
+    /// let address = chain.next_contract_address();
+    /// let res_init = chain
+    ///     .contract_init(Signer::with_one_key(), account, Energy::from(10000), InitContractPayload {
+    ///         mod_ref:   res_deploy.module_reference,
+    ///         init_name: OwnedContractName::new_unchecked("init_my_contract".into()),
+    ///         param:     OwnedParameter::empty(),
+    ///         amount:    Amount::zero(),
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(res_init.contract_address, address);
+    /// ```
+    pub fn next_contract_address(&self) -> ContractAddress {
+        ContractAddress::new(self.next_contract_index, 0)
     }
 
-    /// Check whether an [`Account`] exists.
-    pub fn account_exists(&self, address: AccountAddress) -> bool {
-        self.accounts.contains_key(&address.into())
+    /// Set the index that the next successfully initialized contract instance
+    /// will get.
+    ///
+    /// This is useful for testing how contracts handle large contract indices.
+    /// The index cannot be lower than the current next index, as that could
+    /// reuse the address of an existing contract.
+    ///
+    /// The index `u64::MAX` is never given to a contract. Once the next index
+    /// reaches it, initializations fail with
+    /// [`ContractInitErrorKind::ContractIndexOverflow`].
+    pub fn set_next_contract_index(&mut self, index: u64) -> Result<(), ContractIndexInUse> {
+        if index < self.next_contract_index {
+            return Err(ContractIndexInUse {
+                index,
+                next_index: self.next_contract_index,
+            });
+        }
+        self.next_contract_index = index;
+        Ok(())
     }
 
-    /// Check whether a [`Contract`] exists.
-    pub fn contract_exists(&self, address: ContractAddress) -> bool {
-        self.contracts.contains_key(&address)
+    /// Insert a contract instance without running its init function.
+    ///
+    /// The instance gets the next contract address, which is returned, and
+    /// afterwards it can be updated and invoked like any other instance. This
+    /// is useful for testing against a contract in a known state, for example
+    /// one whose source is not available.
+    ///
+    /// **This bypasses the invariants that a normal initialization ensures**:
+    /// the owner need not exist, the balance is created out of nothing instead
+    /// of being transferred from the owner, the name need not be a contract in
+    /// the module, and the state is neither charged for nor checked against
+    /// [`ChainBuilder::max_state_key_length`]. No transaction is recorded.
+    ///
+    /// Returns an error if the module has not been deployed, or if all
+    /// contract indices have been used, as for
+    /// [`ContractInitErrorKind::ContractIndexOverflow`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use concordium_smart_contract_testing::*;
+    /// # let mut chain = Chain::new();
+    /// # let account = AccountAddress([0; 32]);
+    /// # chain.create_account(Account::new(account, Amount::from_ccd(10000)));
+    /// # let module = module_load_v1("my_module_v1.wasm.v1").unwrap();
+    /// # let res_deploy = chain.module_deploy_v1(Signer::with_one_key(), account, module).unwrap();
+    /// let spec = ContractSpec::new(
+    ///     res_deploy.module_reference,
+    ///     OwnedContractName::new_unchecked("init_counter".into()),
+    ///     AccountAddress([0; 32]),
+    /// )
+    /// .with_state_entry([0u8; 8], 41u64.to_le_bytes())
+    /// .with_balance(Amount::from_ccd(10));
+    /// let address = chain.insert_contract_unchecked(spec).unwrap();
+    /// ```
+    pub fn insert_contract_unchecked(
+        &mut self,
+        spec: ContractSpec,
+    ) -> Result<ContractAddress, InsertContractError> {
+        self.contract_module(spec.module_reference)?;
+        let address =
+            self.create_contract_address().ok_or(InsertContractError::ContractIndexOverflow)?;
+        self.contracts.insert(address, Contract {
+            address,
+            module_reference: spec.module_reference,
+            contract_name: spec.contract_name,
+            state: v1::trie::PersistentState::from_iterator(spec.state.into_iter()),
+            owner: spec.owner,
+            self_balance: spec.balance,
+        });
+        Ok(address)
     }
 
-    /// Check whether an object with the [`Address`] exists.
+    /// Set the entry with the `key` in the state of the contract `address` to
+    /// `value`, without going through the contract.
     ///
-    /// That is, if it is an account address, whether the account exists,
-    /// and if it is a contract address, whether the contract exists.
-    fn address_exists(&self, address: Address) -> bool {
-        match address {
-            Address::Account(acc) => self.account_exists(acc),
-            Address::Contract(contr) => self.contract_exists(contr),
+    /// This is useful for putting a contract into a state which is hard to
+    /// reach with its entrypoints, such as a counter close to overflowing. The
+    /// change is made to the saved state of the contract, so subsequent
+    /// updates see it exactly as if the contract had written the entry. No
+    /// checks are made, so the contract might not be able to handle the
+    /// state, and the key is not checked against
+    /// [`ChainBuilder::max_state_key_length`].
+    ///
+    /// Returns an error if the contract does not exist.
+    pub fn set_contract_state_entry_unchecked(
+        &mut self,
+        address: ContractAddress,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    ) -> Result<(), ContractDoesNotExist> {
+        let contract = self.contracts.get_mut(&address).ok_or(ContractDoesNotExist {
+            address,
+        })?;
+        let mut loader = v1::trie::Loader::new(&[][..]);
+        let mut state = contract.state.thaw();
+        {
+            let inner = state.get_inner(&mut loader);
+            let mut trie = inner.lock();
+            trie.insert(&mut loader, &key, value)
+                .expect("The state has no iterators, so no part of it is locked.");
         }
+        contract.state = state.freeze(&mut loader, &mut v1::trie::SizeCollector::default());
+        Ok(())
     }
 
-    /// Convert a [`ContractInvokeErrorKind`] to a
-    /// [`ContractInvokeError`] by calculating the `energy_used` and
-    /// `transaction_fee`.
+    /// Delete the entry with the `key` from the state of the contract
+    /// `address`, without going through the contract.
     ///
-    /// If the `kind` is an out of energy, then `0` is used instead of the
-    /// `remaining_energy` parameter, as it will likely not be `0` due to short
-    /// circuiting during execution.
-    fn convert_to_invoke_error(
+    /// Like [`Chain::set_contract_state_entry_unchecked`], the change is made
+    /// to the saved state of the contract and no checks are made.
+    ///
+    /// Returns whether the entry existed, or an error if the contract does not
+    /// exist.
+    pub fn delete_contract_state_entry_unchecked(
+        &mut self,
+        address: ContractAddress,
+        key: &[u8],
+    ) -> Result<bool, ContractDoesNotExist> {
+        let contract = self.contracts.get_mut(&address).ok_or(ContractDoesNotExist {
+            address,
+        })?;
+        let mut loader = v1::trie::Loader::new(&[][..]);
+        let mut state = contract.state.thaw();
+        let existed = {
+            let inner = state.get_inner(&mut loader);
+            let mut trie = inner.lock();
+            trie.delete(&mut loader, key)
+                .expect("The state has no iterators, so no part of it is locked.")
+        };
+        contract.state = state.freeze(&mut loader, &mut v1::trie::SizeCollector::default());
+        Ok(existed)
+    }
+
+    /// Set the balance returned when a contract queries the balance of the
+    /// account `address`, without creating the account.
+    ///
+    /// This is useful for contracts that query the balance of an account
+    /// which is otherwise not part of the test, such as a treasury. The mock
+    /// is only used for balance queries made by contracts, and only while no
+    /// account with the address exists, in which case the actual balance of
+    /// the account is returned. An account is not created, so the mocked
+    /// balance cannot be transferred from or to.
+    ///
+    /// Returns the previous mocked balance of the account, if any.
+    pub fn mock_account_balance(
+        &mut self,
+        address: AccountAddress,
+        balance: AccountBalance,
+    ) -> Option<AccountBalance> {
+        self.mocked_account_balances.insert(address.into(), balance)
+    }
+
+    /// Remove the mocked balance of the account `address`, see
+    /// [`Chain::mock_account_balance`].
+    ///
+    /// Returns the mocked balance, if any.
+    pub fn remove_account_balance_mock(
+        &mut self,
+        address: AccountAddress,
+    ) -> Option<AccountBalance> {
+        self.mocked_account_balances.remove(&address.into())
+    }
+
+    /// Return whether calling the entrypoint `entrypoint` of the contract
+    /// instance at `address` would execute a receive function.
+    ///
+    /// This is the case if the contract has the entrypoint or a fallback
+    /// entrypoint. It is the same check as the one made when a contract calls
+    /// another contract, where the call fails with
+    /// [`InvokeFailure::NonExistentEntrypoint`](v1::InvokeFailure::NonExistentEntrypoint)
+    /// if the check fails. Returns `false` if the contract does not exist.
+    pub fn contract_has_entrypoint(
+        &self,
+        address: ContractAddress,
+        entrypoint: EntrypointName,
+    ) -> bool {
+        let Some(contract) = self.contracts.get(&address) else {
+            return false;
+        };
+        let Some(module) = self.modules.get(&contract.module_reference) else {
+            return false;
+        };
+        let receive_name = OwnedReceiveName::construct_unchecked(
+            contract.contract_name.as_contract_name(),
+            entrypoint,
+        );
+        resolve_receive_name(module, receive_name.as_receive_name()).is_some()
+    }
+
+    /// Iterate over all the accounts on the chain.
+    ///
+    /// The accounts are ordered by their address, where aliases of an account
+    /// are considered equal to it. The address of each account is the one
+    /// it was created with.
+    pub fn accounts(&self) -> impl Iterator<Item = (&AccountAddress, &Account)> {
+        self.accounts.values().map(|account| (&account.address, account))
+    }
+
+    /// Iterate over all the contract instances on the chain, ordered by their
+    /// address.
+    pub fn contracts(&self) -> impl Iterator<Item = (&ContractAddress, &Contract)> {
+        self.contracts.iter()
+    }
+
+    /// Return the addresses of all the contract instances whose code is in
+    /// the module `module_reference`, in increasing order.
+    ///
+    /// Contracts that have been upgraded are listed under the module they
+    /// were upgraded to.
+    pub fn contracts_by_module(&self, module_reference: ModuleReference) -> Vec<ContractAddress> {
+        self.contracts
+            .values()
+            .filter(|contract| contract.module_reference == module_reference)
+            .map(|contract| contract.address)
+            .collect()
+    }
+
+    /// Returns the nonce of the next transaction sent from an account, if the
+    /// account exists.
+    ///
+    /// As on the chain, the nonce of a new account is `1`. It is incremented
+    /// by each transaction sent from the account which is charged a fee, i.e.,
+    /// module deployments, contract initializations, contract updates (for
+    /// the invoker) and transfers, whether they succeed or not. Invocations
+    /// with [`Chain::contract_invoke`] are not transactions and do not change
+    /// the nonce.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// let alice = AccountAddress([0; 32]);
+    /// let bob = AccountAddress([1; 32]);
+    /// chain.create_account(Account::new(alice, Amount::from_ccd(100)));
+    /// chain.create_account(Account::new(bob, Amount::from_ccd(100)));
+    /// assert_eq!(chain.account_nonce(alice), Some(1));
+    ///
+    /// chain.transfer(Signer::with_one_key(), alice, bob, Amount::from_ccd(10)).unwrap();
+    /// assert_eq!(chain.account_nonce(alice), Some(2));
+    /// assert_eq!(chain.account_nonce(bob), Some(1));
+    /// assert_eq!(chain.account_nonce(AccountAddress([2; 32])), None);
+    /// ```
+    pub fn account_nonce(&self, address: AccountAddress) -> Option<u64> {
+        self.accounts.get(&address.into()).map(|account| account.nonce)
+    }
+
+    /// Returns the balance of an account if it exists.
+    pub fn account_balance(&self, address: AccountAddress) -> Option<AccountBalance> {
+        self.accounts.get(&address.into()).map(|ai| ai.balance)
+    }
+
+    /// Returns the available balance of an account if it exists.
+    pub fn account_balance_available(&self, address: AccountAddress) -> Option<Amount> {
+        self.accounts.get(&address.into()).map(|ai| ai.balance.available())
+    }
+
+    /// Returns the balance of an contract if it exists.
+    pub fn contract_balance(&self, address: ContractAddress) -> Option<Amount> {
+        self.contracts.get(&address).map(|ci| ci.self_balance)
+    }
+
+    /// Returns the owner of a contract instance if it exists.
+    ///
+    /// The owner is the account that initialized the contract, i.e., the
+    /// `sender` of the [`Chain::contract_init`] call. This is the account that
+    /// contracts see as the owner in the receive context, e.g., via
+    /// `ctx.owner()`, which is commonly used to guard admin entrypoints.
+    pub fn contract_owner(&self, address: ContractAddress) -> Option<AccountAddress> {
+        self.contracts.get(&address).map(|contract| contract.owner)
+    }
+
+    /// Returns information about a contract instance if it exists.
+    ///
+    /// This includes the module reference of the instance, which reflects any
+    /// upgrades the contract has performed.
+    pub fn contract_info(&self, address: ContractAddress) -> Option<ContractInfo> {
+        self.contracts.get(&address).map(|contract| ContractInfo {
+            module_reference: contract.module_reference,
+            contract_name:    contract.contract_name.clone(),
+            owner:            contract.owner,
+            amount:           contract.self_balance,
+        })
+    }
+
+    /// Return at most `limit` keys of the state of a contract instance, in
+    /// increasing order.
+    ///
+    /// This is useful for inspecting the structure of a large state without
+    /// reading all of it. Returns `None` if the contract does not exist.
+    pub fn contract_state_keys(
+        &self,
+        address: ContractAddress,
+        limit: usize,
+    ) -> Option<Vec<Vec<u8>>> {
+        let state = self.contracts.get(&address)?.state.thaw();
+        Some(state_entries_up_to(&state, limit).into_iter().map(|(key, _)| key).collect())
+    }
+
+    /// Return the number of entries in the state of a contract instance and
+    /// the number of bytes in their keys and values.
+    ///
+    /// Only the entries currently in the state are counted, so entries that
+    /// have been deleted do not contribute. This makes it possible to check
+    /// that a sequence of operations does not grow the state. Returns `None`
+    /// if the contract does not exist.
+    pub fn contract_state_size(&self, address: ContractAddress) -> Option<StateSize> {
+        let state = self.contracts.get(&address)?.state.thaw();
+        let entries = state_entries_up_to(&state, usize::MAX);
+        Some(StateSize {
+            entries:     entries.len(),
+            key_bytes:   entries.iter().map(|(key, _)| key.len() as u64).sum(),
+            value_bytes: entries.iter().map(|(_, value)| value.len() as u64).sum(),
+        })
+    }
+
+    /// Render the state of a contract instance as human-readable JSON, for
+    /// debugging.
+    ///
+    /// The state is rendered as a JSON object with an entry for each key in
+    /// the state, where the keys are hex encoded. The values are hex encoded
+    /// as well, except for the root of the state stored by `concordium-std`
+    /// under the key `0000000000000000`, which is rendered with the state
+    /// schema of the contract if `schema` contains one. Only V0 module
+    /// schemas include state schemas, and if the value does not match the
+    /// schema, it is hex encoded instead.
+    ///
+    /// Returns `None` if the contract does not exist.
+    pub fn dump_contract_state(
+        &self,
+        address: ContractAddress,
+        schema: &schema::VersionedModuleSchema,
+    ) -> Option<String> {
+        let contract = self.contracts.get(&address)?;
+        let contract_name = contract.contract_name.as_contract_name().contract_name();
+        let state_schema = match schema {
+            schema::VersionedModuleSchema::V0(module) => {
+                module.contracts.get(contract_name).and_then(|contract| contract.state.as_ref())
+            }
+            _ => None,
+        };
+        let entries = state_entries_up_to(&contract.state.thaw(), usize::MAX)
+            .into_iter()
+            .map(|(key, value)| {
+                let rendered = state_schema
+                    .filter(|_| key == [0u8; 8])
+                    .and_then(|state_schema| {
+                        let mut cursor = contracts_common::Cursor::new(&value[..]);
+                        let json = state_schema.to_json(&mut cursor).ok()?;
+                        (cursor.offset == value.len()).then_some(json)
+                    })
+                    .unwrap_or_else(|| serde_json::Value::String(hex_encode(&value)));
+                (hex_encode(&key), rendered)
+            })
+            .collect::<serde_json::Map<_, _>>();
+        Some(
+            serde_json::to_string_pretty(&serde_json::Value::Object(entries))
+                .expect("Serializing JSON values succeeds."),
+        )
+    }
+
+    /// Helper method for looking up part of the state of a smart contract,
+    /// which is a key-value store.
+    pub fn contract_state_lookup(&self, address: ContractAddress, key: &[u8]) -> Option<Vec<u8>> {
+        let mut loader = v1::trie::Loader::new(&[][..]);
+        self.contracts.get(&address)?.state.lookup(&mut loader, key)
+    }
+
+    /// Return a clone of the [`ContractModule`] (which has an `Arc` around the
+    /// artifact so cloning is cheap).
+    fn contract_module(
+        &self,
+        module_ref: ModuleReference,
+    ) -> Result<ContractModule, ModuleDoesNotExist> {
+        let module = self.modules.get(&module_ref).ok_or(ModuleDoesNotExist {
+            module_reference: module_ref,
+        })?;
+        Ok(module.clone())
+    }
+
+    /// Returns an immutable reference to an [`Account`].
+    pub fn account(&self, address: AccountAddress) -> Result<&Account, AccountDoesNotExist> {
+        self.accounts.get(&address.into()).ok_or(AccountDoesNotExist {
+            address,
+        })
+    }
+
+    /// Returns a mutable reference to [`Account`].
+    fn account_mut(
+        &mut self,
+        address: AccountAddress,
+    ) -> Result<&mut Account, AccountDoesNotExist> {
+        self.accounts.get_mut(&address.into()).ok_or(AccountDoesNotExist {
+            address,
+        })
+    }
+
+    /// Check whether an [`Account`] exists.
+    pub fn account_exists(&self, address: AccountAddress) -> bool {
+        self.accounts.contains_key(&address.into())
+    }
+
+    /// Check whether a [`Contract`] exists.
+    pub fn contract_exists(&self, address: ContractAddress) -> bool {
+        self.contracts.contains_key(&address)
+    }
+
+    /// Check whether a module with the [`ModuleReference`] has been deployed.
+    pub fn module_exists(&self, module_reference: ModuleReference) -> bool {
+        self.modules.contains_key(&module_reference)
+    }
+
+    /// Check whether an object with the [`Address`] exists.
+    ///
+    /// That is, if it is an account address, whether the account exists,
+    /// and if it is a contract address, whether the contract exists.
+    fn address_exists(&self, address: Address) -> bool {
+        match address {
+            Address::Account(acc) => self.account_exists(acc),
+            Address::Contract(contr) => self.contract_exists(contr),
+        }
+    }
+
+    /// Convert a [`ContractInvokeErrorKind`] to a
+    /// [`ContractInvokeError`] by calculating the `energy_used` and
+    /// `transaction_fee`.
+    ///
+    /// If the `kind` is an out of energy, then `0` is used instead of the
+    /// `remaining_energy` parameter, as it will likely not be `0` due to short
+    /// circuiting during execution.
+    fn convert_to_invoke_error(
         &self,
         kind: ContractInvokeErrorKind,
         trace_elements: Vec<DebugTraceElement>,
@@ -1611,101 +3128,868 @@ impl Chain {
         }
     }
 
-    /// Construct a [`ContractInvokeErrorKind`] of the `OutOfEnergy` kind with
-    /// the energy and transaction fee fields based on the `energy_reserved`
-    /// parameter.
-    fn invocation_out_of_energy_error(
-        &self,
-        energy_reserved: Energy,
-        debug_trace: DebugTracker,
-        module_load_energy: Energy,
-    ) -> ContractInvokeError {
-        self.convert_to_invoke_error(
-            ContractInvokeErrorKind::OutOfEnergy {
-                debug_trace,
-            },
-            Vec::new(),
-            energy_reserved,
-            Energy::from(0),
-            module_load_energy,
-        )
+    /// Construct a [`ContractInvokeErrorKind`] of the `OutOfEnergy` kind with
+    /// the energy and transaction fee fields based on the `energy_reserved`
+    /// parameter.
+    fn invocation_out_of_energy_error(
+        &self,
+        energy_reserved: Energy,
+        debug_trace: DebugTracker,
+        module_load_energy: Energy,
+    ) -> ContractInvokeError {
+        self.convert_to_invoke_error(
+            ContractInvokeErrorKind::OutOfEnergy {
+                debug_trace,
+            },
+            Vec::new(),
+            energy_reserved,
+            Energy::from(0),
+            module_load_energy,
+        )
+    }
+
+    /// Convert a [`ContractInitErrorKind`] to a
+    /// [`ContractInitError`] by calculating the `energy_used` and
+    /// `transaction_fee`.
+    fn convert_to_init_error(
+        &self,
+        kind: ContractInitErrorKind,
+        energy_reserved: Energy,
+        remaining_energy: Energy,
+    ) -> ContractInitError {
+        let energy_used = energy_reserved - remaining_energy;
+        let transaction_fee = self.parameters.calculate_energy_cost(energy_used);
+        ContractInitError {
+            energy_used,
+            transaction_fee,
+            kind,
+        }
+    }
+
+    /// Try to set the exchange rates on the chain.
+    ///
+    /// Will fail if they result in the cost of one energy being larger than
+    /// `u64::MAX / 100_000_000_000`.
+    pub fn set_exchange_rates(
+        &mut self,
+        micro_ccd_per_euro: ExchangeRate,
+        euro_per_energy: ExchangeRate,
+    ) -> Result<(), ExchangeRateError> {
+        // Ensure the exchange rates are within a valid range.
+        check_exchange_rates(euro_per_energy, micro_ccd_per_euro)?;
+        self.parameters.micro_ccd_per_euro = micro_ccd_per_euro;
+        self.parameters.euro_per_energy = euro_per_energy;
+        Ok(())
+    }
+
+    /// Schedule exchange rates to take effect at a specific block time.
+    ///
+    /// The rates are set when [`Chain::tick_block_time`] moves the block time
+    /// to or past `effective_time`. This can be used for testing contracts
+    /// across changes in the exchange rates. If the block time already is at
+    /// or past `effective_time`, the rates are set immediately. Scheduling
+    /// rates for the same time as previously scheduled rates replaces them.
+    ///
+    /// Will fail if they result in the cost of one energy being larger than
+    /// `u64::MAX / 100_000_000_000`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// let micro_ccd_per_euro = ExchangeRate::new_unchecked(100, 1);
+    /// let euro_per_energy = ExchangeRate::new_unchecked(1, 1000);
+    /// chain
+    ///     .schedule_exchange_rates(
+    ///         Timestamp::from_timestamp_millis(1000),
+    ///         micro_ccd_per_euro,
+    ///         euro_per_energy,
+    ///     )
+    ///     .unwrap();
+    ///
+    /// chain.tick_block_time(Duration::from_millis(1000)).unwrap();
+    /// assert_eq!(chain.micro_ccd_per_euro(), micro_ccd_per_euro);
+    /// assert_eq!(chain.euro_per_energy(), euro_per_energy);
+    /// ```
+    pub fn schedule_exchange_rates(
+        &mut self,
+        effective_time: SlotTime,
+        micro_ccd_per_euro: ExchangeRate,
+        euro_per_energy: ExchangeRate,
+    ) -> Result<(), ExchangeRateError> {
+        // Ensure the exchange rates are within a valid range.
+        check_exchange_rates(euro_per_energy, micro_ccd_per_euro)?;
+        self.scheduled_exchange_rates.insert(effective_time, ExchangeRates {
+            euro_per_energy,
+            micro_ccd_per_euro,
+        });
+        self.apply_scheduled_exchange_rates();
+        Ok(())
+    }
+
+    /// Set the scheduled exchange rates that are effective at the current
+    /// block time, in the order of their effective times.
+    fn apply_scheduled_exchange_rates(&mut self) {
+        while let Some(entry) = self.scheduled_exchange_rates.first_entry() {
+            if *entry.key() > self.parameters.block_time {
+                break;
+            }
+            let rates = entry.remove();
+            // The rates were checked when they were scheduled.
+            self.parameters.micro_ccd_per_euro = rates.micro_ccd_per_euro;
+            self.parameters.euro_per_energy = rates.euro_per_energy;
+        }
+    }
+
+    /// Get the microCCD per euro and euro per energy exchange rates by querying
+    /// an external node using the external query block.
+    fn get_exchange_rates_via_external_node(&self) -> Result<ExchangeRates, ExternalNodeError> {
+        let connection = self.external_node_connection()?;
+
+        // Get the values from the external node.
+        connection.with_client(None, |block_identifier, mut client| async move {
+            let (euro_per_energy, micro_ccd_per_euro) =
+                match client.get_block_chain_parameters(block_identifier).await?.response {
+                    sdk::v2::ChainParameters::V0(p) => (p.euro_per_energy, p.micro_ccd_per_euro),
+                    sdk::v2::ChainParameters::V1(p) => (p.euro_per_energy, p.micro_ccd_per_euro),
+                    sdk::v2::ChainParameters::V2(p) => (p.euro_per_energy, p.micro_ccd_per_euro),
+                };
+            Ok(ExchangeRates {
+                euro_per_energy,
+                micro_ccd_per_euro,
+            })
+        })
+    }
+
+    /// Tick the block time on the [`Chain`] by a [`Duration`].
+    ///
+    /// Returns an error if ticking causes the block time to overflow.
+    ///
+    /// Exchange rates scheduled with [`Chain::schedule_exchange_rates`] take
+    /// effect if their effective time is reached.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    ///
+    /// // Block time defaults to 0.
+    /// let mut chain = Chain::new();
+    ///
+    /// // Increase block time by 123 milliseconds.
+    /// chain.tick_block_time(Duration::from_millis(123)).unwrap();
+    ///
+    /// // Block time has now increased.
+    /// assert_eq!(chain.block_time(), Timestamp::from_timestamp_millis(123));
+    /// ```
+    pub fn tick_block_time(&mut self, duration: Duration) -> Result<(), BlockTimeOverflow> {
+        self.parameters.block_time =
+            self.parameters.block_time.checked_add(duration).ok_or(BlockTimeOverflow)?;
+        self.apply_scheduled_exchange_rates();
+        Ok(())
+    }
+
+    /// Tick the block time on the [`Chain`] by a [`std::time::Duration`].
+    ///
+    /// The block time has millisecond precision, which is also the precision
+    /// of the slot time seen by contracts, so the duration is rounded down to
+    /// whole milliseconds. Otherwise this is the same as
+    /// [`Chain::tick_block_time`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// chain.tick_block_time_std(std::time::Duration::from_secs(2)).unwrap();
+    /// assert_eq!(chain.block_time(), Timestamp::from_timestamp_millis(2000));
+    /// ```
+    pub fn tick_block_time_std(
+        &mut self,
+        duration: std::time::Duration,
+    ) -> Result<(), BlockTimeOverflow> {
+        let millis = u64::try_from(duration.as_millis()).map_err(|_| BlockTimeOverflow)?;
+        self.tick_block_time(Duration::from_millis(millis))
+    }
+
+    /// Set the block time on the [`Chain`].
+    ///
+    /// Exchange rates scheduled with [`Chain::schedule_exchange_rates`] take
+    /// effect if their effective time is reached. Setting the block time to an
+    /// earlier time does not revert exchange rates that have already taken
+    /// effect.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// chain.set_block_time(Timestamp::from_timestamp_millis(5000));
+    /// assert_eq!(chain.block_time(), Timestamp::from_timestamp_millis(5000));
+    /// ```
+    pub fn set_block_time(&mut self, block_time: Timestamp) {
+        self.parameters.block_time = block_time;
+        self.apply_scheduled_exchange_rates();
+    }
+
+    /// Register a callback which is called whenever a contract observes its
+    /// own balance during a contract update or invocation.
+    ///
+    /// The callback is given the address of the contract and its balance. A
+    /// contract observes its balance when it starts executing, which includes
+    /// the amount it was sent, and again when it resumes after an interrupt,
+    /// e.g., after transferring CCD or calling another contract. The balance
+    /// returned by `self_balance` in the contract is the one most recently
+    /// observed.
+    ///
+    /// Registering a new callback replaces the previous one. Forks of the
+    /// chain share the callback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let mut chain = Chain::new();
+    /// let observed = Arc::new(Mutex::new(Vec::new()));
+    /// let observed_hook = Arc::clone(&observed);
+    /// chain.on_self_balance_query(move |address, balance| {
+    ///     observed_hook.lock().unwrap().push((address, balance));
+    /// });
+    /// ```
+    pub fn on_self_balance_query(
+        &mut self,
+        hook: impl Fn(ContractAddress, Amount) + Send + Sync + 'static,
+    ) {
+        self.hooks.self_balance_query = Some(Arc::new(hook));
+    }
+
+    /// Register a callback which is called when a contract upgrades its
+    /// module in [`Chain::contract_update`] or [`Chain::contract_invoke`].
+    ///
+    /// The callback is given the old and new module references and a snapshot
+    /// of the state of the contract at the time of the upgrade, which
+    /// includes the changes the contract made before upgrading. Together with
+    /// the state after the update, e.g., from [`Chain::contract_state_lookup`],
+    /// this makes it possible to check that a state migration run after the
+    /// upgrade transformed the state correctly.
+    ///
+    /// The callback is called as soon as the upgrade succeeds, so it is also
+    /// called for upgrades which are later rolled back because the update
+    /// fails. Failed upgrades, e.g., to a module which does not exist, are not
+    /// reported.
+    ///
+    /// Registering a new callback replaces the previous one. Forks of the
+    /// chain share the callback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// chain.on_upgrade(|upgrade| {
+    ///     println!(
+    ///         "{} upgraded from {} to {} with {} state entries",
+    ///         upgrade.address,
+    ///         upgrade.from,
+    ///         upgrade.to,
+    ///         upgrade.state.entries().len()
+    ///     );
+    /// });
+    /// ```
+    pub fn on_upgrade(&mut self, hook: impl Fn(&UpgradeEvent) + Send + Sync + 'static) {
+        self.hooks.upgrade = Some(Arc::new(hook));
+    }
+
+    /// Set the maximum energy that the transactions in a block may use, or
+    /// remove the limit with `None`. By default, there is no limit.
+    ///
+    /// Like in the node, a transaction is rejected before it is executed if
+    /// its energy does not fit in the energy remaining in the current block.
+    /// For contract initializations and updates, the energy reserved is
+    /// compared with the remaining energy, whereas the energy used is what
+    /// counts towards the limit. Rejected transactions are not charged.
+    ///
+    /// All transactions count towards the limit, i.e., module deployments,
+    /// contract initializations and updates, and transfers. Use
+    /// [`Chain::new_block`] to start a new block with all the block energy
+    /// available.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// chain.set_block_energy_limit(Some(Energy::from(3_000_000)));
+    /// assert_eq!(chain.block_energy_remaining(), Some(Energy::from(3_000_000)));
+    /// ```
+    pub fn set_block_energy_limit(&mut self, limit: Option<Energy>) {
+        self.block_energy_limit = limit;
+    }
+
+    /// The maximum energy that the transactions in a block may use, if any.
+    pub fn block_energy_limit(&self) -> Option<Energy> { self.block_energy_limit }
+
+    /// Set the maximum depth of nested contract calls, or remove the limit
+    /// with `None`, which is the default.
+    ///
+    /// The contract called by a transaction is at depth 0, and each call to
+    /// another contract increases the depth by one, as for
+    /// [`CallFrame::depth`]. A call to a contract which would be deeper than
+    /// the maximum depth is not made, so a maximum of 0 prevents all calls
+    /// between contracts. The call fails for the calling contract with
+    /// [`InvokeFailure::RuntimeError`](v1::InvokeFailure::RuntimeError), as if
+    /// the called contract trapped. The calling contract can then handle the
+    /// failure or fail itself. This makes it possible to test that recursive
+    /// contracts terminate. The chain has no such limit, so calls are
+    /// otherwise only limited by the energy available.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// chain.set_max_call_depth(Some(8));
+    /// assert_eq!(chain.max_call_depth(), Some(8));
+    /// ```
+    pub fn set_max_call_depth(&mut self, max_depth: Option<usize>) {
+        self.max_call_depth = max_depth;
+    }
+
+    /// The maximum depth of nested contract calls, if any.
+    pub fn max_call_depth(&self) -> Option<usize> { self.max_call_depth }
+
+    /// Set the maximum number of state entries a single contract update or
+    /// invocation may write, or remove the limit with `None`, which is the
+    /// default.
+    ///
+    /// The writes are counted as described for [`StateWrites`], across all
+    /// the contracts called by the update, and are reported in
+    /// [`ContractInvokeSuccess::state_writes`]. An update which makes more
+    /// writes fails with [`ContractInvokeErrorKind::StateWritesExceeded`],
+    /// and none of its changes are persisted. This makes it possible to test
+    /// that the storage used by an entrypoint stays bounded. The chain has no
+    /// such limit, so writes are otherwise only limited by the energy
+    /// available.
+    ///
+    /// The writes are made by host functions of the engine, so they are
+    /// counted each time a contract returns, is interrupted by a call or
+    /// query, or traps. The update fails at that point, and is charged for
+    /// the energy used until then. The error reports the writes up to the
+    /// maximum.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// chain.set_max_state_writes_per_update(Some(100));
+    /// assert_eq!(chain.max_state_writes_per_update(), Some(100));
+    /// ```
+    pub fn set_max_state_writes_per_update(&mut self, max_writes: Option<usize>) {
+        self.max_state_writes = max_writes;
+    }
+
+    /// The maximum number of state entries an update may write, if any.
+    pub fn max_state_writes_per_update(&self) -> Option<usize> { self.max_state_writes }
+
+    /// Set an event schema that the events logged in successful contract
+    /// updates and invocations are checked against, or `None` for not
+    /// checking the events, which is the default.
+    ///
+    /// The events that cannot be decoded with the schema, including those
+    /// with bytes left over after decoding, are reported as
+    /// [`log_warnings`](ContractInvokeSuccess::log_warnings) on the result.
+    /// The update still succeeds. This is useful for catching events that an
+    /// off-chain indexer would not be able to parse.
+    ///
+    /// The same schema is used for the events of all contracts. The event
+    /// schema of a contract can be obtained with
+    /// [`VersionedModuleSchema::get_event_schema`](schema::VersionedModuleSchema::get_event_schema).
+    pub fn set_strict_logs(&mut self, event_schema: Option<schema::Type>) {
+        self.strict_logs = event_schema;
+    }
+
+    /// The event schema set with [`Chain::set_strict_logs`], if any.
+    pub fn strict_logs(&self) -> Option<&schema::Type> { self.strict_logs.as_ref() }
+
+    /// The protocol version whose rules are used when deploying modules, see
+    /// [`ChainBuilder::protocol_version`].
+    pub fn protocol_version(&self) -> ProtocolVersion { self.protocol_version }
+
+    /// Allow modules deployed from now on with [`Chain::module_deploy_v1`]
+    /// to use the debug host functions.
+    ///
+    /// This makes it possible to deploy modules built with debug output, for
+    /// example with `cargo concordium build --allow-debug`. The messages the
+    /// contracts print with `concordium_dbg!` can then be read from the
+    /// result of an update or invocation with
+    /// [`debug_messages`](DebugInfoExt::debug_messages).
+    ///
+    /// Such modules are not valid on the chain, where debug output is never
+    /// allowed.
+    pub fn enable_debug_output(&mut self) { self.debug_output = true; }
+
+    /// Whether debug output has been enabled with
+    /// [`Chain::enable_debug_output`].
+    pub fn debug_output_enabled(&self) -> bool { self.debug_output }
+
+    /// The energy used by the transactions in the current block.
+    pub fn block_energy_used(&self) -> Energy { self.block_energy_used }
+
+    /// The energy remaining in the current block, or `None` if there is no
+    /// block energy limit.
+    pub fn block_energy_remaining(&self) -> Option<Energy> {
+        self.block_energy_limit
+            .map(|limit| limit.checked_sub(self.block_energy_used).unwrap_or_else(|| 0.into()))
+    }
+
+    /// Start a new block, which increments the block height and resets the
+    /// energy used in the current block.
+    ///
+    /// The block time is not changed, see [`Chain::tick_block_time`].
+    pub fn new_block(&mut self) {
+        self.block_energy_used = 0.into();
+        self.block_height += 1;
+    }
+
+    /// The height of the current block.
+    ///
+    /// The height starts at 0 and is only increased by [`Chain::new_block`]
+    /// and [`Chain::warp_to_block_height`], independently of the block time.
+    /// It is useful for testing scenarios with a notion of blocks, such as
+    /// per-block rate limits, in which the test drives both the height and
+    /// the time.
+    ///
+    /// Contracts cannot query the block height, since there is no host
+    /// function for it. A contract only sees the block time, as the slot time
+    /// of its chain metadata.
+    pub fn block_height(&self) -> u64 { self.block_height }
+
+    /// Start a new block at the given height, e.g., to skip a number of
+    /// blocks in which nothing happens.
+    ///
+    /// Like [`Chain::new_block`], this resets the energy used in the current
+    /// block, unless `height` is the current height, in which case nothing
+    /// changes. The block time is not changed.
+    ///
+    /// Returns an error if `height` is below the current height, as the
+    /// height never decreases.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// chain.new_block();
+    /// assert_eq!(chain.block_height(), 1);
+    ///
+    /// chain.warp_to_block_height(100).unwrap();
+    /// assert_eq!(chain.block_height(), 100);
+    /// assert!(chain.warp_to_block_height(99).is_err());
+    /// ```
+    pub fn warp_to_block_height(&mut self, height: u64) -> Result<(), BlockHeightDecrease> {
+        if height < self.block_height {
+            return Err(BlockHeightDecrease {
+                current:   self.block_height,
+                requested: height,
+            });
+        }
+        if height > self.block_height {
+            self.block_energy_used = 0.into();
+            self.block_height = height;
+        }
+        Ok(())
+    }
+
+    /// Whether a transaction with the given energy fits in the current block.
+    fn fits_in_block(&self, energy: Energy) -> bool {
+        self.block_energy_remaining().map_or(true, |remaining| energy <= remaining)
+    }
+
+    /// Include a transaction sent from the account `sender` which used the
+    /// given energy in the current block, and return the index of the
+    /// transaction.
+    ///
+    /// This increments the nonce of the sender and adds the transaction fee,
+    /// which must already have been charged, to the fees collected.
+    fn include_transaction(
+        &mut self,
+        sender: AccountAddress,
+        energy: Energy,
+        transaction_fee: Amount,
+    ) -> u64 {
+        self.account_mut(sender).expect("Account known to exist").nonce += 1;
+        self.block_energy_used = self.block_energy_used + energy;
+        self.fees_collected += transaction_fee;
+        let transaction_index = self.transaction_count;
+        self.transaction_count += 1;
+        transaction_index
+    }
+
+    /// Return the events emitted by contracts since the chain was created, in
+    /// the order they were emitted.
+    ///
+    /// Only events from successful initializations and updates are included,
+    /// as events from failed transactions are never committed. Each event
+    /// carries the index of the transaction which emitted it, which counts all
+    /// the transactions the chain has executed, including failed ones.
+    ///
+    /// Restoring a [`Checkpoint`] also restores the event log, so events from
+    /// the transactions rolled back are removed.
+    pub fn event_log(&self) -> &[ChainEvent] { &self.event_log }
+
+    /// Run `scenario` and return the points at which a call made by the
+    /// contract at `address` changed the state of the contract before it
+    /// resumed, i.e., where the contract was reentered and modified.
+    ///
+    /// Each point is reported with the entrypoint and the call depth of the
+    /// contract when it resumed. They are found by comparing the modification
+    /// index of the contract when it made each call with the one it had when
+    /// it resumed, see [`ModificationCheck`]. Only the calls made in
+    /// successful updates with [`Chain::contract_update`] are considered, as
+    /// the changes made by failed updates are rolled back.
+    ///
+    /// An empty result means that the contract was never modified by the
+    /// contracts it called in the scenario. This does not prove that the
+    /// contract is safe from reentrancy, only that the scenario did not
+    /// exploit it.
+    pub fn detect_reentrancy(
+        &mut self,
+        address: ContractAddress,
+        scenario: impl FnOnce(&mut Self),
+    ) -> Vec<ModificationCheck> {
+        let outer_log = self.reentrancy_log.replace(Vec::new());
+        scenario(self);
+        let checks = std::mem::replace(&mut self.reentrancy_log, outer_log).unwrap_or_default();
+        // Make the checks available to an enclosing scenario as well.
+        if let Some(outer_log) = &mut self.reentrancy_log {
+            outer_log.extend(checks.iter().cloned());
+        }
+        checks
+            .into_iter()
+            .filter(|check| check.address == address && check.index_before != check.index_after)
+            .collect()
     }
 
-    /// Convert a [`ContractInitErrorKind`] to a
-    /// [`ContractInitError`] by calculating the `energy_used` and
-    /// `transaction_fee`.
-    fn convert_to_init_error(
-        &self,
-        kind: ContractInitErrorKind,
-        energy_reserved: Energy,
-        remaining_energy: Energy,
-    ) -> ContractInitError {
-        let energy_used = energy_reserved - remaining_energy;
-        let transaction_fee = self.parameters.calculate_energy_cost(energy_used);
-        ContractInitError {
-            energy_used,
-            transaction_fee,
-            kind,
+    /// Create a [`Checkpoint`] of the current state of the chain.
+    ///
+    /// The chain can be reset to the checkpoint with
+    /// [`Chain::restore`](Self::restore). The contract states are shared with
+    /// the checkpoint until they are modified, but the accounts and the event
+    /// log are copied.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// let checkpoint = chain.checkpoint();
+    ///
+    /// chain.create_account(Account::new(AccountAddress([0; 32]), Amount::from_ccd(10)));
+    /// assert!(chain.account_exists(AccountAddress([0; 32])));
+    ///
+    /// chain.restore(&checkpoint);
+    /// assert!(!chain.account_exists(AccountAddress([0; 32])));
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            parameters:               self.parameters.clone(),
+            accounts:                 self.accounts.clone(),
+            modules:                  self.modules.clone(),
+            contracts:                self.contracts.clone(),
+            next_contract_index:      self.next_contract_index,
+            mocked_account_balances:  self.mocked_account_balances.clone(),
+            account_creation_times:   self.account_creation_times.clone(),
+            scheduled_exchange_rates: self.scheduled_exchange_rates.clone(),
+            block_energy_used:        self.block_energy_used,
+            block_height:             self.block_height,
+            module_tags:              self.module_tags.clone(),
+            event_log:                self.event_log.clone(),
+            transaction_count:        self.transaction_count,
+            fees_collected:           self.fees_collected,
         }
     }
 
-    /// Try to set the exchange rates on the chain.
+    /// Reset the chain to the state it had when the [`Checkpoint`] was
+    /// created.
     ///
-    /// Will fail if they result in the cost of one energy being larger than
-    /// `u64::MAX / 100_000_000_000`.
-    pub fn set_exchange_rates(
-        &mut self,
-        micro_ccd_per_euro: ExchangeRate,
-        euro_per_energy: ExchangeRate,
-    ) -> Result<(), ExchangeRateError> {
-        // Ensure the exchange rates are within a valid range.
-        check_exchange_rates(euro_per_energy, micro_ccd_per_euro)?;
-        self.parameters.micro_ccd_per_euro = micro_ccd_per_euro;
-        self.parameters.euro_per_energy = euro_per_energy;
-        Ok(())
+    /// The external node connection and the labelled checkpoints are kept.
+    pub fn restore(&mut self, checkpoint: &Checkpoint) { self.restore_owned(checkpoint.clone()) }
+
+    /// Reset the chain to the [`Checkpoint`], taking ownership of it.
+    fn restore_owned(&mut self, checkpoint: Checkpoint) {
+        self.parameters = checkpoint.parameters;
+        self.accounts = checkpoint.accounts;
+        self.modules = checkpoint.modules;
+        self.contracts = checkpoint.contracts;
+        self.next_contract_index = checkpoint.next_contract_index;
+        self.mocked_account_balances = checkpoint.mocked_account_balances;
+        self.account_creation_times = checkpoint.account_creation_times;
+        self.scheduled_exchange_rates = checkpoint.scheduled_exchange_rates;
+        self.block_energy_used = checkpoint.block_energy_used;
+        self.block_height = checkpoint.block_height;
+        self.module_tags = checkpoint.module_tags;
+        self.event_log = checkpoint.event_log;
+        self.transaction_count = checkpoint.transaction_count;
+        self.fees_collected = checkpoint.fees_collected;
     }
 
-    /// Get the microCCD per euro and euro per energy exchange rates by querying
-    /// an external node using the external query block.
-    fn get_exchange_rates_via_external_node(&self) -> Result<ExchangeRates, ExternalNodeError> {
-        let connection = self.external_node_connection()?;
+    /// Create an independent copy of the chain.
+    ///
+    /// The fork starts out with the same accounts, modules, contracts, block
+    /// time, exchange rates and labelled checkpoints as this chain, but
+    /// changes to either chain do not affect the other. This makes it possible
+    /// to set up a scenario once and then run variations of it, possibly on
+    /// separate threads.
+    ///
+    /// Forking is cheap, as the compiled modules and the contract states are
+    /// shared until they are modified.
+    ///
+    /// The external node connection, configured with
+    /// [`ChainBuilder::external_node_connection`], is not part of the fork,
+    /// so external accounts and contracts cannot be queried from it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// chain.create_account(Account::new(AccountAddress([0; 32]), Amount::from_ccd(10)));
+    ///
+    /// let mut fork = chain.fork();
+    /// fork.create_account(Account::new(AccountAddress([1; 32]), Amount::from_ccd(10)));
+    ///
+    /// assert!(fork.account_exists(AccountAddress([0; 32])));
+    /// assert!(!chain.account_exists(AccountAddress([1; 32])));
+    /// ```
+    pub fn fork(&self) -> Chain {
+        Chain {
+            parameters:               self.parameters.clone(),
+            accounts:                 self.accounts.clone(),
+            modules:                  self.modules.clone(),
+            contracts:                self.contracts.clone(),
+            next_contract_index:      self.next_contract_index,
+            external_node_connection: None,
+            max_state_key_length:     self.max_state_key_length,
+            max_call_depth:           self.max_call_depth,
+            max_state_writes:         self.max_state_writes,
+            strict_logs:              self.strict_logs.clone(),
+            default_account_balance:  self.default_account_balance,
+            mocked_account_balances:  self.mocked_account_balances.clone(),
+            account_creation_times:   self.account_creation_times.clone(),
+            protocol_version:         self.protocol_version,
+            debug_output:             self.debug_output,
+            scheduled_exchange_rates: self.scheduled_exchange_rates.clone(),
+            checkpoints:              self.checkpoints.clone(),
+            block_energy_limit:       self.block_energy_limit,
+            block_energy_used:        self.block_energy_used,
+            block_height:             self.block_height,
+            module_tags:              self.module_tags.clone(),
+            event_log:                self.event_log.clone(),
+            transaction_count:        self.transaction_count,
+            fees_collected:           self.fees_collected,
+            hooks:                    self.hooks.clone(),
+            reentrancy_log:           None,
+            recording:                None,
+        }
+    }
 
-        // Get the values from the external node.
-        connection.with_client(None, |block_identifier, mut client| async move {
-            let (euro_per_energy, micro_ccd_per_euro) =
-                match client.get_block_chain_parameters(block_identifier).await?.response {
-                    sdk::v2::ChainParameters::V0(p) => (p.euro_per_energy, p.micro_ccd_per_euro),
-                    sdk::v2::ChainParameters::V1(p) => (p.euro_per_energy, p.micro_ccd_per_euro),
-                    sdk::v2::ChainParameters::V2(p) => (p.euro_per_energy, p.micro_ccd_per_euro),
+    /// Compare the chain with `other`, which is treated as the newer of the
+    /// two, and return their differences.
+    ///
+    /// This is useful for checking that an operation, such as a migration of
+    /// a contract, only changed what it was supposed to. The usual way to
+    /// get the chain before the operation is with [`Chain::fork`] or by
+    /// loading a chain saved with [`Chain::persist`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let mut chain = Chain::new();
+    /// let before = chain.fork();
+    /// chain.create_account(Account::new(AccountAddress([0; 32]), Amount::from_ccd(10)));
+    ///
+    /// let diff = before.diff(&chain);
+    /// assert_eq!(diff.accounts, [AccountDiff {
+    ///     address:        AccountAddress([0; 32]),
+    ///     balance_before: None,
+    ///     balance_after:  Some(Amount::from_ccd(10)),
+    /// }]);
+    /// assert!(diff.contracts.is_empty());
+    /// ```
+    pub fn diff(&self, other: &Chain) -> ChainDiff {
+        let modules_added = other
+            .modules
+            .keys()
+            .filter(|module| !self.modules.contains_key(module))
+            .copied()
+            .collect();
+        let modules_removed = self
+            .modules
+            .keys()
+            .filter(|module| !other.modules.contains_key(module))
+            .copied()
+            .collect();
+
+        let account_addresses: BTreeSet<_> =
+            self.accounts.keys().chain(other.accounts.keys()).collect();
+        let accounts = account_addresses
+            .into_iter()
+            .filter_map(|address| {
+                let balance = |chain: &Chain| {
+                    chain.accounts.get(address).map(|account| account.balance.total)
                 };
-            Ok(ExchangeRates {
-                euro_per_energy,
-                micro_ccd_per_euro,
+                let (balance_before, balance_after) = (balance(self), balance(other));
+                let address =
+                    self.accounts.get(address).or_else(|| other.accounts.get(address))?.address;
+                (balance_before != balance_after).then_some(AccountDiff {
+                    address,
+                    balance_before,
+                    balance_after,
+                })
             })
-        })
+            .collect();
+
+        let contract_addresses: BTreeSet<_> =
+            self.contracts.keys().chain(other.contracts.keys()).collect();
+        let contracts = contract_addresses
+            .into_iter()
+            .filter_map(|address| {
+                let (before, after) = (self.contracts.get(address), other.contracts.get(address));
+                let entries = |contract: Option<&Contract>| {
+                    contract.map_or_else(Vec::new, |contract| state_entries(&contract.state.thaw()))
+                };
+                let diff = ContractDiff {
+                    address:        *address,
+                    balance_before: before.map(|contract| contract.self_balance),
+                    balance_after:  after.map(|contract| contract.self_balance),
+                    module_before:  before.map(|contract| contract.module_reference),
+                    module_after:   after.map(|contract| contract.module_reference),
+                    state:          StateDiff::between(entries(before), entries(after)),
+                };
+                let changed = diff.balance_before != diff.balance_after
+                    || diff.module_before != diff.module_after
+                    || !diff.state.is_empty();
+                changed.then_some(diff)
+            })
+            .collect();
+
+        ChainDiff {
+            block_time_before: self.parameters.block_time,
+            block_time_after: other.parameters.block_time,
+            modules_added,
+            modules_removed,
+            accounts,
+            contracts,
+        }
     }
 
-    /// Tick the block time on the [`Chain`] by a [`Duration`].
+    /// Compare the energy used by a batched operation against performing the
+    /// same operation one item at a time.
     ///
-    /// Returns an error if ticking causes the block time to overflow.
+    /// Both `batched` and `single` start from the current state of the chain
+    /// and must return the energy they used. `batched` is called once to
+    /// perform the operation on all `batch_size` items, and `single` is
+    /// called with the indices `0..batch_size` to perform the operation on
+    /// one item at a time. Afterwards, the chain is reset to its current
+    /// state, so the comparison has no lasting effects.
     ///
     /// # Example
     ///
-    /// ```
+    /// ```no_run
     /// # use concordium_smart_contract_testing::*;
+    /// # let mut chain = Chain::new();
+    /// # let account = AccountAddress([0; 32]);
+    /// # let contract = ContractAddress::new(0, 0);
+    /// # let batch_parameter = OwnedParameter::empty();
+    /// # let single_parameter = |_index: usize| OwnedParameter::empty();
+    /// let update = |chain: &mut Chain, message: OwnedParameter| {
+    ///     chain
+    ///         .contract_update(
+    ///             Signer::with_one_key(),
+    ///             account,
+    ///             Address::Account(account),
+    ///             Energy::from(100000),
+    ///             UpdateContractPayload {
+    ///                 amount: Amount::zero(),
+    ///                 address: contract,
+    ///                 receive_name: OwnedReceiveName::new_unchecked("contract.transfer".into()),
+    ///                 message,
+    ///             },
+    ///         )
+    ///         .unwrap()
+    ///         .energy_used
+    /// };
+    /// let comparison = chain.compare_batch_energy(
+    ///     10,
+    ///     |chain| update(chain, batch_parameter),
+    ///     |chain, index| update(chain, single_parameter(index)),
+    /// );
+    /// assert!(comparison.batch_is_cheaper());
+    /// ```
+    pub fn compare_batch_energy(
+        &mut self,
+        batch_size: usize,
+        batched: impl FnOnce(&mut Self) -> Energy,
+        mut single: impl FnMut(&mut Self, usize) -> Energy,
+    ) -> BatchEnergyComparison {
+        let checkpoint = self.checkpoint();
+        let batched = batched(self);
+        self.restore(&checkpoint);
+        let mut individual = Energy::from(0);
+        for index in 0..batch_size {
+            individual = individual + single(self, index);
+        }
+        self.restore_owned(checkpoint);
+        BatchEnergyComparison {
+            batched,
+            individual,
+        }
+    }
+
+    /// Save a [`Checkpoint`] of the current state of the chain under the given
+    /// label.
     ///
-    /// // Block time defaults to 0.
+    /// The chain can be reset to the checkpoint with
+    /// [`Chain::load`](Self::load). If a checkpoint already exists with the
+    /// label, it is replaced and returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
     /// let mut chain = Chain::new();
+    /// chain.save("empty");
     ///
-    /// // Increase block time by 123 milliseconds.
-    /// chain.tick_block_time(Duration::from_millis(123)).unwrap();
+    /// chain.create_account(Account::new(AccountAddress([0; 32]), Amount::from_ccd(10)));
+    /// assert!(chain.save("after-setup").is_none());
     ///
-    /// // Block time has now increased.
-    /// assert_eq!(chain.block_time(), Timestamp::from_timestamp_millis(123));
+    /// chain.load("empty").unwrap();
+    /// assert!(!chain.account_exists(AccountAddress([0; 32])));
+    ///
+    /// chain.load("after-setup").unwrap();
+    /// assert!(chain.account_exists(AccountAddress([0; 32])));
     /// ```
-    pub fn tick_block_time(&mut self, duration: Duration) -> Result<(), BlockTimeOverflow> {
-        self.parameters.block_time =
-            self.parameters.block_time.checked_add(duration).ok_or(BlockTimeOverflow)?;
+    pub fn save(&mut self, label: impl Into<String>) -> Option<Checkpoint> {
+        let checkpoint = self.checkpoint();
+        self.checkpoints.insert(label.into(), checkpoint)
+    }
+
+    /// Reset the chain to the [`Checkpoint`] saved under the given label with
+    /// [`Chain::save`](Self::save).
+    ///
+    /// The checkpoint is kept, so it can be loaded again.
+    ///
+    /// Returns an error if no checkpoint exists with the label.
+    pub fn load(&mut self, label: &str) -> Result<(), UnknownCheckpoint> {
+        let checkpoint = self.checkpoints.get(label).ok_or_else(|| UnknownCheckpoint {
+            label: label.to_string(),
+        })?;
+        self.restore_owned(checkpoint.clone());
         Ok(())
     }
 
@@ -1854,6 +4138,10 @@ impl Chain {
     /// Return the current euro per energy exchange rate.
     pub fn euro_per_energy(&self) -> ExchangeRate { self.parameters.euro_per_energy }
 
+    /// Return the maximum length of a key in the state of a contract
+    /// instance.
+    pub fn max_state_key_length(&self) -> usize { self.max_state_key_length }
+
     /// Return the current block time.
     pub fn block_time(&self) -> Timestamp { self.parameters.block_time }
 
@@ -1906,6 +4194,93 @@ impl ExternalNodeConnection {
     }
 }
 
+impl ContractSpec {
+    /// Create a [`ContractSpec`](Self) with an empty state and a balance of
+    /// zero.
+    pub fn new(
+        module_reference: ModuleReference,
+        contract_name: OwnedContractName,
+        owner: AccountAddress,
+    ) -> Self {
+        Self {
+            module_reference,
+            contract_name,
+            state: Vec::new(),
+            owner,
+            balance: Amount::zero(),
+        }
+    }
+
+    /// Add an entry to the initial state, replacing any entry with the same
+    /// key.
+    pub fn with_state_entry(mut self, key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> Self {
+        let key = key.into();
+        self.state.retain(|(existing, _)| *existing != key);
+        self.state.push((key, value.into()));
+        self
+    }
+
+    /// Set the balance of the contract.
+    pub fn with_balance(mut self, balance: Amount) -> Self {
+        self.balance = balance;
+        self
+    }
+}
+
+impl ChainDiff {
+    /// Whether the chains have no differences, apart from their block time.
+    pub fn is_empty(&self) -> bool {
+        self.modules_added.is_empty()
+            && self.modules_removed.is_empty()
+            && self.accounts.is_empty()
+            && self.contracts.is_empty()
+    }
+
+    /// The time the block time moved forward between the two chains, or
+    /// `None` if the block time of the second chain is earlier.
+    pub fn block_time_delta(&self) -> Option<Duration> {
+        self.block_time_after.duration_since(self.block_time_before)
+    }
+}
+
+impl StateDiff {
+    /// Compute the changes between two lists of state entries, each ordered by
+    /// their keys.
+    pub(crate) fn between(before: Vec<(Vec<u8>, Vec<u8>)>, after: Vec<(Vec<u8>, Vec<u8>)>) -> Self {
+        let mut diff = Self::default();
+        let mut after: BTreeMap<_, _> = after.into_iter().collect();
+        for (key, before) in before {
+            match after.remove(&key) {
+                None => diff.removed.push((key, before)),
+                Some(after) if after != before => diff.changed.push(StateEntryChange {
+                    key,
+                    before,
+                    after,
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.added = after.into_iter().collect();
+        diff
+    }
+
+    /// Whether the states are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl StateView {
+    /// Look up the value stored under `key`, if any.
+    pub fn lookup(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let mut loader = v1::trie::Loader::new(&[][..]);
+        self.0.lookup(&mut loader, key)
+    }
+
+    /// All the entries of the state, ordered by their keys.
+    pub fn entries(&self) -> Vec<(Vec<u8>, Vec<u8>)> { state_entries(&self.0.thaw()) }
+}
+
 impl Account {
     /// Create new [`Account`](Self) with the provided account policy and keys.
     pub fn new_with_policy_and_keys(
@@ -1919,6 +4294,7 @@ impl Account {
             policy,
             address,
             keys,
+            nonce: 1,
         }
     }
 
@@ -1934,9 +4310,47 @@ impl Account {
             policy: Self::empty_policy(),
             address,
             keys,
+            nonce: 1,
         }
     }
 
+    /// Create new [`Account`](Self) with the provided total balance of which
+    /// `staked` is staked, e.g., as a baker or delegator.
+    ///
+    /// The staked amount is included in the total balance, and thus in the
+    /// balance returned when contracts query the account, but it cannot be
+    /// transferred. Pending changes to the stake are not represented, since
+    /// they are not visible to contracts.
+    ///
+    /// Returns `None` if `staked` exceeds `total_balance`.
+    /// See [`new`][Self::new] for what the default policy and keys are.
+    pub fn new_with_stake(
+        address: AccountAddress,
+        total_balance: Amount,
+        staked: Amount,
+    ) -> Option<Self> {
+        let balance = AccountBalance::new(total_balance, staked, Amount::zero())?;
+        Some(Self::new_with_balance(address, balance))
+    }
+
+    /// Create new [`Account`](Self) with the provided total balance of which
+    /// `locked` is locked in a release schedule.
+    ///
+    /// Like a stake, the locked amount is included in the total balance but
+    /// cannot be transferred, see [`AccountBalance::available`]. The release
+    /// schedule itself is not represented, so the amount stays locked.
+    ///
+    /// Returns `None` if `locked` exceeds `total_balance`.
+    /// See [`new`][Self::new] for what the default policy and keys are.
+    pub fn new_with_locked(
+        address: AccountAddress,
+        total_balance: Amount,
+        locked: Amount,
+    ) -> Option<Self> {
+        let balance = AccountBalance::new(total_balance, Amount::zero(), locked)?;
+        Some(Self::new_with_balance(address, balance))
+    }
+
     /// Create new [`Account`](Self) with the provided account policy.
     /// The account keys are initialized with an [`AccountAccessStructure`]
     /// with a threshold of 1, and no keys. So it is impossible to verify any
@@ -1986,6 +4400,18 @@ impl Account {
         )
     }
 
+    /// Create new [`Account`](Self) with the address and keys of an account
+    /// exported from the Concordium browser wallet, see
+    /// [`WalletExport::from_json`].
+    ///
+    /// See [`new`][Self::new] for what the default policy is.
+    pub fn new_from_wallet_export(
+        json: &str,
+        total_balance: Amount,
+    ) -> Result<Self, WalletExportError> {
+        Ok(WalletExport::from_json(json)?.account(total_balance))
+    }
+
     /// Helper for creating an empty policy.
     ///
     /// It has identity provider `0`, no items, and is valid from unix epoch
@@ -1998,6 +4424,145 @@ impl Account {
             items:             Vec::new(),
         }
     }
+
+    /// Whether the account has the policy given by [`Account::empty_policy`].
+    fn has_empty_policy(&self) -> bool {
+        let empty = Self::empty_policy();
+        self.policy.identity_provider == empty.identity_provider
+            && self.policy.created_at == empty.created_at
+            && self.policy.valid_to == empty.valid_to
+            && self.policy.items.is_empty()
+    }
+}
+
+impl WalletExport {
+    /// Parse an account export from the Concordium browser wallet.
+    ///
+    /// The export is a JSON object of the form
+    ///
+    /// ```json
+    /// {
+    ///   "type": "concordium-browser-wallet-account",
+    ///   "v": 0,
+    ///   "value": {
+    ///     "address": "<base58 encoded address>",
+    ///     "accountKeys": {
+    ///       "keys": {
+    ///         "0": {
+    ///           "keys": { "0": { "signKey": "<hex>", "verifyKey": "<hex>" } },
+    ///           "threshold": 1
+    ///         }
+    ///       },
+    ///       "threshold": 1
+    ///     }
+    ///   }
+    /// }
+    /// ```
+    ///
+    /// Other fields, such as the credentials and the environment, are
+    /// ignored. Only version `0` of the format is supported.
+    pub fn from_json(json: &str) -> Result<Self, WalletExportError> {
+        let export: serde_json::Value = serde_json::from_str(json)?;
+        let export_type = export.get("type").and_then(|value| value.as_str());
+        if export_type != Some(WALLET_EXPORT_TYPE) {
+            return Err(WalletExportError::UnsupportedType(export_type.map(String::from)));
+        }
+        let version = export.get("v").and_then(|value| value.as_u64());
+        if version != Some(WALLET_EXPORT_VERSION) {
+            return Err(WalletExportError::UnsupportedVersion {
+                found:    version,
+                expected: WALLET_EXPORT_VERSION,
+            });
+        }
+        let value = &export["value"];
+        let address: String = serde_json::from_value(value["address"].clone())?;
+        let keys: AccountKeys = serde_json::from_value(value["accountKeys"].clone())?;
+        Ok(Self {
+            address: address
+                .parse()
+                .map_err(|_| WalletExportError::InvalidAddress(address.clone()))?,
+            keys,
+        })
+    }
+
+    /// Create an [`Account`] with the address and the public keys of the
+    /// export and the provided total balance.
+    ///
+    /// See [`Account::new`] for what the default policy is.
+    pub fn account(&self, total_balance: Amount) -> Account {
+        Account::new_with_keys(
+            self.address,
+            AccountBalance {
+                total:  total_balance,
+                staked: Amount::zero(),
+                locked: Amount::zero(),
+            },
+            AccountAccessStructure::from(&self.keys),
+        )
+    }
+}
+
+/// Derive an account address from a label, by hashing the label with
+/// SHA-256.
+///
+/// This gives readable and deterministic addresses in tests. Different labels
+/// give different accounts, as the chance of two labels giving aliases of the
+/// same account is negligible. See also [`Chain::create_funded_account`].
+pub fn account_address_from_label(label: &str) -> AccountAddress {
+    AccountAddress(Sha256::digest(label.as_bytes()).into())
+}
+
+/// Build the payload for initializing a contract, checking that `init_name`
+/// is a valid init name, e.g. `"init_my_contract"`.
+///
+/// Unlike with [`OwnedContractName::new_unchecked`], a malformed name, such as
+/// one that is missing the `init_` prefix, is longer than 100 bytes or
+/// contains characters that are not ASCII alphanumeric or punctuation, is
+/// rejected here instead of making the transaction fail.
+pub fn init_payload(
+    mod_ref: ModuleReference,
+    init_name: &str,
+    param: OwnedParameter,
+    amount: Amount,
+) -> Result<InitContractPayload, NameError> {
+    let init_name =
+        OwnedContractName::new(init_name.to_string()).map_err(|error| NameError::InitName {
+            name: init_name.to_string(),
+            error,
+        })?;
+    Ok(InitContractPayload {
+        amount,
+        mod_ref,
+        init_name,
+        param,
+    })
+}
+
+/// Build the payload for updating a contract, checking that `receive_name` is
+/// a valid receive name, e.g. `"my_contract.view"`.
+///
+/// Unlike with [`OwnedReceiveName::new_unchecked`], a malformed name, such as
+/// one that is missing the `.` separator, is longer than 100 bytes or contains
+/// characters that are not ASCII alphanumeric or punctuation, is rejected here
+/// instead of making the transaction fail.
+pub fn update_payload(
+    address: ContractAddress,
+    receive_name: &str,
+    message: OwnedParameter,
+    amount: Amount,
+) -> Result<UpdateContractPayload, NameError> {
+    let receive_name = OwnedReceiveName::new(receive_name.to_string()).map_err(|error| {
+        NameError::ReceiveName {
+            name: receive_name.to_string(),
+            error,
+        }
+    })?;
+    Ok(UpdateContractPayload {
+        amount,
+        address,
+        receive_name,
+        message,
+    })
 }
 
 /// Load a raw wasm module, i.e. one **without** the prefix of 4 version
@@ -2057,7 +4622,148 @@ pub fn module_load_v1(module_path: impl AsRef<Path>) -> Result<WasmModule, Modul
             kind: ModuleLoadErrorKind::UnsupportedModuleVersion(module.version),
         });
     }
-    Ok(module)
+    Ok(module)
+}
+
+/// Parse a v1 wasm module from bytes as they are output from `cargo concordium
+/// build`, i.e. **including** the prefix of 4 version bytes and 4 module length
+/// bytes.
+///
+/// This is the in-memory counterpart of [`module_load_v1`], and performs the
+/// same checks.
+pub fn module_load_v1_from_bytes(module_bytes: &[u8]) -> Result<WasmModule, ModuleLoadErrorKind> {
+    let module: WasmModule = base::common::from_bytes(&mut std::io::Cursor::new(module_bytes))
+        .map_err(|e| ModuleLoadErrorKind::ReadModule(e.into()))?;
+    if module.version != WasmVersion::V1 {
+        return Err(ModuleLoadErrorKind::UnsupportedModuleVersion(module.version));
+    }
+    Ok(module)
+}
+
+/// The names of the custom sections in which `cargo concordium` embeds the
+/// schema of a module, for the different versions of the schema.
+const SCHEMA_SECTION_NAMES: [&str; 3] =
+    ["concordium-schema", "concordium-schema-v1", "concordium-schema-v2"];
+
+/// Parse a section of a wasm module with the parser of the engine, or return
+/// the empty section if the module does not have it.
+///
+/// Returns `None` if the section cannot be parsed.
+fn parse_wasm_section<'a, A>(section: &Option<wasm::parse::UnparsedSection<'a>>) -> Option<A>
+where
+    A: wasm::parse::Parseable<'a, ()> + Default, {
+    wasm::parse::parse_sec_with_default(wasm::parse::EMPTY_CTX, section).ok()
+}
+
+/// Get the names of the custom sections of a wasm module, in the order they
+/// appear.
+///
+/// If the module cannot be parsed, no names are returned, and if the name of
+/// a custom section cannot be parsed, the names before it are returned.
+fn wasm_custom_sections(module_bytes: &[u8]) -> Vec<String> {
+    let Ok(skeleton) = wasm::parse::parse_skeleton(module_bytes) else {
+        return Vec::new();
+    };
+    skeleton
+        .custom
+        .iter()
+        .map_while(|section| wasm::parse::parse_custom(section).ok())
+        .map(|section| section.name.name)
+        .collect()
+}
+
+/// Get the imports of a wasm module as pairs of the module name and the name of
+/// the imported item, in the order they appear in the import section.
+///
+/// The module is expected to be valid. If the import section cannot be parsed,
+/// no imports are returned.
+fn wasm_imports(module_bytes: &[u8]) -> Vec<(String, String)> {
+    let Ok(skeleton) = wasm::parse::parse_skeleton(module_bytes) else {
+        return Vec::new();
+    };
+    let imports: wasm::types::ImportSection =
+        parse_wasm_section(&skeleton.import).unwrap_or_default();
+    imports
+        .imports
+        .into_iter()
+        .map(|import| (import.mod_name.name, import.item_name.name))
+        .collect()
+}
+
+/// Write an unsigned LEB128 encoded `u32`.
+fn write_wasm_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Rebuild the wasm module of the `skeleton` with the function bodies of the
+/// `code` section, replacing the bodies from the `from`th defined function and
+/// onwards with `unreachable`.
+fn wasm_with_unreachable_bodies(
+    skeleton: &wasm::parse::Skeleton,
+    code: &wasm::parse::CodeSkeletonSection,
+    from: usize,
+) -> Vec<u8> {
+    let mut code_section = Vec::new();
+    write_wasm_u32(&mut code_section, code.impls.len() as u32);
+    for (index, body) in code.impls.iter().enumerate() {
+        if index >= from {
+            // No locals, `unreachable` and `end`.
+            code_section.extend_from_slice(&[3, 0x00, 0x00, 0x0b]);
+            continue;
+        }
+        let mut bytes = Vec::new();
+        write_wasm_u32(&mut bytes, body.locals.len() as u32);
+        for local in body.locals.iter() {
+            write_wasm_u32(&mut bytes, local.multiplicity);
+            bytes.push(match local.ty {
+                wasm::types::ValueType::I32 => 0x7f,
+                wasm::types::ValueType::I64 => 0x7e,
+            });
+        }
+        bytes.extend_from_slice(body.expr_bytes);
+        write_wasm_u32(&mut code_section, bytes.len() as u32);
+        code_section.extend_from_slice(&bytes);
+    }
+
+    let mut out = b"\x00asm\x01\x00\x00\x00".to_vec();
+    let mut write_section = |section_id: u8, content: &[u8]| {
+        out.push(section_id);
+        write_wasm_u32(&mut out, content.len() as u32);
+        out.extend_from_slice(content);
+    };
+    // The sections in the order given by the wasm specification. The custom
+    // sections are not needed for validating the module.
+    let sections = [
+        (1, &skeleton.ty),
+        (2, &skeleton.import),
+        (3, &skeleton.func),
+        (4, &skeleton.table),
+        (5, &skeleton.memory),
+        (6, &skeleton.global),
+        (7, &skeleton.export),
+        (8, &skeleton.start),
+        (9, &skeleton.element),
+    ];
+    for (section_id, section) in sections {
+        if let Some(section) = section {
+            write_section(section_id, section.bytes);
+        }
+    }
+    if skeleton.code.is_some() {
+        write_section(10, &code_section);
+    }
+    if let Some(section) = &skeleton.data {
+        write_section(11, section.bytes);
+    }
+    out
 }
 
 /// Load the current smart contract module output using the environment variable
@@ -2088,7 +4794,63 @@ impl Signer {
     }
 }
 
+impl AmountDelta {
+    /// Create the delta which changes the amount `before` into `after`.
+    pub fn between(before: Amount, after: Amount) -> Self {
+        if after >= before {
+            Self::Positive(after.subtract_micro_ccd(before.micro_ccd))
+        } else {
+            Self::Negative(before.subtract_micro_ccd(after.micro_ccd))
+        }
+    }
+}
+
+impl ModuleInvalidError {
+    /// The validation rule that the module broke.
+    ///
+    /// The reason is diagnosed by inspecting the module after the validation
+    /// failed. It is [`ModuleInvalidReason::Other`] if the module breaks a rule
+    /// that is not diagnosed, in which case the error message has the details.
+    pub fn reason(&self) -> &ModuleInvalidReason { &self.reason }
+}
+
 impl ContractInvokeError {
+    /// Whether the invocation ran out of the energy reserved for it.
+    pub fn is_out_of_energy(&self) -> bool {
+        matches!(self.kind, ContractInvokeErrorKind::OutOfEnergy { .. })
+    }
+
+    /// Whether a contract rejected the invocation on its own, i.e., returned
+    /// an error code. See [`Self::reject_code`] for the code.
+    pub fn is_reject(&self) -> bool { self.reject_code().is_some() }
+
+    /// Whether the execution of a contract trapped, e.g., because it
+    /// panicked.
+    pub fn is_trap(&self) -> bool {
+        matches!(self.kind, ContractInvokeErrorKind::ExecutionError {
+            failure_kind: v1::InvokeFailure::RuntimeError,
+        })
+    }
+
+    /// Whether the invocation failed before any contract code was executed,
+    /// e.g., because the invoker cannot pay for the energy or the contract
+    /// does not exist. Failures while handling an interrupt, such as a call to
+    /// a missing entrypoint, are reported by the calling contract instead.
+    pub fn is_precondition_failure(&self) -> bool {
+        matches!(
+            self.kind,
+            ContractInvokeErrorKind::ModuleDoesNotExist(_)
+                | ContractInvokeErrorKind::ContractDoesNotExist(_)
+                | ContractInvokeErrorKind::EntrypointDoesNotExist(_)
+                | ContractInvokeErrorKind::InvokerDoesNotExist(_)
+                | ContractInvokeErrorKind::SenderDoesNotExist(_)
+                | ContractInvokeErrorKind::InsufficientFunds
+                | ContractInvokeErrorKind::AmountTooLarge
+                | ContractInvokeErrorKind::ParameterTooLarge
+                | ContractInvokeErrorKind::OutOfBlockEnergy
+        )
+    }
+
     /// Try to extract the value returned.
     ///
     /// This only returns `Some` if the contract rejected on its own.
@@ -2122,6 +4884,54 @@ impl ContractInvokeError {
         }
     }
 
+    /// Whether a contract rejected because it tried to send more CCD than its
+    /// balance, either to an account or to another contract.
+    ///
+    /// This is recognized by the reject codes that `concordium-std` uses for
+    /// `TransferError::AmountTooLarge` and `CallContractError::AmountTooLarge`,
+    /// so it only applies to contracts that reject with these errors, e.g.,
+    /// with the `?` operator. It is different from the invoker not being able
+    /// to pay for the energy or the amount of the update, in which case no
+    /// contract is run, see [`ContractInvokeErrorKind::InsufficientFunds`] and
+    /// [`ContractInvokeErrorKind::AmountTooLarge`].
+    pub fn is_insufficient_funds_reject(&self) -> bool {
+        matches!(
+            self.reject_code(),
+            Some(
+                constants::REJECT_TRANSFER_AMOUNT_TOO_LARGE
+                    | constants::REJECT_CALL_AMOUNT_TOO_LARGE
+            )
+        )
+    }
+
+    /// If the contract execution rejected the transaction, this returns the
+    /// name of the error that the reject code corresponds to.
+    ///
+    /// The error schema of the receive function `receive_name` is looked up
+    /// in `schema`. When it is an enum, the reject code `-1` corresponds to
+    /// the first variant, `-2` to the second, and so on, as produced by
+    /// deriving `Reject` in `concordium-std`. If no error schema is present,
+    /// or the code does not correspond to a variant, the code itself is
+    /// returned instead, e.g. `"-17"`.
+    ///
+    /// Returns `None` if the contract did not reject.
+    pub fn reject_reason_pretty(
+        &self,
+        schema: &schema::VersionedModuleSchema,
+        receive_name: ReceiveName,
+    ) -> Option<String> {
+        let code = self.reject_code()?;
+        let (contract_name, function_name) = split_receive_name(receive_name);
+        let variant = match schema.get_receive_error_schema(contract_name, function_name) {
+            Ok(schema::Type::Enum(variants)) => usize::try_from(-i64::from(code) - 1)
+                .ok()
+                .and_then(|index| variants.into_iter().nth(index))
+                .map(|(name, _)| name),
+            _ => None,
+        };
+        Some(variant.unwrap_or_else(|| code.to_string()))
+    }
+
     /// Try to extract and parse the value returned into a type that implements
     /// [`Deserial`].
     ///
@@ -2145,6 +4955,64 @@ impl ContractInvokeError {
     }
 }
 
+impl ContractInitError {
+    /// Whether the initialization ran out of the energy reserved for it.
+    pub fn is_out_of_energy(&self) -> bool {
+        matches!(
+            self.kind,
+            ContractInitErrorKind::OutOfEnergy { .. }
+                | ContractInitErrorKind::ExecutionError {
+                    error: InitExecutionError::OutOfEnergy,
+                    ..
+                }
+        )
+    }
+
+    /// Whether the init function rejected on its own, i.e., returned an
+    /// error code. See [`Self::reject_code`] for the code.
+    pub fn is_reject(&self) -> bool { self.reject_code().is_some() }
+
+    /// Whether the execution of the init function trapped, e.g., because it
+    /// panicked.
+    pub fn is_trap(&self) -> bool {
+        matches!(self.kind, ContractInitErrorKind::ExecutionError {
+            error: InitExecutionError::Trap { .. },
+            ..
+        })
+    }
+
+    /// If the init function rejected, this returns the code it used to
+    /// signal the rejection.
+    pub fn reject_code(&self) -> Option<i32> {
+        match &self.kind {
+            ContractInitErrorKind::ExecutionError {
+                error:
+                    InitExecutionError::Reject {
+                        reason,
+                        ..
+                    },
+                ..
+            } => Some(*reason),
+            _ => None,
+        }
+    }
+
+    /// If the init function rejected, this returns the value it returned.
+    pub fn return_value(&self) -> Option<&[u8]> {
+        match &self.kind {
+            ContractInitErrorKind::ExecutionError {
+                error:
+                    InitExecutionError::Reject {
+                        return_value,
+                        ..
+                    },
+                ..
+            } => Some(return_value),
+            _ => None,
+        }
+    }
+}
+
 impl From<TestConfigurationError> for ContractInvokeErrorKind {
     fn from(err: TestConfigurationError) -> Self {
         match err {
@@ -2154,8 +5022,253 @@ impl From<TestConfigurationError> for ContractInvokeErrorKind {
                 debug_trace,
             },
             TestConfigurationError::BalanceOverflow => Self::BalanceOverflow,
+            TestConfigurationError::StateWritesExceeded {
+                writes,
+                max_writes,
+            } => Self::StateWritesExceeded {
+                writes,
+                max_writes,
+            },
+            TestConfigurationError::StateKeyTooLarge(error) => Self::StateKeyTooLarge(error),
+        }
+    }
+}
+
+/// Convert [`Energy`] to [`InterpreterEnergy`].
+///
+/// This uses the same ratio as the node, namely that one [`Energy`] is `1000`
+/// [`InterpreterEnergy`]. Converting back with
+/// [`interpreter_energy_to_energy`] gives the original [`Energy`].
+///
+/// # Example
+/// ```
+/// # use concordium_smart_contract_testing::*;
+/// let energy = Energy::from(10000);
+/// let interpreter_energy = energy_to_interpreter_energy(energy);
+/// assert_eq!(interpreter_energy.energy, 10_000_000);
+/// assert_eq!(interpreter_energy_to_energy(interpreter_energy), energy);
+/// ```
+pub fn energy_to_interpreter_energy(energy: Energy) -> InterpreterEnergy {
+    InterpreterEnergy::new(to_interpreter_energy(energy))
+}
+
+/// Convert [`InterpreterEnergy`] to [`Energy`], rounding down.
+///
+/// This uses the same ratio as the node, see
+/// [`energy_to_interpreter_energy`].
+pub fn interpreter_energy_to_energy(interpreter_energy: InterpreterEnergy) -> Energy {
+    from_interpreter_energy(&interpreter_energy)
+}
+
+/// Validate and instantiate a module with the Wasm validation rules and cost
+/// model of the given protocol version.
+fn instantiate_module(
+    protocol_version: ProtocolVersion,
+    enable_debug: bool,
+    module_bytes: &[u8],
+) -> Result<wasm::utils::InstantiatedModule<v1::ProcessedImports>, ModuleInvalidError> {
+    let version = u64::from(protocol_version);
+    if version < 4 {
+        return Err(ModuleInvalidError {
+            error:  anyhow!(
+                "Version 1 smart contract modules are not supported in protocol version {version}."
+            ),
+            reason: ModuleInvalidReason::Other,
+        });
+    }
+    validate_module(protocol_version, enable_debug, module_bytes).map_err(|err| {
+        ModuleInvalidError {
+            error:  anyhow!("Not valid in protocol version {version}: {err:#}"),
+            reason: diagnose_invalid_module(protocol_version, enable_debug, module_bytes),
+        }
+    })
+}
+
+/// Validate and instantiate a module in a protocol version which supports
+/// version 1 modules, without diagnosing why an invalid module is invalid.
+fn validate_module(
+    protocol_version: ProtocolVersion,
+    enable_debug: bool,
+    module_bytes: &[u8],
+) -> anyhow::Result<wasm::utils::InstantiatedModule<v1::ProcessedImports>> {
+    let version = u64::from(protocol_version);
+    let imports = allowed_imports(protocol_version, enable_debug);
+    // The validation rules changed in protocol version 6, which allows sign
+    // extension instructions, and the cost model in protocol version 7.
+    let validation_config = if version >= 6 {
+        ValidationConfig::V1
+    } else {
+        ValidationConfig::V0
+    };
+    let result = if version >= 7 {
+        wasm::utils::instantiate_with_metering(
+            validation_config,
+            CostConfigurationV1,
+            &imports,
+            module_bytes,
+        )
+    } else {
+        wasm::utils::instantiate_with_metering(
+            validation_config,
+            CostConfigurationV0,
+            &imports,
+            module_bytes,
+        )
+    };
+    result.map_err(anyhow::Error::from)
+}
+
+/// The host functions that modules may import in the protocol version.
+fn allowed_imports(
+    protocol_version: ProtocolVersion,
+    enable_debug: bool,
+) -> v1::ConcordiumAllowedImports {
+    v1::ConcordiumAllowedImports {
+        support_upgrade: u64::from(protocol_version) >= 5,
+        enable_debug,
+    }
+}
+
+/// Diagnose which validation rule an invalid module broke.
+///
+/// The module is parsed with the parser of the engine, and the imports, the
+/// memory limits and the exports are checked with the rules of the engine. A
+/// function with an invalid body is found by replacing the bodies with
+/// `unreachable`, which is valid for any function, until the module becomes
+/// valid.
+fn diagnose_invalid_module(
+    protocol_version: ProtocolVersion,
+    enable_debug: bool,
+    module_bytes: &[u8],
+) -> ModuleInvalidReason {
+    let Ok(skeleton) = wasm::parse::parse_skeleton(module_bytes) else {
+        return ModuleInvalidReason::Malformed;
+    };
+    for (index, section) in skeleton.custom.iter().enumerate() {
+        if wasm::parse::parse_custom(section).is_err() {
+            return ModuleInvalidReason::MalformedCustomSection {
+                index,
+            };
+        }
+    }
+    let (Some(types), Some(imports), Some(functions), Some(memory), Some(exports), Some(code)) = (
+        parse_wasm_section::<wasm::types::TypeSection>(&skeleton.ty),
+        parse_wasm_section::<wasm::types::ImportSection>(&skeleton.import),
+        parse_wasm_section::<wasm::types::FunctionSection>(&skeleton.func),
+        parse_wasm_section::<wasm::types::MemorySection>(&skeleton.memory),
+        parse_wasm_section::<wasm::types::ExportSection>(&skeleton.export),
+        parse_wasm_section::<wasm::parse::CodeSkeletonSection>(&skeleton.code),
+    ) else {
+        return ModuleInvalidReason::Malformed;
+    };
+
+    let allowed_imports = allowed_imports(protocol_version, enable_debug);
+    let mut seen_imports = BTreeSet::new();
+    // The type index of every function, including the imported ones.
+    let mut type_indices = Vec::new();
+    for import in imports.imports.iter() {
+        let wasm::types::ImportDescription::Func {
+            type_idx,
+        } = import.description;
+        type_indices.push(type_idx);
+        let duplicate = !seen_imports.insert((&import.mod_name.name, &import.item_name.name));
+        let allowed = types.types.get(type_idx as usize).map_or(false, |ty| {
+            allowed_imports.validate_import_function(
+                duplicate,
+                &import.mod_name,
+                &import.item_name,
+                ty,
+            )
+        });
+        if !allowed {
+            return ModuleInvalidReason::DisallowedImport {
+                module: import.mod_name.name.clone(),
+                name:   import.item_name.name.clone(),
+            };
+        }
+    }
+    type_indices.extend(functions.types.iter().copied());
+
+    if let Some(memory_type) = memory.memory_type {
+        let (initial, maximum) = (memory_type.limits.min, memory_type.limits.max);
+        if initial > wasm::constants::MAX_INIT_MEMORY_SIZE
+            || maximum.map_or(false, |maximum| maximum < initial)
+        {
+            return ModuleInvalidReason::MemoryLimits {
+                initial,
+                maximum,
+            };
+        }
+    }
+
+    // The exported functions, as pairs of the name and the function index.
+    let function_exports: Vec<_> = exports
+        .exports
+        .iter()
+        .filter_map(|export| match export.description {
+            wasm::types::ExportDescription::Func {
+                index,
+            } => Some((&export.name, index)),
+            _ => None,
+        })
+        .collect();
+    for (name, index) in function_exports.iter() {
+        let valid = type_indices
+            .get(*index as usize)
+            .and_then(|type_index| types.types.get(*type_index as usize))
+            .map_or(false, |ty| allowed_imports.validate_export_function(name, ty));
+        if !valid {
+            return ModuleInvalidReason::InvalidExport {
+                name: name.name.clone(),
+            };
+        }
+    }
+
+    // Bodies `from..` are replaced with `unreachable`. If the module is valid
+    // when all are replaced, but not when none are, there is a last number of
+    // bodies that can be kept, after which is the first invalid body.
+    let is_valid_from = |from| {
+        let bytes = wasm_with_unreachable_bodies(&skeleton, &code, from);
+        validate_module(protocol_version, enable_debug, &bytes).is_ok()
+    };
+    if is_valid_from(0) {
+        let (mut valid, mut invalid) = (0, code.impls.len());
+        while invalid - valid > 1 {
+            let middle = valid + (invalid - valid) / 2;
+            if is_valid_from(middle) {
+                valid = middle;
+            } else {
+                invalid = middle;
+            }
         }
+        let index = (imports.imports.len() + valid) as u32;
+        return ModuleInvalidReason::InvalidFunctionBody {
+            index,
+            export: function_exports
+                .into_iter()
+                .find(|(_, i)| *i == index)
+                .map(|(name, _)| name.name.clone()),
+        };
     }
+
+    let names = function_exports.iter().map(|(name, _)| name.name.as_str());
+    if let Some(name) = receive_function_without_init(names) {
+        return ModuleInvalidReason::MissingInit {
+            receive_name: name.to_string(),
+        };
+    }
+    ModuleInvalidReason::Other
+}
+
+/// Find a receive function among the exported `names` for whose contract no
+/// init function is exported.
+fn receive_function_without_init<'a>(
+    names: impl Iterator<Item = &'a str> + Clone,
+) -> Option<&'a str> {
+    let inits: BTreeSet<_> = names.clone().filter_map(|name| name.strip_prefix("init_")).collect();
+    names
+        .filter(|name| !name.starts_with("init_"))
+        .find(|name| name.split_once('.').map_or(false, |(contract, _)| !inits.contains(contract)))
 }
 
 /// Convert [`Energy`] to [`InterpreterEnergy`] by multiplying by `1000`.
@@ -2229,6 +5342,118 @@ fn check_exchange_rates(
     Ok(())
 }
 
+/// Split a receive name into the contract name and the entrypoint name, as
+/// used for looking up functions in a schema.
+fn split_receive_name(receive_name: ReceiveName) -> (&str, &str) {
+    receive_name.get_chain_name().split_once('.').expect("Receive names always contain a '.'.")
+}
+
+/// Collect all the entries of a contract state, ordered by their keys.
+///
+/// The entries are read from a clone of the state, so iterating does not
+/// affect `state`.
+pub(crate) fn state_entries(state: &v1::trie::MutableState) -> Vec<(Vec<u8>, Vec<u8>)> {
+    state_entries_up_to(state, usize::MAX)
+}
+
+/// Collect at most `limit` entries of a contract state, ordered by their keys.
+///
+/// See [`state_entries`] for details.
+pub(crate) fn state_entries_up_to(
+    state: &v1::trie::MutableState,
+    limit: usize,
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+    // An empty loader is fine currently, as we do not use caching in this lib.
+    let mut loader = v1::trie::Loader::new(&[][..]);
+    let mut state = state.clone();
+    let inner = state.get_inner(&mut loader);
+    let mut trie = inner.lock();
+    let mut entries = Vec::new();
+    if let Ok(Some(mut iterator)) = trie.iter(&mut loader, &[]) {
+        while entries.len() < limit {
+            let Ok(Some(entry)) =
+                trie.next(&mut loader, &mut iterator, &mut v1::trie::EmptyCounter)
+            else {
+                break;
+            };
+            let value = trie.with_entry(entry, &mut loader, |value| value.to_vec());
+            entries.push((iterator.get_key().to_vec(), value.unwrap_or_default()));
+        }
+        trie.delete_iter(&iterator);
+    }
+    entries
+}
+
+/// Return the name of the receive function in `module` which handles calls to
+/// `receive_name`. This is either the receive function itself or, if it does
+/// not exist, the fallback entrypoint of the contract, if any.
+pub(crate) fn resolve_receive_name(
+    module: &ContractModule,
+    receive_name: ReceiveName,
+) -> Option<OwnedReceiveName> {
+    if module.artifact.has_entrypoint(receive_name.get_chain_name()) {
+        return Some(receive_name.to_owned());
+    }
+    let fallback_receive_name = format!("{}.", receive_name.contract_name());
+    if module.artifact.has_entrypoint(fallback_receive_name.as_str()) {
+        Some(OwnedReceiveName::new_unchecked(fallback_receive_name))
+    } else {
+        None
+    }
+}
+
+/// Encode bytes as lowercase hex.
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Find the first key in the state which is longer than `max_length`, if any,
+/// when the execution with the host function calls in `trace` created state
+/// entries.
+///
+/// The keys are created by host functions of the engine, so they cannot be
+/// checked as the entries are created. Instead, the state is traversed after
+/// the execution, but only if it created an entry, and if `max_length` is
+/// lower than [`MAX_STATE_KEY_LENGTH`](constants::MAX_STATE_KEY_LENGTH), which
+/// the engine itself ensures.
+pub(crate) fn oversized_state_key(
+    state: &v1::trie::MutableState,
+    trace: &DebugTracker,
+    max_length: usize,
+) -> Option<StateKeyTooLarge> {
+    let creates_entries = trace.host_call_trace.iter().any(|(_, call)| {
+        matches!(call.host_function, HostFunctionV1::Common(CommonFunc::StateCreateEntry))
+    });
+    if max_length >= constants::MAX_STATE_KEY_LENGTH || !creates_entries {
+        return None;
+    }
+    // An empty loader is fine currently, as we do not use caching in this lib.
+    let mut loader = v1::trie::Loader::new(&[][..]);
+    let mut state = state.clone();
+    let inner = state.get_inner(&mut loader);
+    let mut trie = inner.lock();
+    let mut iterator = trie
+        .iter(&mut loader, &[])
+        .expect("Internal error: a fresh copy of the state has no iterators.")?;
+    let oversized = loop {
+        let entry = trie
+            .next(&mut loader, &mut iterator, &mut v1::trie::EmptyCounter)
+            .expect("Internal error: the state is in memory, so it can be read.");
+        match entry {
+            Some(_) if iterator.get_key().len() > max_length => {
+                break Some(StateKeyTooLarge {
+                    key_length: iterator.get_key().len(),
+                    max_length,
+                });
+            }
+            Some(_) => {}
+            None => break None,
+        }
+    };
+    trie.delete_iter(&iterator);
+    oversized
+}
+
 /// A helper function for converting `[v0::Logs]` into [`Vec<ContractEvent>`].
 pub(crate) fn contract_events_from_logs(logs: v0::Logs) -> Vec<ContractEvent> {
     logs.logs.into_iter().map(ContractEvent::from).collect()
@@ -2244,7 +5469,10 @@ impl From<ExchangeRateError> for ChainBuilderError {
 
 #[cfg(test)]
 mod tests {
-    use concordium_rust_sdk::base::base::AccountAddressEq;
+    use concordium_rust_sdk::base::{
+        base::AccountAddressEq,
+        contracts_common::{NewContractNameError, NewReceiveNameError, SignatureThreshold},
+    };
 
     use super::*;
 
@@ -2314,6 +5542,71 @@ mod tests {
         assert_eq!(chain.account_balance_available(acc_other), Some(expected_amount_other));
     }
 
+    /// Test that transfers move the amount between the accounts and charge the
+    /// sender the transaction fee.
+    #[test]
+    fn test_transfer() {
+        let mut chain = Chain::new();
+        let sender = AccountAddress([0; 32]);
+        let receiver = AccountAddress([1; 32]);
+        let initial_balance = Amount::from_ccd(100);
+        chain.create_account(Account::new(sender, initial_balance));
+        chain.create_account(Account::new(receiver, initial_balance));
+
+        let amount = Amount::from_ccd(10);
+        let res = chain
+            .transfer(Signer::with_one_key(), sender, receiver, amount)
+            .expect("Transfer should succeed");
+        assert_eq!(res.transaction_fee, chain.calculate_energy_cost(res.energy_used));
+        assert_eq!(
+            chain.account_balance_available(sender),
+            Some(initial_balance - amount - res.transaction_fee)
+        );
+        assert_eq!(chain.account_balance_available(receiver), Some(initial_balance + amount));
+
+        // Transferring to an alias of the sender only charges the fee.
+        let mut alias = sender;
+        alias.0[31] = 1;
+        let balance = chain.account_balance_available(sender).unwrap();
+        let res = chain
+            .transfer(Signer::with_one_key(), sender, alias, amount)
+            .expect("Transfer should succeed");
+        assert_eq!(chain.account_balance_available(sender), Some(balance - res.transaction_fee));
+    }
+
+    /// Test that transfers fail without changing any balances if the sender
+    /// cannot pay for the amount and the fee, or if an account is missing.
+    #[test]
+    fn test_transfer_errors() {
+        let mut chain = Chain::new();
+        let sender = AccountAddress([0; 32]);
+        let receiver = AccountAddress([1; 32]);
+        let missing = AccountAddress([2; 32]);
+        let initial_balance = Amount::from_ccd(100);
+        chain.create_account(Account::new(sender, initial_balance));
+        chain.create_account(Account::new(receiver, initial_balance));
+
+        // The sender can pay for the amount, but not also for the fee.
+        assert_eq!(
+            chain.transfer(Signer::with_one_key(), sender, receiver, initial_balance),
+            Err(AccountTransferError::InsufficientFunds)
+        );
+        assert_eq!(
+            chain.transfer(Signer::with_one_key(), missing, receiver, Amount::zero()),
+            Err(AccountTransferError::MissingAccount(AccountDoesNotExist {
+                address: missing,
+            }))
+        );
+        assert_eq!(
+            chain.transfer(Signer::with_one_key(), sender, missing, Amount::zero()),
+            Err(AccountTransferError::MissingAccount(AccountDoesNotExist {
+                address: missing,
+            }))
+        );
+        assert_eq!(chain.account_balance_available(sender), Some(initial_balance));
+        assert_eq!(chain.account_balance_available(receiver), Some(initial_balance));
+    }
+
     /// Test that building a chain with valid parameters succeeds.
     ///
     /// This test does *not* include external node endpoint, see
@@ -2349,6 +5642,344 @@ mod tests {
 
         assert!(matches!(error, ChainBuilderError::ExchangeRateError));
     }
+
+    /// Test that scheduled exchange rates take effect once the block time
+    /// reaches their effective time, and not before.
+    #[test]
+    fn test_scheduled_exchange_rates() {
+        let mut chain = Chain::new();
+        let initial_micro_ccd_per_euro = chain.micro_ccd_per_euro();
+        let initial_euro_per_energy = chain.euro_per_energy();
+        let initial_cost = chain.calculate_energy_cost(Energy::from(1000));
+
+        let micro_ccd_per_euro_1 = ExchangeRate::new_unchecked(100, 1);
+        let euro_per_energy_1 = ExchangeRate::new_unchecked(1, 1000);
+        let micro_ccd_per_euro_2 = ExchangeRate::new_unchecked(200, 1);
+        let euro_per_energy_2 = ExchangeRate::new_unchecked(1, 500);
+        chain
+            .schedule_exchange_rates(
+                Timestamp::from_timestamp_millis(2000),
+                micro_ccd_per_euro_2,
+                euro_per_energy_2,
+            )
+            .unwrap();
+        chain
+            .schedule_exchange_rates(
+                Timestamp::from_timestamp_millis(1000),
+                micro_ccd_per_euro_1,
+                euro_per_energy_1,
+            )
+            .unwrap();
+
+        // The effective time has not been reached.
+        chain.tick_block_time(Duration::from_millis(999)).unwrap();
+        assert_eq!(chain.micro_ccd_per_euro(), initial_micro_ccd_per_euro);
+        assert_eq!(chain.euro_per_energy(), initial_euro_per_energy);
+        assert_eq!(chain.calculate_energy_cost(Energy::from(1000)), initial_cost);
+
+        // The first rates take effect.
+        chain.tick_block_time(Duration::from_millis(1)).unwrap();
+        assert_eq!(chain.micro_ccd_per_euro(), micro_ccd_per_euro_1);
+        assert_eq!(chain.euro_per_energy(), euro_per_energy_1);
+        assert_eq!(chain.calculate_energy_cost(Energy::from(1000)), Amount::from_micro_ccd(100));
+
+        // Ticking past the second effective time sets the second rates.
+        chain.tick_block_time(Duration::from_millis(5000)).unwrap();
+        assert_eq!(chain.micro_ccd_per_euro(), micro_ccd_per_euro_2);
+        assert_eq!(chain.euro_per_energy(), euro_per_energy_2);
+        assert_eq!(chain.calculate_energy_cost(Energy::from(1000)), Amount::from_micro_ccd(400));
+
+        // Rates scheduled in the past take effect immediately.
+        chain
+            .schedule_exchange_rates(
+                Timestamp::from_timestamp_millis(0),
+                micro_ccd_per_euro_1,
+                euro_per_energy_1,
+            )
+            .unwrap();
+        assert_eq!(chain.micro_ccd_per_euro(), micro_ccd_per_euro_1);
+    }
+
+    /// Test that scheduling invalid exchange rates fails.
+    #[test]
+    fn test_schedule_invalid_exchange_rates() {
+        let mut chain = Chain::new();
+        let error = chain.schedule_exchange_rates(
+            Timestamp::from_timestamp_millis(1000),
+            ExchangeRate::new_unchecked(1000000, 1),
+            ExchangeRate::new_unchecked(100000000, 1),
+        );
+        assert!(error.is_err());
+    }
+
+    /// Test that funded accounts get deterministic addresses and are only
+    /// created once.
+    #[test]
+    fn test_create_funded_account() {
+        let mut chain =
+            Chain::builder().default_account_balance(Amount::from_ccd(5)).build().unwrap();
+        let alice = chain.create_funded_account("alice");
+        let bob = chain.create_funded_account("bob");
+        assert_ne!(alice, bob);
+        assert!(!alice.is_alias(&bob));
+        assert_eq!(alice, account_address_from_label("alice"));
+        assert_eq!(chain.account_balance_available(alice), Some(Amount::from_ccd(5)));
+
+        // Creating the account again leaves it unchanged.
+        chain.transfer(Signer::with_one_key(), alice, bob, Amount::from_ccd(1)).unwrap();
+        let balance = chain.account_balance(alice);
+        assert_eq!(chain.create_funded_account("alice"), alice);
+        assert_eq!(chain.account_balance(alice), balance);
+    }
+
+    /// Test that the different ways of changing the block time agree on the
+    /// millisecond representation, and that overflows are reported.
+    #[test]
+    fn test_block_time_conversions() {
+        let mut chain = Chain::new_at(Timestamp::from_timestamp_millis(10));
+        chain.tick_block_time(Duration::from_seconds(1)).unwrap();
+        chain.tick_block_time_std(std::time::Duration::from_micros(1999)).unwrap();
+        assert_eq!(chain.block_time(), Timestamp::from_timestamp_millis(1011));
+
+        chain.set_block_time(Timestamp::from_timestamp_millis(u64::MAX - 1));
+        assert_eq!(
+            chain.tick_block_time_std(std::time::Duration::from_millis(2)),
+            Err(BlockTimeOverflow)
+        );
+        assert_eq!(chain.tick_block_time_std(std::time::Duration::MAX), Err(BlockTimeOverflow));
+        assert_eq!(chain.block_time(), Timestamp::from_timestamp_millis(u64::MAX - 1));
+    }
+
+    /// Test that the accounts are iterated in the order of their addresses.
+    #[test]
+    fn test_accounts_iterator() {
+        let mut chain = Chain::new();
+        let addresses = [AccountAddress([2; 32]), AccountAddress([0; 32]), AccountAddress([1; 32])];
+        for (i, address) in addresses.iter().enumerate() {
+            chain.create_account(Account::new(*address, Amount::from_ccd(i as u64)));
+        }
+        let accounts: Vec<_> =
+            chain.accounts().map(|(address, account)| (*address, account.balance.total)).collect();
+        assert_eq!(accounts, [
+            (AccountAddress([0; 32]), Amount::from_ccd(1)),
+            (AccountAddress([1; 32]), Amount::from_ccd(2)),
+            (AccountAddress([2; 32]), Amount::from_ccd(0)),
+        ]);
+        assert_eq!(chain.contracts().count(), 0);
+    }
+
+    /// Test that the address and keys of a wallet export are read, and that
+    /// exports of other types and versions are rejected.
+    #[test]
+    fn test_wallet_export() {
+        let keys = AccountKeys::generate(
+            AccountThreshold::ONE,
+            &[(0.into(), SignatureThreshold::ONE, &[0.into(), 1.into()])],
+            &mut rand::thread_rng(),
+        );
+        let address = AccountAddress([3; 32]);
+        let export = |export_type: &str, version: u64| {
+            serde_json::json!({
+                "type": export_type,
+                "v": version,
+                "environment": "testnet",
+                "value": {
+                    "address": address.to_string(),
+                    "accountKeys": keys,
+                    "credentials": {},
+                },
+            })
+            .to_string()
+        };
+
+        let account =
+            Account::new_from_wallet_export(&export(WALLET_EXPORT_TYPE, 0), Amount::from_ccd(10))
+                .expect("The export is valid");
+        assert_eq!(account.address, address);
+        assert_eq!(account.balance.total, Amount::from_ccd(10));
+        assert_eq!(account.keys, AccountAccessStructure::from(&keys));
+
+        assert!(matches!(
+            WalletExport::from_json(&export(WALLET_EXPORT_TYPE, 1)),
+            Err(WalletExportError::UnsupportedVersion {
+                found:    Some(1),
+                expected: 0,
+            })
+        ));
+        assert!(matches!(
+            WalletExport::from_json(&export("concordium-genesis-account", 0)),
+            Err(WalletExportError::UnsupportedType(Some(_)))
+        ));
+        assert!(matches!(WalletExport::from_json("{"), Err(WalletExportError::Json(_))));
+    }
+
+    /// Test that accounts are created at the block time of the chain when they
+    /// are created.
+    #[test]
+    fn test_account_created_at() {
+        let mut chain = Chain::new_at(Timestamp::from_timestamp_millis(100));
+        let account = AccountAddress([0; 32]);
+        assert_eq!(chain.account_created_at(account), None);
+
+        chain.create_account(Account::new(account, Amount::zero()));
+        chain.tick_block_time(Duration::from_millis(50)).expect("No overflow");
+        assert_eq!(chain.account_created_at(account), Some(Timestamp::from_timestamp_millis(100)));
+
+        // Replacing the account resets the creation time.
+        chain.create_account(Account::new(account, Amount::zero()));
+        assert_eq!(chain.account_created_at(account), Some(Timestamp::from_timestamp_millis(150)));
+        // The time is the one contracts see in the default policy of the account.
+        assert_eq!(
+            chain.account(account).expect("Account exists").policy.created_at,
+            Timestamp::from_timestamp_millis(150)
+        );
+
+        // Other policies are kept as they are.
+        let policy = OwnedPolicy {
+            valid_to: Timestamp::from_timestamp_millis(120),
+            ..Account::empty_policy()
+        };
+        let balance = AccountBalance::new(Amount::zero(), Amount::zero(), Amount::zero())
+            .expect("Valid balance");
+        chain.create_account(Account::new_with_policy(account, balance, policy));
+        assert_eq!(chain.account_created_at(account), Some(Timestamp::from_timestamp_millis(150)));
+        let policy = &chain.account(account).expect("Account exists").policy;
+        assert_eq!(policy.created_at, Timestamp::from_timestamp_millis(0));
+        assert_eq!(policy.valid_to, Timestamp::from_timestamp_millis(120));
+    }
+
+    /// Test that only the reject codes of `concordium-std` for sending too
+    /// much CCD are recognized as the contract having insufficient funds.
+    #[test]
+    fn test_is_insufficient_funds_reject() {
+        let error = |kind| ContractInvokeError {
+            energy_used: Energy::from(0),
+            module_load_energy: Energy::from(0),
+            transaction_fee: Amount::zero(),
+            trace_elements: Vec::new(),
+            kind,
+        };
+        let reject = |code| {
+            error(ContractInvokeErrorKind::ExecutionError {
+                failure_kind: v1::InvokeFailure::ContractReject {
+                    code,
+                    data: Vec::new(),
+                },
+            })
+        };
+        assert!(reject(constants::REJECT_TRANSFER_AMOUNT_TOO_LARGE).is_insufficient_funds_reject());
+        assert!(reject(constants::REJECT_CALL_AMOUNT_TOO_LARGE).is_insufficient_funds_reject());
+        assert!(!reject(-1).is_insufficient_funds_reject());
+        assert!(!error(ContractInvokeErrorKind::InsufficientFunds).is_insufficient_funds_reject());
+        assert!(!error(ContractInvokeErrorKind::AmountTooLarge).is_insufficient_funds_reject());
+    }
+
+    /// Test that the block height is advanced by starting new blocks,
+    /// independently of the block time, and that it is restored by
+    /// checkpoints.
+    #[test]
+    fn test_block_height() {
+        let mut chain = Chain::new();
+        assert_eq!(chain.block_height(), 0);
+        chain.tick_block_time(Duration::from_millis(10)).expect("No overflow");
+        assert_eq!(chain.block_height(), 0);
+        chain.new_block();
+        chain.new_block();
+        assert_eq!(chain.block_height(), 2);
+
+        let checkpoint = chain.checkpoint();
+        chain.warp_to_block_height(2).expect("Same height");
+        assert_eq!(chain.block_height(), 2);
+        chain.warp_to_block_height(50).expect("Greater height");
+        assert_eq!(chain.block_height(), 50);
+        assert_eq!(
+            chain.warp_to_block_height(49),
+            Err(BlockHeightDecrease {
+                current:   50,
+                requested: 49,
+            })
+        );
+        assert_eq!(chain.block_height(), 50);
+        assert_eq!(chain.fork().block_height(), 50);
+
+        chain.restore(&checkpoint);
+        assert_eq!(chain.block_height(), 2);
+    }
+
+    /// Test that restoring a checkpoint resets the mocked account balances and
+    /// the creation times of the accounts.
+    #[test]
+    fn test_checkpoint_mocks_and_creation_times() {
+        let mut chain = Chain::new_at(Timestamp::from_timestamp_millis(100));
+        let (account, mocked) = (AccountAddress([0; 32]), AccountAddress([1; 32]));
+        chain.create_account(Account::new(account, Amount::zero()));
+        let checkpoint = chain.checkpoint();
+
+        let balance = AccountBalance::new(Amount::from_ccd(5), Amount::zero(), Amount::zero())
+            .expect("Valid balance");
+        chain.mock_account_balance(mocked, balance);
+        chain.create_account_at_block(
+            Account::new(account, Amount::zero()),
+            Timestamp::from_timestamp_millis(10),
+        );
+        assert_eq!(chain.account_created_at(account), Some(Timestamp::from_timestamp_millis(10)));
+
+        chain.restore(&checkpoint);
+        assert!(chain.mocked_account_balances.is_empty());
+        assert_eq!(chain.account_created_at(account), Some(Timestamp::from_timestamp_millis(100)));
+    }
+
+    /// Test that malformed names are rejected when building the payloads, and
+    /// that valid names are kept as they are.
+    #[test]
+    fn test_checked_payloads() {
+        let mod_ref = ModuleReference::from([0; 32]);
+        let address = ContractAddress::new(0, 0);
+        let init = init_payload(mod_ref, "init_counter", OwnedParameter::empty(), Amount::zero())
+            .expect("Valid init name");
+        assert_eq!(init.init_name.as_contract_name().get_chain_name(), "init_counter");
+        let update =
+            update_payload(address, "counter.inc", OwnedParameter::empty(), Amount::zero())
+                .expect("Valid receive name");
+        assert_eq!(update.receive_name.as_receive_name().get_chain_name(), "counter.inc");
+
+        let too_long = format!("counter.{}", "a".repeat(100));
+        assert_eq!(
+            update_payload(address, &too_long, OwnedParameter::empty(), Amount::zero()).err(),
+            Some(NameError::ReceiveName {
+                name:  too_long.clone(),
+                error: NewReceiveNameError::TooLong,
+            })
+        );
+        assert_eq!(
+            update_payload(address, "counter.in c", OwnedParameter::empty(), Amount::zero()).err(),
+            Some(NameError::ReceiveName {
+                name:  "counter.in c".into(),
+                error: NewReceiveNameError::InvalidCharacters,
+            })
+        );
+        assert_eq!(
+            update_payload(address, "counter", OwnedParameter::empty(), Amount::zero()).err(),
+            Some(NameError::ReceiveName {
+                name:  "counter".into(),
+                error: NewReceiveNameError::MissingDotSeparator,
+            })
+        );
+        assert_eq!(
+            init_payload(mod_ref, "counter", OwnedParameter::empty(), Amount::zero()).err(),
+            Some(NameError::InitName {
+                name:  "counter".into(),
+                error: NewContractNameError::MissingInitPrefix,
+            })
+        );
+        assert_eq!(
+            init_payload(mod_ref, "init_c\u{e9}", OwnedParameter::empty(), Amount::zero()).err(),
+            Some(NameError::InitName {
+                name:  "init_c\u{e9}".into(),
+                error: NewContractNameError::InvalidCharacters,
+            })
+        );
+    }
 }
 
 /// Return whether execution is running under `cargo concordium test` with
@@ -3,10 +3,11 @@ use crate::{
     constants::{self, verify_ed25519_energy_cost},
     impls::{
         contract_events_from_logs, from_interpreter_energy, lookup_module_cost,
-        to_interpreter_energy,
+        oversized_state_key, resolve_receive_name, state_entries, to_interpreter_energy,
     },
     types::{Account, BalanceError, Contract, ContractModule, TransferError},
-    AccountSignatures, DebugTraceElement, ExecutionError, InvokeExecutionError,
+    AccountSignatures, AmountDelta, CallFrame, DebugTraceElement, ExecutionError,
+    InvokeExecutionError, ModificationCheck, StateDiff, StateView, StateWrites, UpgradeEvent,
 };
 use concordium_rust_sdk::{
     base::{
@@ -25,7 +26,7 @@ use concordium_rust_sdk::{
     },
     smart_contracts::engine::{
         v0,
-        v1::{self, trie, DebugTracker, InvokeResponse},
+        v1::{self, trie, CommonFunc, DebugTracker, HostFunctionV1, InvokeResponse},
         wasm::artifact::{self, CompiledFunction},
         DebugInfo, InterpreterEnergy,
     },
@@ -46,12 +47,27 @@ macro_rules! exit_ooe {
 
 impl InvocationData {
     /// An internal helper function to construct a [`DebugTraceElement`] from a
-    /// set of debug events.
-    pub(crate) fn debug_trace(&self, debug_trace: DebugTracker) -> DebugTraceElement {
+    /// set of debug events and the response to the query that interrupted the
+    /// execution.
+    pub(crate) fn debug_trace(
+        &self,
+        debug_trace: DebugTracker,
+        response: &v1::InvokeResponse,
+    ) -> DebugTraceElement {
+        let response = match response {
+            v1::InvokeResponse::Success {
+                data,
+                ..
+            } => data.clone(),
+            v1::InvokeResponse::Failure {
+                ..
+            } => None,
+        };
         DebugTraceElement::Debug {
             entrypoint: self.entrypoint.clone(),
             address: self.address,
             debug_trace,
+            response,
         }
     }
 }
@@ -155,22 +171,13 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
             let owned_contract_name =
                 OwnedContractName::new_unchecked(format!("init_{}", contract_name));
             let owned_entrypoint_name = borrowed_receive_name.entrypoint_name().to_owned();
-            let receive_name = borrowed_receive_name.get_chain_name();
-            let fallback_receive_name = format!("{}.", contract_name);
-            if module.artifact.has_entrypoint(receive_name) {
-                (owned_contract_name, payload.receive_name, owned_entrypoint_name)
-            } else if module.artifact.has_entrypoint(fallback_receive_name.as_str()) {
-                (
-                    owned_contract_name,
-                    OwnedReceiveName::new_unchecked(fallback_receive_name),
-                    owned_entrypoint_name,
-                )
-            } else {
+            let Some(receive_name) = resolve_receive_name(&module, borrowed_receive_name) else {
                 // Return early.
                 return Ok(Err(v1::InvokeResponse::Failure {
                     kind: v1::InvokeFailure::NonExistentEntrypoint,
                 }));
-            }
+            };
+            (owned_contract_name, receive_name, owned_entrypoint_name)
         };
 
         // Subtract the cost of looking up the module
@@ -221,6 +228,8 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
         let inner = mutable_state.get_inner(&mut loader);
         let instance_state = v1::InstanceState::new(loader, inner);
 
+        self.observe_self_balance(payload.address, instance_self_balance);
+
         // Get the initial result from invoking receive
         let initial_result = self.run_interpreter(|energy| {
             v1::invoke_receive(
@@ -250,6 +259,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
             trace_elements_checkpoint,
             next_mod_idx_checkpoint: mod_idx_before_invoke,
             mod_idx_before_invoke,
+            call_frame: self.call_frames.len() - 1,
         })))
     }
 
@@ -277,15 +287,27 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
         // Initialized to a dummy value. This will always be set or the function will
         // terminate with an Err.
         let mut invoke_response: Option<InvokeResponse> = None;
+        // The invocation charged for the energy used since `energy_mark`.
+        let mut current_frame = None;
+        let mut energy_mark = *self.remaining_energy;
         while let Some(invocation_data) = stack.pop() {
+            self.charge_call_frame(current_frame, &mut energy_mark);
             let (receive_result, mut invocation_data) = match invocation_data {
                 Next::Resume {
                     mut data,
                     config,
                     response,
                 } => {
+                    current_frame = Some(data.call_frame);
                     match response {
                         Some(response) => {
+                            if let v1::InvokeResponse::Success {
+                                new_balance,
+                                ..
+                            } = &response
+                            {
+                                self.observe_self_balance(data.address, *new_balance);
+                            }
                             let receive_result = self.run_interpreter(|energy| {
                                 v1::resume_receive(
                                     config,
@@ -311,12 +333,14 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                     data: return_value,
                                     ..
                                 } => {
+                                    let new_balance = self.contract_balance_unchecked(data.address);
+                                    self.observe_self_balance(data.address, new_balance);
                                     let invoke_response = v1::InvokeResponse::Success {
                                         // The balance returned by `invoke_entrypoint`
                                         // is the balance of the contract called. But we
                                         // are interested in the new balance of the caller.
-                                        new_balance: self.contract_balance_unchecked(data.address),
-                                        data:        return_value,
+                                        new_balance,
+                                        data: return_value,
                                     };
                                     (true, invoke_response)
                                 }
@@ -339,6 +363,17 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                 }
                                 state_changed
                             };
+                            // The contracts still waiting for a call to return are the
+                            // callers of the resumed contract.
+                            let depth = call_depth(&stack);
+                            self.modification_checks.push(ModificationCheck {
+                                address: data.address,
+                                entrypoint: data.entrypoint.clone(),
+                                depth,
+                                index_before: data.mod_idx_before_invoke,
+                                index_after: self.modification_index(data.address),
+                                state_changed,
+                            });
 
                             // Add resume event
                             let resume_event = ContractTraceElement::Resumed {
@@ -374,6 +409,20 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                     payload,
                     trace_elements_checkpoint,
                 } => {
+                    // The callers waiting for a call to return are the ones below this
+                    // invocation.
+                    let depth = call_depth(&stack);
+                    self.call_frames.push(CallFrame {
+                        address: payload.address,
+                        entrypoint: payload
+                            .receive_name
+                            .as_receive_name()
+                            .entrypoint_name()
+                            .to_owned(),
+                        depth,
+                        energy: 0.into(),
+                    });
+                    current_frame = Some(self.call_frames.len() - 1);
                     match self.invoke_entrypoint_initial(
                         invoker,
                         sender,
@@ -390,6 +439,8 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                     }
                 }
             };
+            self.count_state_writes(&receive_result)?;
+            self.check_state_keys(&receive_result, &invocation_data.state)?;
 
             match receive_result {
                 v1::ReceiveResult::Success {
@@ -563,6 +614,14 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                 trace,
                             );
 
+                            // Each contract waiting for a call to return has a pending resume on
+                            // the stack, so the current contract is at the depth `call_depth`
+                            // and the called contract one deeper.
+                            let depth = call_depth(&stack) + 1;
+                            let exceeds_max_depth = self
+                                .chain
+                                .max_call_depth
+                                .is_some_and(|max_depth| depth > max_depth);
                             match self
                                 .chain
                                 .contracts
@@ -593,6 +652,29 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                         response: Some(response),
                                     });
                                 }
+                                // The call would exceed the maximum call depth, so it fails
+                                // like a call to a contract which traps.
+                                Some(_) if exceeds_max_depth => {
+                                    let response = v1::InvokeResponse::Failure {
+                                        kind: v1::InvokeFailure::RuntimeError,
+                                    };
+                                    let resume_event = ContractTraceElement::Resumed {
+                                        address: invocation_data.address,
+                                        success: false,
+                                    };
+                                    self.push_regular_trace_element(
+                                        &mut trace_elements,
+                                        resume_event,
+                                        invocation_data.entrypoint.clone(),
+                                        DebugTracker::empty_trace(), /* The contract was not
+                                                                      * called. */
+                                    );
+                                    stack.push(Next::Resume {
+                                        data: invocation_data,
+                                        config,
+                                        response: Some(response),
+                                    });
+                                }
                                 Some(contract_name) => {
                                     // Make a checkpoint before calling another contract so that we
                                     // may roll back.
@@ -676,6 +758,11 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                                 constants::INITIALIZE_CONTRACT_INSTANCE_CREATE_COST,
                                             ), DebugTracker::empty_trace());
 
+                                            self.report_upgrade(
+                                                &invocation_data,
+                                                old_module_ref,
+                                                module_ref,
+                                            );
                                             let upgrade_event = ContractTraceElement::Upgraded {
                                                 address: invocation_data.address,
                                                 from:    old_module_ref,
@@ -725,7 +812,11 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                         v1::Interrupt::QueryAccountBalance {
                             address,
                         } => {
-                            let response = match self.account_balance(address) {
+                            // Mocked balances are only used if the account does not exist.
+                            let balance = self.account_balance(address).or_else(|| {
+                                self.chain.mocked_account_balances.get(&address.into()).copied()
+                            });
+                            let response = match balance {
                                 Some(balance) => v1::InvokeResponse::Success {
                                     new_balance: self
                                         .contract_balance_unchecked(invocation_data.address),
@@ -741,7 +832,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                 ),
                                 trace
                             );
-                            trace_elements.push(invocation_data.debug_trace(trace));
+                            trace_elements.push(invocation_data.debug_trace(trace, &response));
                             stack.push(Next::Resume {
                                 data: invocation_data,
                                 config,
@@ -770,7 +861,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                 ),
                                 trace
                             );
-                            trace_elements.push(invocation_data.debug_trace(trace));
+                            trace_elements.push(invocation_data.debug_trace(trace, &response));
                             stack.push(Next::Resume {
                                 data: invocation_data,
                                 config,
@@ -796,7 +887,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                 trace
                             );
 
-                            trace_elements.push(invocation_data.debug_trace(trace));
+                            trace_elements.push(invocation_data.debug_trace(trace, &response));
                             stack.push(Next::Resume {
                                 data: invocation_data,
                                 config,
@@ -868,7 +959,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                     kind: v1::InvokeFailure::NonExistentAccount,
                                 },
                             };
-                            trace_elements.push(invocation_data.debug_trace(trace));
+                            trace_elements.push(invocation_data.debug_trace(trace, &response));
                             stack.push(Next::Resume {
                                 data: invocation_data,
                                 config,
@@ -905,7 +996,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                     kind: v1::InvokeFailure::NonExistentAccount,
                                 },
                             };
-                            trace_elements.push(invocation_data.debug_trace(trace));
+                            trace_elements.push(invocation_data.debug_trace(trace, &response));
                             stack.push(Next::Resume {
                                 data: invocation_data,
                                 config,
@@ -933,7 +1024,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                 ),
                                 trace
                             );
-                            trace_elements.push(invocation_data.debug_trace(trace));
+                            trace_elements.push(invocation_data.debug_trace(trace, &response));
                             stack.push(Next::Resume {
                                 data: invocation_data,
                                 config,
@@ -961,7 +1052,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                                 ),
                                 trace
                             );
-                            trace_elements.push(invocation_data.debug_trace(trace));
+                            trace_elements.push(invocation_data.debug_trace(trace, &response));
                             stack.push(Next::Resume {
                                 data: invocation_data,
                                 config,
@@ -1047,9 +1138,19 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                 }
             }
         }
+        self.charge_call_frame(current_frame, &mut energy_mark);
         Ok((invoke_response.expect("Response should have been set."), trace_elements))
     }
 
+    /// Add the energy used since `energy_mark` to the call frame `frame`, if
+    /// any, and move the mark to the current remaining energy.
+    fn charge_call_frame(&mut self, frame: Option<usize>, energy_mark: &mut Energy) {
+        if let Some(frame) = frame {
+            self.call_frames[frame].energy.energy += (*energy_mark - *self.remaining_energy).energy;
+        }
+        *energy_mark = *self.remaining_energy;
+    }
+
     /// Make a transfer from a contract to an account in the changeset.
     ///
     /// Returns the new balance of `from`.
@@ -1069,8 +1170,9 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
 
         // Make the transfer.
         let new_balance = self.change_contract_balance(from, AmountDelta::Negative(amount))?;
-        self.change_account_balance(to, AmountDelta::Positive(amount))
-            .expect("Cannot fail when adding");
+        // Adding can only fail with an overflow, which the caller reports as a
+        // configuration error instead of panicking.
+        self.change_account_balance(to, AmountDelta::Positive(amount))?;
 
         Ok(new_balance)
     }
@@ -1162,7 +1264,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
             }
             btree_map::Entry::Occupied(mut occ) => {
                 let contract_changes = occ.get_mut();
-                let new_delta = contract_changes.self_balance_delta.add_delta(delta);
+                let new_delta = contract_changes.self_balance_delta.add_delta(delta)?;
                 // Try to apply the balance or return an error if insufficient funds.
                 let new_contract_balance =
                     new_delta.apply_to_balance(contract_changes.self_balance_original)?;
@@ -1217,7 +1319,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
             }
             btree_map::Entry::Occupied(mut occ) => {
                 let account_changes = occ.get_mut();
-                let new_delta = account_changes.balance_delta.add_delta(delta);
+                let new_delta = account_changes.balance_delta.add_delta(delta)?;
                 // Try to apply the balance or return an error if insufficient funds.
                 let new_account_balance =
                     new_delta.apply_to_balance(account_changes.original_balance)?;
@@ -1385,6 +1487,38 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
         modification_index
     }
 
+    /// Stop the update if, after the execution which produced `result`, the
+    /// contract is about to save a `state` with a key longer than the maximum
+    /// configured with
+    /// [`ChainBuilder::max_state_key_length`](crate::ChainBuilder::max_state_key_length).
+    ///
+    /// The keys are created by host functions of the engine, so they can only
+    /// be checked when the contract returns or is interrupted, and only if it
+    /// created an entry. The energy used until then is charged.
+    fn check_state_keys(
+        &self,
+        result: &v1::ReceiveResult<artifact::CompiledFunction, DebugTracker>,
+        state: &trie::MutableState,
+    ) -> Result<(), TestConfigurationError> {
+        let saves_state = matches!(
+            result,
+            v1::ReceiveResult::Success {
+                state_changed: true,
+                ..
+            } | v1::ReceiveResult::Interrupt {
+                state_changed: true,
+                ..
+            }
+        );
+        if !saves_state {
+            return Ok(());
+        }
+        match oversized_state_key(state, receive_trace(result), self.chain.max_state_key_length) {
+            Some(error) => Err(TestConfigurationError::StateKeyTooLarge(error)),
+            None => Ok(()),
+        }
+    }
+
     /// Saves a new module reference for the contract in the changeset.
     ///
     /// If the contract already has an entry in the changeset, the old module is
@@ -1448,6 +1582,70 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
     /// Roll back to the previous checkpoint.
     fn rollback(&mut self) { self.changeset.rollback(); }
 
+    /// Call the hook registered with [`Chain::on_self_balance_query`], if any,
+    /// as the contract at `address` is given its balance.
+    fn observe_self_balance(&self, address: ContractAddress, balance: Amount) {
+        if let Some(hook) = &self.chain.hooks.self_balance_query {
+            hook(address, balance);
+        }
+    }
+
+    /// Count the state entries written by the host function calls made in the
+    /// execution which produced `result`, and stop the update if it writes
+    /// more entries than the maximum configured with
+    /// [`Chain::set_max_state_writes_per_update`].
+    ///
+    /// The entries are written by host functions of the engine, so the calls
+    /// can only be counted when the contract returns, is interrupted or
+    /// traps. The energy used until then is charged. The error reports the
+    /// writes up to the maximum, i.e., without the one exceeding it.
+    fn count_state_writes(
+        &mut self,
+        result: &v1::ReceiveResult<artifact::CompiledFunction, DebugTracker>,
+    ) -> Result<(), TestConfigurationError> {
+        for (_, call) in receive_trace(result).host_call_trace.iter() {
+            let mut writes = self.state_writes;
+            match call.host_function {
+                HostFunctionV1::Common(CommonFunc::StateCreateEntry) => writes.created += 1,
+                HostFunctionV1::Common(
+                    CommonFunc::StateEntryWrite | CommonFunc::StateEntryResize,
+                ) => writes.modified += 1,
+                HostFunctionV1::Common(
+                    CommonFunc::StateDeleteEntry | CommonFunc::StateDeletePrefix,
+                ) => writes.deleted += 1,
+                _ => continue,
+            }
+            if let Some(max_writes) =
+                self.chain.max_state_writes.filter(|&max_writes| writes.total() > max_writes)
+            {
+                return Err(TestConfigurationError::StateWritesExceeded {
+                    writes: self.state_writes,
+                    max_writes,
+                });
+            }
+            self.state_writes = writes;
+        }
+        Ok(())
+    }
+
+    /// Call the hook registered with [`Chain::on_upgrade`], if any, as the
+    /// contract of the invocation `data` upgrades from the module `from` to
+    /// `to`.
+    fn report_upgrade(&self, data: &InvocationData, from: ModuleReference, to: ModuleReference) {
+        let Some(hook) = &self.chain.hooks.upgrade else {
+            return;
+        };
+        // Copy the entries, so the snapshot does not share the state the contract
+        // continues to modify.
+        let entries = state_entries(&data.state);
+        hook(&UpgradeEvent {
+            address: data.address,
+            from,
+            to,
+            state: StateView(trie::PersistentState::from_iterator(entries.into_iter())),
+        });
+    }
+
     /// Update the `remaining_energy` field by converting the input to
     /// [`InterpreterEnergy`] and then [`Energy`].
     fn update_energy(&mut self, remaining_energy: InterpreterEnergy) {
@@ -1491,6 +1689,7 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
                         .saturating_sub(&remaining_energy),
                 );
                 self.remaining_energy.tick_energy(used_energy)?;
+                self.peak_segment_energy = self.peak_segment_energy.max(used_energy);
                 remaining_energy.energy = to_interpreter_energy(*self.remaining_energy);
                 Ok(remaining_energy)
             };
@@ -1595,6 +1794,48 @@ impl<'a, 'b> EntrypointInvocationHandler<'a, 'b> {
     }
 }
 
+/// The number of contracts on `stack` waiting for a call to return, each of
+/// which has a pending resume without a response.
+fn call_depth(stack: &[Next]) -> usize {
+    stack
+        .iter()
+        .filter(|next| {
+            matches!(next, Next::Resume {
+                response: None,
+                ..
+            })
+        })
+        .count()
+}
+
+/// The trace of the host function calls made in the execution which produced
+/// `result`.
+fn receive_trace(
+    result: &v1::ReceiveResult<artifact::CompiledFunction, DebugTracker>,
+) -> &DebugTracker {
+    match result {
+        v1::ReceiveResult::Success {
+            trace,
+            ..
+        }
+        | v1::ReceiveResult::Interrupt {
+            trace,
+            ..
+        }
+        | v1::ReceiveResult::Reject {
+            trace,
+            ..
+        }
+        | v1::ReceiveResult::Trap {
+            trace,
+            ..
+        }
+        | v1::ReceiveResult::OutOfEnergy {
+            trace,
+        } => trace,
+    }
+}
+
 /// A pair of the signatures, and the data.
 type DeserializedSignatureAndData<'a> = (AccountSignatures, &'a [u8]);
 
@@ -1765,6 +2006,34 @@ impl ChangeSet {
 
         Ok(invoked_contract_has_state_changes)
     }
+
+    /// Compute the changes to the states of the contracts modified in the
+    /// last checkpoint, compared with their persisted states, ordered by the
+    /// contract addresses. Contracts whose entries end up unchanged are
+    /// omitted.
+    ///
+    /// **Preconditions:**
+    ///  - All contracts referred must exist in persistence.
+    pub(crate) fn state_diffs(
+        &self,
+        persisted_contracts: &BTreeMap<ContractAddress, Contract>,
+    ) -> Vec<(ContractAddress, StateDiff)> {
+        self.current()
+            .contracts
+            .iter()
+            .filter_map(|(addr, changes)| {
+                let modified_state = changes.state.as_ref()?;
+                let contract = persisted_contracts
+                    .get(addr)
+                    .expect("Precondition violation: contract must exist");
+                let diff = StateDiff::between(
+                    state_entries(&contract.state.thaw()),
+                    state_entries(modified_state),
+                );
+                (!diff.is_empty()).then_some((*addr, diff))
+            })
+            .collect()
+    }
 }
 
 impl Default for ChangeSet {
@@ -1804,15 +2073,29 @@ impl AmountDelta {
     }
 
     /// Add two [`Self`] to create a new one.
-    fn add_delta(self, delta: AmountDelta) -> Self {
-        match delta {
-            AmountDelta::Positive(d) => self.add_amount(d),
-            AmountDelta::Negative(d) => self.subtract_amount(d),
+    ///
+    /// Returns an error instead of overflowing if both have the same sign and
+    /// their sum does not fit in an [`Amount`]. A negative delta that large can
+    /// never be covered by a balance, so it is reported as insufficient funds.
+    fn add_delta(self, delta: AmountDelta) -> Result<Self, BalanceError> {
+        match (self, delta) {
+            (Self::Positive(current), AmountDelta::Positive(d))
+                if current.checked_add(d).is_none() =>
+            {
+                Err(BalanceError::Overflow)
+            }
+            (Self::Negative(current), AmountDelta::Negative(d))
+                if current.checked_add(d).is_none() =>
+            {
+                Err(BalanceError::Insufficient)
+            }
+            (_, AmountDelta::Positive(d)) => Ok(self.add_amount(d)),
+            (_, AmountDelta::Negative(d)) => Ok(self.subtract_amount(d)),
         }
     }
 
     /// Whether the [`Self`] is zero (either `+0` or `-0`).
-    fn is_zero(&self) -> bool {
+    pub fn is_zero(&self) -> bool {
         match self {
             AmountDelta::Positive(d) => d.micro_ccd == 0,
             AmountDelta::Negative(d) => d.micro_ccd == 0,
@@ -1904,5 +2187,26 @@ mod tests {
             x = x.subtract_amount(Amount::from_micro_ccd(2)); // -1 mCCD
             assert_eq!(x, AmountDelta::Negative(Amount::from_micro_ccd(1)));
         }
+
+        /// Test that adding deltas reports overflows as errors instead of
+        /// panicking.
+        #[test]
+        fn test_add_delta_overflow() {
+            use crate::types::BalanceError;
+            let max = Amount::from_micro_ccd(u64::MAX);
+            let one = Amount::from_micro_ccd(1);
+            assert_eq!(
+                AmountDelta::Positive(max).add_delta(AmountDelta::Positive(one)),
+                Err(BalanceError::Overflow)
+            );
+            assert_eq!(
+                AmountDelta::Negative(max).add_delta(AmountDelta::Negative(one)),
+                Err(BalanceError::Insufficient)
+            );
+            assert_eq!(
+                AmountDelta::Negative(max).add_delta(AmountDelta::Positive(one)),
+                Ok(AmountDelta::Negative(Amount::from_micro_ccd(u64::MAX - 1)))
+            );
+        }
     }
 }
@@ -1,4 +1,4 @@
-use crate::Chain;
+use crate::{AmountDelta, CallFrame, Chain, ModificationCheck, StateKeyTooLarge, StateWrites};
 use concordium_rust_sdk::{
     base::{
         base::{AccountAddressEq, Energy},
@@ -35,6 +35,17 @@ pub(crate) struct EntrypointInvocationHandler<'a, 'b> {
     pub(crate) remaining_energy: &'a mut Energy,
     /// Costs incurred from smart contract module loading.
     pub(crate) module_load_energy: Energy,
+    /// The largest amount of energy used by the interpreter in a single
+    /// uninterrupted execution of contract code.
+    pub(crate) peak_segment_energy: Energy,
+    /// The modification index checks made when contracts resumed after
+    /// calling other contracts.
+    pub(crate) modification_checks: Vec<ModificationCheck>,
+    /// The invocations of contract entrypoints made so far, with the energy
+    /// used by each of them.
+    pub(crate) call_frames: Vec<CallFrame>,
+    /// The state entries written by the host function calls made so far.
+    pub(crate) state_writes: StateWrites,
     /// The energy reserved for the execution. Used for calculating intermediate
     /// energy usages in contract trace elements.
     pub(crate) energy_reserved: Energy,
@@ -161,15 +172,9 @@ pub(super) struct InvocationData {
     /// Differs from the `next_mod_idx_checkpoint` in that this value can be
     /// altered during the execution of a single entrypoint.
     pub(super) mod_idx_before_invoke:     u32,
-}
-
-/// A positive or negative delta in for an [`Amount`].
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(super) enum AmountDelta {
-    /// A positive delta.
-    Positive(Amount),
-    /// A negative delta.
-    Negative(Amount),
+    /// The index of the invocation in
+    /// [`EntrypointInvocationHandler::call_frames`].
+    pub(super) call_frame:                usize,
 }
 
 /// Errors that occur due to the configuration of the test.
@@ -183,4 +188,14 @@ pub(crate) enum TestConfigurationError {
     /// [`Amount`]. On the chain there is roughly 10 billion CCD, which
     /// means that overflows of amounts cannot occur.
     BalanceOverflow,
+    /// The update wrote more state entries than the maximum configured with
+    /// [`Chain::set_max_state_writes_per_update`].
+    StateWritesExceeded {
+        /// The writes made before the one exceeding the maximum.
+        writes:     StateWrites,
+        max_writes: usize,
+    },
+    /// A contract created a key in its state which is longer than the maximum
+    /// configured for the [`Chain`].
+    StateKeyTooLarge(StateKeyTooLarge),
 }
@@ -84,22 +84,46 @@
 //!     - initialization.transaction_fee
 //!     - update.transaction_fee));
 //! ```
+mod cis2;
 mod constants;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+mod golden;
 mod impls;
 mod invocation;
+#[doc(hidden)]
+pub mod macros;
+mod persist;
+mod replay;
 mod types;
-pub use constants::CONTRACT_MODULE_OUTPUT_PATH_ENV_VAR;
-pub use impls::{is_debug_enabled, module_load_output, module_load_v1, module_load_v1_raw};
+pub use cis2::{Cis2Client, Cis2Error, Cis2Receiver, Cis2Transfer};
+pub use constants::{
+    CONTRACT_MODULE_OUTPUT_PATH_ENV_VAR, DEFAULT_ACCOUNT_BALANCE, DEFAULT_PROTOCOL_VERSION,
+    MAX_STATE_KEY_LENGTH,
+};
+#[cfg(feature = "arbitrary")]
+pub use fuzz::{ArbitraryParameter, FuzzOutcome};
+pub use golden::{GoldenError, GoldenMask, GoldenResult};
+pub use impls::{
+    account_address_from_label, energy_to_interpreter_energy, init_payload,
+    interpreter_energy_to_energy, is_debug_enabled, module_load_output, module_load_v1,
+    module_load_v1_from_bytes, module_load_v1_raw, update_payload,
+};
+pub use persist::{ChainPersistError, PERSIST_FORMAT_VERSION, PERSIST_MAGIC};
+pub use replay::{
+    BlockResult, RecordedTransaction, Transaction, TransactionLog, TransactionOutcome,
+    TRANSACTION_LOG_FORMAT_VERSION,
+};
 pub use types::*;
 
 // Re-export types.
 pub use concordium_rust_sdk::{
     base::{
-        base::Energy,
+        base::{Energy, ProtocolVersion},
         common::types::{CredentialIndex, KeyIndex},
         contracts_common::{
-            from_bytes, to_bytes, AccountAddress, AccountBalance, AccountThreshold, Address,
-            Amount, ContractAddress, ContractName, Duration, EntrypointName, ExchangeRate,
+            from_bytes, schema, to_bytes, AccountAddress, AccountBalance, AccountThreshold,
+            Address, Amount, ContractAddress, ContractName, Duration, EntrypointName, ExchangeRate,
             ModuleReference, OwnedContractName, OwnedEntrypointName, OwnedParameter,
             OwnedReceiveName, Parameter, ReceiveName, SignatureThreshold, SlotTime, Timestamp,
         },
@@ -109,7 +133,8 @@ pub use concordium_rust_sdk::{
         smart_contracts::{ContractEvent, ContractTraceElement, InstanceUpdatedEvent, WasmVersion},
         transactions::{AccountAccessStructure, InitContractPayload, UpdateContractPayload},
     },
-    smart_contracts::engine::v1::InvokeFailure,
+    cis2::{Event as Cis2Event, TokenAmount, TokenId},
+    smart_contracts::engine::{v1::InvokeFailure, InterpreterEnergy},
     types::RejectReason,
     v2::Endpoint,
 };
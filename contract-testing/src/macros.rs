@@ -0,0 +1,230 @@
+//! Assertion macros for amounts and balances.
+//!
+//! The macros are exported at the root of the crate. The functions in this
+//! module are only used by the macros and are not part of the public API.
+use crate::{Address, Amount, AmountDelta, AmountExt, Chain};
+
+/// Assert that two [`Amount`](crate::Amount)s are equal.
+///
+/// On failure, both amounts and their difference are printed in CCD, which
+/// is easier to read than the raw microCCD values printed by `assert_eq!`.
+/// An optional message with format arguments can be supplied, just like with
+/// `assert_eq!`.
+///
+/// ```
+/// # use concordium_smart_contract_testing::*;
+/// assert_amount_eq!(Amount::from_ccd(1), Amount::from_micro_ccd(1_000_000));
+/// ```
+#[macro_export]
+macro_rules! assert_amount_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        match (&$actual, &$expected) {
+            (actual, expected) => {
+                if *actual != *expected {
+                    panic!("{}", $crate::macros::amount_mismatch(*actual, *expected));
+                }
+            }
+        }
+    };
+    ($actual:expr, $expected:expr, $($arg:tt)+) => {
+        match (&$actual, &$expected) {
+            (actual, expected) => {
+                if *actual != *expected {
+                    panic!(
+                        "{}\n{}",
+                        ::std::format_args!($($arg)+),
+                        $crate::macros::amount_mismatch(*actual, *expected)
+                    );
+                }
+            }
+        }
+    };
+}
+
+/// Assert that running a block of code changes the balance of an address by
+/// the given [`AmountDelta`](crate::AmountDelta).
+///
+/// The total balance of the address, which can be an account or a contract,
+/// is recorded before the block is run and compared to the balance
+/// afterwards. An address that does not exist has a balance of zero. On
+/// failure, the balances before and after and the expected and actual
+/// changes are printed in CCD.
+///
+/// The block is run in place, so it can use the chain directly. The value of
+/// the block is returned by the macro.
+///
+/// ```
+/// # use concordium_smart_contract_testing::*;
+/// let mut chain = Chain::new();
+/// let alice = AccountAddress([0; 32]);
+/// let bob = AccountAddress([1; 32]);
+/// chain.create_account(Account::new(alice, Amount::from_ccd(100)));
+/// chain.create_account(Account::new(bob, Amount::from_ccd(100)));
+///
+/// assert_balance_changed!(chain, bob, by: AmountDelta::Positive(Amount::from_ccd(10)), {
+///     chain.transfer(Signer::with_one_key(), alice, bob, Amount::from_ccd(10)).unwrap();
+/// });
+/// ```
+#[macro_export]
+macro_rules! assert_balance_changed {
+    ($chain:expr, $address:expr,by: $delta:expr, $body:block $(,)?) => {{
+        let address: $crate::Address = ::core::convert::Into::into($address);
+        let before = $crate::macros::total_balance(&$chain, address);
+        let value = $body;
+        let after = $crate::macros::total_balance(&$chain, address);
+        let expected: $crate::AmountDelta = $delta;
+        if $crate::AmountDelta::between(before, after) != expected {
+            panic!("{}", $crate::macros::balance_change_mismatch(address, before, after, expected));
+        }
+        value
+    }};
+}
+
+/// Construct an [`Amount`](crate::Amount) from a decimal number of CCD, e.g.,
+/// `ccd!(12.5)`.
+///
+/// The number is parsed exactly with the [`FromStr`](std::str::FromStr)
+/// implementation of [`Amount`](crate::Amount), after removing any `_` digit
+/// separators. The macro panics if the number has more than 6
+/// decimals or is too large, instead of rounding.
+///
+/// ```
+/// # use concordium_smart_contract_testing::*;
+/// assert_eq!(ccd!(12.5), Amount::from_micro_ccd(12_500_000));
+/// assert_eq!(ccd!(1_000_000), Amount::from_ccd(1_000_000));
+/// ```
+#[macro_export]
+macro_rules! ccd {
+    ($amount:literal) => {
+        $crate::macros::ccd_literal(::core::stringify!($amount))
+    };
+}
+
+/// Parse the literal of [`ccd!`](crate::ccd).
+pub fn ccd_literal(literal: &str) -> Amount {
+    literal
+        .replace('_', "")
+        .parse()
+        .unwrap_or_else(|e| panic!("Invalid amount in `ccd!({literal})`: {e}"))
+}
+
+/// Get the total balance of an address, or zero if it does not exist.
+pub fn total_balance(chain: &Chain, address: Address) -> Amount { chain.total_balance(address) }
+
+/// The failure message of [`assert_amount_eq!`](crate::assert_amount_eq).
+pub fn amount_mismatch(actual: Amount, expected: Amount) -> String {
+    format!(
+        "assertion `actual == expected` failed for amounts\n    actual: {}\n  expected: \
+         {}\ndifference: {}",
+        format_ccd(actual),
+        format_ccd(expected),
+        format_delta(AmountDelta::between(expected, actual))
+    )
+}
+
+/// The failure message of
+/// [`assert_balance_changed!`](crate::assert_balance_changed).
+pub fn balance_change_mismatch(
+    address: Address,
+    before: Amount,
+    after: Amount,
+    expected: AmountDelta,
+) -> String {
+    format!(
+        "unexpected balance change for {}\n  before: {}\n   after: {}\n  change: {}\nexpected: {}",
+        format_address(address),
+        format_ccd(before),
+        format_ccd(after),
+        format_delta(AmountDelta::between(before, after)),
+        format_delta(expected)
+    )
+}
+
+/// Format an amount in CCD with [`AmountExt::to_ccd_string`], e.g. `1.5 CCD`.
+fn format_ccd(amount: Amount) -> String { format!("{} CCD", amount.to_ccd_string()) }
+
+/// Format a delta in CCD with an explicit sign, e.g. `-1.5 CCD`.
+fn format_delta(delta: AmountDelta) -> String {
+    match delta {
+        AmountDelta::Positive(amount) => format!("+{}", format_ccd(amount)),
+        AmountDelta::Negative(amount) => format!("-{}", format_ccd(amount)),
+    }
+}
+
+fn format_address(address: Address) -> String {
+    match address {
+        Address::Account(address) => format!("account {}", address),
+        Address::Contract(address) => format!("contract {}", address),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const ALICE: AccountAddress = AccountAddress([0; 32]);
+    const BOB: AccountAddress = AccountAddress([1; 32]);
+
+    /// Test the formatting of the amount mismatch message.
+    #[test]
+    fn test_amount_mismatch_message() {
+        let message =
+            super::amount_mismatch(Amount::from_micro_ccd(1_500_000), Amount::from_ccd(2));
+        assert!(message.contains("actual: 1.5 CCD"), "{message}");
+        assert!(message.contains("expected: 2 CCD"), "{message}");
+        assert!(message.contains("difference: -0.5 CCD"), "{message}");
+    }
+
+    /// Test that `assert_amount_eq!` panics on different amounts.
+    #[test]
+    #[should_panic(expected = "difference: +1 CCD")]
+    fn test_assert_amount_eq_fails() {
+        assert_amount_eq!(Amount::from_ccd(2), Amount::from_ccd(1));
+    }
+
+    /// Test that `assert_balance_changed!` checks the change of the balance
+    /// and returns the value of the block.
+    #[test]
+    fn test_assert_balance_changed() {
+        let mut chain = Chain::new();
+        chain.create_account(Account::new(ALICE, Amount::from_ccd(100)));
+        chain.create_account(Account::new(BOB, Amount::from_ccd(100)));
+
+        let res = assert_balance_changed!(chain, BOB, by: AmountDelta::Positive(Amount::from_ccd(10)), {
+            chain.transfer(Signer::with_one_key(), ALICE, BOB, Amount::from_ccd(10)).unwrap()
+        });
+        assert_balance_changed!(chain, ALICE, by: AmountDelta::Positive(Amount::zero()), {});
+        assert_amount_eq!(
+            chain.account_balance_available(ALICE).unwrap(),
+            Amount::from_ccd(90) - res.transaction_fee
+        );
+    }
+
+    /// Test that `ccd!` constructs exact amounts.
+    #[test]
+    fn test_ccd() {
+        assert_eq!(ccd!(12.5), Amount::from_micro_ccd(12_500_000));
+        assert_eq!(ccd!(0.000_001), Amount::from_micro_ccd(1));
+        assert_eq!(ccd!(1_000_000), Amount::from_ccd(1_000_000));
+    }
+
+    /// Test that `ccd!` panics instead of rounding.
+    #[test]
+    #[should_panic(expected = "Invalid amount in `ccd!(0.0000001)`")]
+    fn test_ccd_too_many_decimals() {
+        ccd!(0.0000001);
+    }
+
+    /// Test that `assert_balance_changed!` panics on an unexpected change.
+    #[test]
+    #[should_panic(expected = "change: -10 CCD")]
+    fn test_assert_balance_changed_fails() {
+        let mut chain = Chain::new();
+        chain.create_account(Account::new(ALICE, Amount::from_ccd(100)));
+        chain.create_account(Account::new(BOB, Amount::from_ccd(100)));
+
+        assert_balance_changed!(chain, BOB, by: AmountDelta::Negative(Amount::from_ccd(10)), {
+            chain.transfer(Signer::with_one_key(), ALICE, BOB, Amount::from_ccd(10)).unwrap();
+        });
+    }
+}
@@ -0,0 +1,412 @@
+//! Persisting the state of a [`Chain`] to disk and loading it again.
+//!
+//! The chain is stored in a binary format which starts with the magic bytes
+//! [`PERSIST_MAGIC`] followed by the format version
+//! [`PERSIST_FORMAT_VERSION`]. Files with a different version are rejected
+//! when loading, so that a file written by an incompatible version of the
+//! library never results in a corrupt chain.
+use crate::{Account, Chain, Contract, ContractModule};
+use concordium_rust_sdk::{
+    base::{
+        base::{Energy, ProtocolVersion},
+        common,
+        contracts_common::{
+            self, AccountAddress, AccountBalance, Amount, AttributeTag, AttributeValue,
+            ContractAddress, Cursor, Deserial, ExchangeRate, ExchangeRates, ModuleReference,
+            OwnedContractName, OwnedPolicy, Serial, SlotTime, Timestamp,
+        },
+        transactions::AccountAccessStructure,
+    },
+    smart_contracts::engine::{
+        v1::{self, trie},
+        wasm::{self, artifact, output::Output},
+    },
+};
+use std::{path::Path, sync::Arc};
+use thiserror::Error;
+
+/// The magic bytes at the start of a file written by [`Chain::persist`].
+pub const PERSIST_MAGIC: [u8; 8] = *b"CCDCHAIN";
+
+/// The version of the format written by [`Chain::persist`].
+///
+/// The version is increased whenever the format changes. [`Chain::load_from`]
+/// only accepts files with exactly this version.
+pub const PERSIST_FORMAT_VERSION: u32 = 1;
+
+/// An error that can occur while persisting or loading a chain with
+/// [`Chain::persist`] and [`Chain::load_from`], or while reading a transaction
+/// log with [`TransactionLog::from_bytes`](crate::TransactionLog::from_bytes).
+#[derive(Debug, Error)]
+pub enum ChainPersistError {
+    /// The file could not be read or written.
+    #[error("Could not access the chain file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The file does not start with [`PERSIST_MAGIC`].
+    #[error("The file is not a persisted chain.")]
+    NotAChainFile,
+    /// The data was written with another version of the format.
+    #[error("Unsupported format version {found}, only version {expected} is supported.")]
+    UnsupportedVersion {
+        /// The version of the file.
+        found:    u32,
+        /// The version supported by this library.
+        expected: u32,
+    },
+    /// The data has the right version but its contents are invalid.
+    #[error("The data is malformed: {0}")]
+    Malformed(String),
+    /// Part of the chain could not be serialized.
+    #[error("Could not serialize the chain: {0}")]
+    Serialization(String),
+}
+
+impl Chain {
+    /// Write the chain to the file at `path`, replacing it if it exists.
+    ///
+    /// Everything needed to continue using the chain is written: the chain
+    /// parameters, block time and scheduled exchange rates, the accounts,
+    /// the modules and their tags and the contracts including their state. The
+    /// file can be loaded again with [`Chain::load_from`], which makes it
+    /// possible to commit a "golden" chain and compare against it in later
+    /// tests.
+    ///
+    /// The external node connection, the saved checkpoints, the
+    /// [event log](Chain::event_log) and the registered callbacks are not
+    /// written.
+    pub fn persist(&self, path: impl AsRef<Path>) -> Result<(), ChainPersistError> {
+        std::fs::write(path, self.to_persisted_bytes()?)?;
+        Ok(())
+    }
+
+    /// Load a chain written by [`Chain::persist`] from the file at `path`.
+    ///
+    /// Returns an error if the file was written with an incompatible version
+    /// of the format or is malformed.
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Chain, ChainPersistError> {
+        Self::from_persisted_bytes(&std::fs::read(path)?)
+    }
+
+    /// Serialize the chain in the format used by [`Chain::persist`].
+    fn to_persisted_bytes(&self) -> Result<Vec<u8>, ChainPersistError> {
+        let mut out = Vec::from(PERSIST_MAGIC);
+        put(&mut out, &PERSIST_FORMAT_VERSION);
+
+        put(&mut out, &self.parameters.block_time);
+        put_rate(&mut out, self.parameters.micro_ccd_per_euro);
+        put_rate(&mut out, self.parameters.euro_per_energy);
+        put(&mut out, &(self.scheduled_exchange_rates.len() as u32));
+        for (time, rates) in self.scheduled_exchange_rates.iter() {
+            put(&mut out, time);
+            put_rate(&mut out, rates.micro_ccd_per_euro);
+            put_rate(&mut out, rates.euro_per_energy);
+        }
+        put(&mut out, &self.next_contract_index);
+        put(&mut out, &(self.max_state_key_length as u64));
+        put(&mut out, &self.default_account_balance);
+        put(&mut out, &self.max_call_depth.map(|depth| depth as u64));
+        put(&mut out, &self.max_state_writes.map(|writes| writes as u64));
+        put(&mut out, &u64::from(self.protocol_version));
+        put(&mut out, &self.debug_output);
+        put(&mut out, &self.strict_logs);
+        put(&mut out, &self.block_energy_limit.map(|energy| energy.energy));
+        put(&mut out, &self.block_energy_used.energy);
+        put(&mut out, &self.block_height);
+
+        put(&mut out, &(self.accounts.len() as u32));
+        for account in self.accounts.values() {
+            put(&mut out, &account.address);
+            put(&mut out, &account.balance.total);
+            put(&mut out, &account.balance.staked);
+            put(&mut out, &account.balance.locked);
+            put(&mut out, &account.nonce);
+            put(&mut out, &account.policy.identity_provider);
+            put(&mut out, &account.policy.created_at);
+            put(&mut out, &account.policy.valid_to);
+            put(&mut out, &(account.policy.items.len() as u32));
+            for (tag, value) in account.policy.items.iter() {
+                put(&mut out, &tag.0);
+                put(&mut out, value);
+            }
+            put(&mut out, &common::to_bytes(&account.keys));
+        }
+
+        put(&mut out, &(self.mocked_account_balances.len() as u32));
+        for (address, balance) in self.mocked_account_balances.iter() {
+            put(&mut out, &AccountAddress::from(*address));
+            put(&mut out, &balance.total);
+            put(&mut out, &balance.staked);
+            put(&mut out, &balance.locked);
+        }
+
+        put(&mut out, &(self.account_creation_times.len() as u32));
+        for (address, created_at) in self.account_creation_times.iter() {
+            put(&mut out, &AccountAddress::from(*address));
+            put(&mut out, created_at);
+        }
+
+        put(&mut out, &(self.modules.len() as u32));
+        for (module_reference, module) in self.modules.iter() {
+            put(&mut out, module_reference);
+            put(&mut out, &module.size);
+            let mut artifact = Vec::new();
+            module
+                .artifact
+                .output(&mut artifact)
+                .map_err(|e| ChainPersistError::Serialization(format!("module artifact: {e}")))?;
+            put(&mut out, &artifact);
+        }
+        put(&mut out, &(self.module_tags.len() as u32));
+        for (tag, module_reference) in self.module_tags.iter() {
+            put(&mut out, tag);
+            put(&mut out, module_reference);
+        }
+
+        put(&mut out, &(self.contracts.len() as u32));
+        for contract in self.contracts.values() {
+            put(&mut out, &contract.address);
+            put(&mut out, &contract.module_reference);
+            put(&mut out, &contract.contract_name);
+            put(&mut out, &contract.owner);
+            put(&mut out, &contract.self_balance);
+            let mut loader = v1::trie::Loader::new(&[][..]);
+            let mut state = Vec::new();
+            contract
+                .state
+                .serialize(&mut loader, &mut state)
+                .map_err(|e| ChainPersistError::Serialization(format!("contract state: {e}")))?;
+            put(&mut out, &state);
+        }
+        Ok(out)
+    }
+
+    /// Deserialize a chain in the format used by [`Chain::persist`].
+    fn from_persisted_bytes(bytes: &[u8]) -> Result<Chain, ChainPersistError> {
+        let Some(rest) = bytes.strip_prefix(&PERSIST_MAGIC[..]) else {
+            return Err(ChainPersistError::NotAChainFile);
+        };
+        let mut source = Cursor::new(rest);
+        let version: u32 = get(&mut source, "format version")?;
+        if version != PERSIST_FORMAT_VERSION {
+            return Err(ChainPersistError::UnsupportedVersion {
+                found:    version,
+                expected: PERSIST_FORMAT_VERSION,
+            });
+        }
+
+        let block_time: SlotTime = get(&mut source, "block time")?;
+        let micro_ccd_per_euro = get_rate(&mut source)?;
+        let euro_per_energy = get_rate(&mut source)?;
+        let mut chain =
+            Chain::new_with_time_and_rates(block_time, micro_ccd_per_euro, euro_per_energy)
+                .map_err(|e| ChainPersistError::Malformed(format!("exchange rates: {e}")))?;
+        let num_rates: u32 = get(&mut source, "scheduled exchange rates")?;
+        for _ in 0..num_rates {
+            let time: SlotTime = get(&mut source, "scheduled exchange rates")?;
+            let micro_ccd_per_euro = get_rate(&mut source)?;
+            let euro_per_energy = get_rate(&mut source)?;
+            chain.scheduled_exchange_rates.insert(time, ExchangeRates {
+                euro_per_energy,
+                micro_ccd_per_euro,
+            });
+        }
+        chain.next_contract_index = get(&mut source, "next contract index")?;
+        let max_state_key_length: u64 = get(&mut source, "maximum state key length")?;
+        chain.max_state_key_length = max_state_key_length as usize;
+        chain.default_account_balance = get(&mut source, "default account balance")?;
+        let max_call_depth: Option<u64> = get(&mut source, "maximum call depth")?;
+        chain.max_call_depth = max_call_depth.map(|depth| depth as usize);
+        let max_state_writes: Option<u64> = get(&mut source, "maximum state writes")?;
+        chain.max_state_writes = max_state_writes.map(|writes| writes as usize);
+        let protocol_version: u64 = get(&mut source, "protocol version")?;
+        chain.protocol_version = ProtocolVersion::try_from(protocol_version).map_err(|_| {
+            ChainPersistError::Malformed(format!("unknown protocol version {protocol_version}"))
+        })?;
+        chain.debug_output = get(&mut source, "debug output")?;
+        chain.strict_logs = get(&mut source, "strict logs schema")?;
+        let block_energy_limit: Option<u64> = get(&mut source, "block energy limit")?;
+        chain.block_energy_limit = block_energy_limit.map(Energy::from);
+        chain.block_energy_used = Energy::from(get::<u64>(&mut source, "block energy used")?);
+        chain.block_height = get(&mut source, "block height")?;
+
+        let num_accounts: u32 = get(&mut source, "accounts")?;
+        for _ in 0..num_accounts {
+            let address: AccountAddress = get(&mut source, "account address")?;
+            let total: Amount = get(&mut source, "account balance")?;
+            let staked: Amount = get(&mut source, "account balance")?;
+            let locked: Amount = get(&mut source, "account balance")?;
+            let nonce: u64 = get(&mut source, "account nonce")?;
+            let balance = AccountBalance::new(total, staked, locked).ok_or_else(|| {
+                ChainPersistError::Malformed(format!("invalid balance of account {address}"))
+            })?;
+            let identity_provider: u32 = get(&mut source, "account policy")?;
+            let created_at: Timestamp = get(&mut source, "account policy")?;
+            let valid_to: Timestamp = get(&mut source, "account policy")?;
+            let num_items: u32 = get(&mut source, "account policy")?;
+            let mut items = Vec::with_capacity(num_items as usize);
+            for _ in 0..num_items {
+                let tag: u8 = get(&mut source, "account policy")?;
+                let value: AttributeValue = get(&mut source, "account policy")?;
+                items.push((AttributeTag(tag), value));
+            }
+            let keys_bytes: Vec<u8> = get(&mut source, "account keys")?;
+            let keys: AccountAccessStructure =
+                common::from_bytes(&mut std::io::Cursor::new(keys_bytes)).map_err(|e| {
+                    ChainPersistError::Malformed(format!("keys of account {address}: {e}"))
+                })?;
+            chain.accounts.insert(address.into(), Account {
+                address,
+                balance,
+                policy: OwnedPolicy {
+                    identity_provider,
+                    created_at,
+                    valid_to,
+                    items,
+                },
+                keys,
+                nonce,
+            });
+        }
+
+        let num_mocks: u32 = get(&mut source, "mocked account balances")?;
+        for _ in 0..num_mocks {
+            let address: AccountAddress = get(&mut source, "mocked account address")?;
+            let total: Amount = get(&mut source, "mocked account balance")?;
+            let staked: Amount = get(&mut source, "mocked account balance")?;
+            let locked: Amount = get(&mut source, "mocked account balance")?;
+            let balance = AccountBalance::new(total, staked, locked).ok_or_else(|| {
+                ChainPersistError::Malformed(format!("invalid mocked balance of account {address}"))
+            })?;
+            chain.mocked_account_balances.insert(address.into(), balance);
+        }
+
+        let num_creation_times: u32 = get(&mut source, "account creation times")?;
+        for _ in 0..num_creation_times {
+            let address: AccountAddress = get(&mut source, "account address")?;
+            let created_at: Timestamp = get(&mut source, "account creation time")?;
+            chain.account_creation_times.insert(address.into(), created_at);
+        }
+
+        let num_modules: u32 = get(&mut source, "modules")?;
+        for _ in 0..num_modules {
+            let module_reference: ModuleReference = get(&mut source, "module reference")?;
+            let size: u64 = get(&mut source, "module size")?;
+            let artifact_bytes: Vec<u8> = get(&mut source, "module artifact")?;
+            let artifact: artifact::OwnedArtifact<v1::ProcessedImports> =
+                wasm::utils::parse_artifact(&artifact_bytes)
+                    .map_err(|e| {
+                        ChainPersistError::Malformed(format!(
+                            "artifact of module {module_reference}: {e}"
+                        ))
+                    })?
+                    .into();
+            chain.modules.insert(module_reference, ContractModule {
+                size,
+                artifact: Arc::new(artifact),
+            });
+        }
+        let num_module_tags: u32 = get(&mut source, "module tags")?;
+        for _ in 0..num_module_tags {
+            let tag: String = get(&mut source, "module tag")?;
+            let module_reference: ModuleReference = get(&mut source, "module reference")?;
+            chain.module_tags.insert(tag, module_reference);
+        }
+
+        let num_contracts: u32 = get(&mut source, "contracts")?;
+        for _ in 0..num_contracts {
+            let address: ContractAddress = get(&mut source, "contract address")?;
+            let module_reference: ModuleReference = get(&mut source, "contract module")?;
+            let contract_name: OwnedContractName = get(&mut source, "contract name")?;
+            let owner: AccountAddress = get(&mut source, "contract owner")?;
+            let self_balance: Amount = get(&mut source, "contract balance")?;
+            let state_bytes: Vec<u8> = get(&mut source, "contract state")?;
+            let state = trie::PersistentState::deserialize(&mut std::io::Cursor::new(state_bytes))
+                .map_err(|e| {
+                    ChainPersistError::Malformed(format!("state of contract {address}: {e}"))
+                })?;
+            chain.contracts.insert(address, Contract {
+                address,
+                module_reference,
+                contract_name,
+                state,
+                owner,
+                self_balance,
+            });
+        }
+
+        if source.offset != rest.len() {
+            return Err(ChainPersistError::Malformed(
+                "unexpected bytes after the end of the chain".into(),
+            ));
+        }
+        Ok(chain)
+    }
+}
+
+/// Append the serialization of a value to the output.
+pub(crate) fn put<T: Serial + ?Sized>(out: &mut Vec<u8>, value: &T) {
+    value.serial(out).expect("Writing to a vector succeeds.");
+}
+
+/// Append an exchange rate as its numerator followed by its denominator.
+pub(crate) fn put_rate(out: &mut Vec<u8>, rate: ExchangeRate) {
+    put(out, &rate.numerator());
+    put(out, &rate.denominator());
+}
+
+/// Read a value, describing the part of the chain being read on failure.
+pub(crate) fn get<T: Deserial>(
+    source: &mut Cursor<&[u8]>,
+    what: &str,
+) -> Result<T, ChainPersistError> {
+    T::deserial(source).map_err(|_| ChainPersistError::Malformed(format!("could not read {what}")))
+}
+
+/// Read an exchange rate written by [`put_rate`].
+pub(crate) fn get_rate(source: &mut Cursor<&[u8]>) -> Result<ExchangeRate, ChainPersistError> {
+    let numerator: u64 = get(source, "exchange rate")?;
+    let denominator: u64 = get(source, "exchange rate")?;
+    ExchangeRate::new(numerator, denominator)
+        .ok_or_else(|| ChainPersistError::Malformed("invalid exchange rate".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a chain survives a round-trip through the persisted format,
+    /// and that other versions of the format are rejected.
+    #[test]
+    fn test_persisted_bytes_round_trip() {
+        let mut chain = Chain::new();
+        chain.create_account(Account::new(AccountAddress([0; 32]), Amount::from_ccd(10)));
+        let bytes = chain.to_persisted_bytes().expect("Serialization succeeds");
+        let loaded = Chain::from_persisted_bytes(&bytes).expect("Deserialization succeeds");
+        assert_eq!(
+            loaded.account_balance(AccountAddress([0; 32])),
+            Some(
+                AccountBalance::new(Amount::from_ccd(10), Amount::zero(), Amount::zero()).unwrap()
+            )
+        );
+        assert_eq!(loaded.block_time(), chain.block_time());
+
+        let mut other_version = bytes.clone();
+        other_version[PERSIST_MAGIC.len()..][..4]
+            .copy_from_slice(&contracts_common::to_bytes(&(PERSIST_FORMAT_VERSION + 1)));
+        assert!(matches!(
+            Chain::from_persisted_bytes(&other_version),
+            Err(ChainPersistError::UnsupportedVersion {
+                found,
+                expected: PERSIST_FORMAT_VERSION,
+            }) if found == PERSIST_FORMAT_VERSION + 1
+        ));
+        assert!(matches!(
+            Chain::from_persisted_bytes(b"not a chain"),
+            Err(ChainPersistError::NotAChainFile)
+        ));
+        assert!(matches!(
+            Chain::from_persisted_bytes(&bytes[..bytes.len() - 1]),
+            Err(ChainPersistError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,485 @@
+//! Recording the transactions executed on a [`Chain`] and replaying them on
+//! another chain.
+//!
+//! This makes it possible to reproduce a failing scenario deterministically,
+//! for example by saving the [`TransactionLog`] of a fuzzing run which found a
+//! bug and replaying it in a regression test.
+use crate::{
+    persist::{get, get_rate, put, put_rate},
+    Chain, ChainPersistError, ContractInvokeSuccess, Signer,
+};
+use concordium_rust_sdk::base::{
+    base::Energy,
+    common,
+    contracts_common::{
+        AccountAddress, Address, Amount, ContractAddress, Cursor, ExchangeRate, ModuleReference,
+        OwnedContractName, OwnedParameter, OwnedReceiveName, Timestamp,
+    },
+    smart_contracts::{ContractEvent, WasmModule},
+    transactions::{InitContractPayload, UpdateContractPayload},
+};
+
+/// The version of the format written by [`TransactionLog::to_bytes`].
+///
+/// The version is increased whenever the format changes, independently of
+/// [`PERSIST_FORMAT_VERSION`](crate::PERSIST_FORMAT_VERSION).
+/// [`TransactionLog::from_bytes`] only accepts logs with exactly this version.
+pub const TRANSACTION_LOG_FORMAT_VERSION: u32 = 1;
+
+/// A transaction recorded in a [`TransactionLog`], with all the arguments it
+/// was executed with.
+#[derive(Debug, Clone)]
+pub enum Transaction {
+    /// A module deployment with [`Chain::module_deploy_v1_debug`], which is
+    /// also used by the other methods for deploying modules.
+    ModuleDeploy {
+        signer:       Signer,
+        sender:       AccountAddress,
+        module:       WasmModule,
+        enable_debug: bool,
+    },
+    /// A contract initialization with [`Chain::contract_init`].
+    ContractInit {
+        signer:          Signer,
+        sender:          AccountAddress,
+        energy_reserved: Energy,
+        payload:         InitContractPayload,
+    },
+    /// A contract update with [`Chain::contract_update`].
+    ContractUpdate {
+        signer:          Signer,
+        invoker:         AccountAddress,
+        sender:          Address,
+        energy_reserved: Energy,
+        payload:         UpdateContractPayload,
+    },
+    /// A transfer between accounts with [`Chain::transfer`].
+    Transfer {
+        signer: Signer,
+        from:   AccountAddress,
+        to:     AccountAddress,
+        amount: Amount,
+    },
+}
+
+/// A transaction together with the block time and exchange rates in effect
+/// when it was executed.
+#[derive(Debug, Clone)]
+pub struct RecordedTransaction {
+    /// The block time.
+    pub block_time:         Timestamp,
+    /// The exchange rate between microCCD and euro.
+    pub micro_ccd_per_euro: ExchangeRate,
+    /// The exchange rate between euro and energy.
+    pub euro_per_energy:    ExchangeRate,
+    /// The transaction.
+    pub transaction:        Transaction,
+}
+
+/// The transactions recorded on a chain, see [`Chain::start_recording`].
+///
+/// The log can be serialized with [`TransactionLog::to_bytes`], for example
+/// to save it to a file, and replayed with [`Chain::replay`].
+#[derive(Debug, Clone, Default)]
+pub struct TransactionLog {
+    /// The transactions in the order they were executed.
+    pub transactions: Vec<RecordedTransaction>,
+}
+
+/// The outcome of a transaction replayed with [`Chain::replay`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionOutcome {
+    /// Whether the transaction succeeded.
+    pub success:         bool,
+    /// The energy used.
+    pub energy_used:     Energy,
+    /// The transaction fee.
+    pub transaction_fee: Amount,
+    /// The events logged by contracts, together with the contract which
+    /// logged them, in the order they were logged.
+    pub events:          Vec<(ContractAddress, ContractEvent)>,
+}
+
+/// The result of executing a block of transactions with
+/// [`Chain::simulate_block`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockResult {
+    /// The outcomes of the transactions, in the order they were executed.
+    pub outcomes:    Vec<TransactionOutcome>,
+    /// The energy used by all the transactions of the block.
+    pub energy_used: Energy,
+}
+
+impl Chain {
+    /// Execute `transactions` as a new block, in the given order, and return
+    /// their outcomes.
+    ///
+    /// A new block is started first, see [`Chain::new_block`], so all the
+    /// transactions share the energy of the block limit set with
+    /// [`Chain::set_block_energy_limit`], if any. A transaction which does not
+    /// fit in the energy remaining in the block fails without being charged,
+    /// and the following transactions are still executed. The block time is
+    /// the same for all the transactions.
+    ///
+    /// Each transaction is committed before the next one is executed, so the
+    /// outcome of a transaction can depend on the transactions before it. This
+    /// makes it possible to test how a contract behaves under different
+    /// orderings of the same transactions.
+    pub fn simulate_block(&mut self, transactions: Vec<Transaction>) -> BlockResult {
+        self.new_block();
+        let outcomes = transactions
+            .into_iter()
+            .map(|transaction| self.execute_recorded(transaction))
+            .collect();
+        BlockResult {
+            outcomes,
+            energy_used: self.block_energy_used,
+        }
+    }
+
+    /// Start recording the transactions executed on the chain, discarding any
+    /// transactions recorded so far.
+    ///
+    /// Module deployments, contract initializations and updates and transfers
+    /// between accounts are recorded, whether they succeed or not. Changes
+    /// made directly to the chain, such as creating accounts or ticking the
+    /// block time, are not, so a replay must start from a chain set up the
+    /// same way as this one was when the recording started.
+    pub fn start_recording(&mut self) { self.recording = Some(TransactionLog::default()); }
+
+    /// Stop recording transactions and return the transactions recorded,
+    /// see [`Chain::start_recording`].
+    pub fn stop_recording(&mut self) -> TransactionLog { self.recording.take().unwrap_or_default() }
+
+    /// The transactions recorded since [`Chain::start_recording`] was called.
+    /// The log is empty if the chain is not recording.
+    pub fn recorded(&self) -> TransactionLog { self.recording.clone().unwrap_or_default() }
+
+    /// Execute the transactions of `log` on the chain, in order, and return
+    /// their outcomes.
+    ///
+    /// The block time and exchange rates of the chain are set to those
+    /// recorded with each transaction before it is executed. When the chain is
+    /// set up the same way as the recorded chain was when the recording
+    /// started, the transactions therefore have the same outcomes and leave
+    /// the chain in the same state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use concordium_smart_contract_testing::*;
+    /// let setup = || {
+    ///     let mut chain = Chain::new();
+    ///     chain.create_account(Account::new(AccountAddress([0; 32]), Amount::from_ccd(10)));
+    ///     chain.create_account(Account::new(AccountAddress([1; 32]), Amount::from_ccd(10)));
+    ///     chain
+    /// };
+    /// let mut chain = setup();
+    /// chain.start_recording();
+    /// chain
+    ///     .transfer(
+    ///         Signer::with_one_key(),
+    ///         AccountAddress([0; 32]),
+    ///         AccountAddress([1; 32]),
+    ///         Amount::from_ccd(1),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let log = TransactionLog::from_bytes(&chain.recorded().to_bytes()).unwrap();
+    /// let mut replayed = setup();
+    /// let outcomes = replayed.replay(&log);
+    /// assert!(outcomes[0].success);
+    /// assert_eq!(
+    ///     replayed.account_balance_available(AccountAddress([1; 32])),
+    ///     chain.account_balance_available(AccountAddress([1; 32]))
+    /// );
+    /// ```
+    pub fn replay(&mut self, log: &TransactionLog) -> Vec<TransactionOutcome> {
+        log.transactions
+            .iter()
+            .map(|recorded| {
+                self.parameters.block_time = recorded.block_time;
+                self.parameters.micro_ccd_per_euro = recorded.micro_ccd_per_euro;
+                self.parameters.euro_per_energy = recorded.euro_per_energy;
+                self.execute_recorded(recorded.transaction.clone())
+            })
+            .collect()
+    }
+
+    /// Add the transaction constructed by `transaction` to the log if the
+    /// chain is recording.
+    pub(crate) fn record(&mut self, transaction: impl FnOnce() -> Transaction) {
+        if let Some(log) = &mut self.recording {
+            log.transactions.push(RecordedTransaction {
+                block_time:         self.parameters.block_time,
+                micro_ccd_per_euro: self.parameters.micro_ccd_per_euro,
+                euro_per_energy:    self.parameters.euro_per_energy,
+                transaction:        transaction(),
+            });
+        }
+    }
+
+    /// Execute a transaction and summarize its outcome.
+    fn execute_recorded(&mut self, transaction: Transaction) -> TransactionOutcome {
+        match transaction {
+            Transaction::ModuleDeploy {
+                signer,
+                sender,
+                module,
+                enable_debug,
+            } => match self.module_deploy_v1_debug(signer, sender, module, enable_debug) {
+                Ok(success) => {
+                    TransactionOutcome::new(true, success.energy_used, success.transaction_fee)
+                }
+                Err(error) => {
+                    TransactionOutcome::new(false, error.energy_used, error.transaction_fee)
+                }
+            },
+            Transaction::ContractInit {
+                signer,
+                sender,
+                energy_reserved,
+                payload,
+            } => match self.contract_init(signer, sender, energy_reserved, payload) {
+                Ok(success) => TransactionOutcome {
+                    events: success
+                        .events
+                        .iter()
+                        .map(|event| (success.contract_address, event.clone()))
+                        .collect(),
+                    ..TransactionOutcome::new(true, success.energy_used, success.transaction_fee)
+                },
+                Err(error) => {
+                    TransactionOutcome::new(false, error.energy_used, error.transaction_fee)
+                }
+            },
+            Transaction::ContractUpdate {
+                signer,
+                invoker,
+                sender,
+                energy_reserved,
+                payload,
+            } => match self.contract_update(signer, invoker, sender, energy_reserved, payload) {
+                Ok(success) => TransactionOutcome {
+                    events: update_events(&success),
+                    ..TransactionOutcome::new(true, success.energy_used, success.transaction_fee)
+                },
+                Err(error) => {
+                    TransactionOutcome::new(false, error.energy_used, error.transaction_fee)
+                }
+            },
+            Transaction::Transfer {
+                signer,
+                from,
+                to,
+                amount,
+            } => match self.transfer(signer, from, to, amount) {
+                Ok(success) => {
+                    TransactionOutcome::new(true, success.energy_used, success.transaction_fee)
+                }
+                // Failed transfers are not executed, so nothing is charged.
+                Err(_) => TransactionOutcome::new(false, Energy::from(0), Amount::zero()),
+            },
+        }
+    }
+}
+
+impl TransactionOutcome {
+    /// An outcome without events.
+    fn new(success: bool, energy_used: Energy, transaction_fee: Amount) -> Self {
+        Self {
+            success,
+            energy_used,
+            transaction_fee,
+            events: Vec::new(),
+        }
+    }
+}
+
+/// The events logged in a successful update, paired with the contract which
+/// logged them.
+fn update_events(success: &ContractInvokeSuccess) -> Vec<(ContractAddress, ContractEvent)> {
+    success
+        .events()
+        .flat_map(|(address, events)| events.iter().map(move |event| (address, event.clone())))
+        .collect()
+}
+
+impl TransactionLog {
+    /// Serialize the log, starting with the format version
+    /// [`TRANSACTION_LOG_FORMAT_VERSION`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        put(&mut out, &TRANSACTION_LOG_FORMAT_VERSION);
+        put(&mut out, &(self.transactions.len() as u32));
+        for recorded in self.transactions.iter() {
+            put(&mut out, &recorded.block_time);
+            put_rate(&mut out, recorded.micro_ccd_per_euro);
+            put_rate(&mut out, recorded.euro_per_energy);
+            match &recorded.transaction {
+                Transaction::ModuleDeploy {
+                    signer,
+                    sender,
+                    module,
+                    enable_debug,
+                } => {
+                    put(&mut out, &0u8);
+                    put(&mut out, &signer.num_keys);
+                    put(&mut out, sender);
+                    put(&mut out, enable_debug);
+                    put(&mut out, &common::to_bytes(module));
+                }
+                Transaction::ContractInit {
+                    signer,
+                    sender,
+                    energy_reserved,
+                    payload,
+                } => {
+                    put(&mut out, &1u8);
+                    put(&mut out, &signer.num_keys);
+                    put(&mut out, sender);
+                    put(&mut out, &energy_reserved.energy);
+                    put(&mut out, &payload.mod_ref);
+                    put(&mut out, &payload.init_name);
+                    put(&mut out, &payload.param.as_ref().to_vec());
+                    put(&mut out, &payload.amount);
+                }
+                Transaction::ContractUpdate {
+                    signer,
+                    invoker,
+                    sender,
+                    energy_reserved,
+                    payload,
+                } => {
+                    put(&mut out, &2u8);
+                    put(&mut out, &signer.num_keys);
+                    put(&mut out, invoker);
+                    put(&mut out, sender);
+                    put(&mut out, &energy_reserved.energy);
+                    put(&mut out, &payload.address);
+                    put(&mut out, &payload.receive_name);
+                    put(&mut out, &payload.message.as_ref().to_vec());
+                    put(&mut out, &payload.amount);
+                }
+                Transaction::Transfer {
+                    signer,
+                    from,
+                    to,
+                    amount,
+                } => {
+                    put(&mut out, &3u8);
+                    put(&mut out, &signer.num_keys);
+                    put(&mut out, from);
+                    put(&mut out, to);
+                    put(&mut out, amount);
+                }
+            }
+        }
+        out
+    }
+
+    /// Deserialize a log written by [`TransactionLog::to_bytes`].
+    ///
+    /// Returns an error if the log was written with another version of the
+    /// format or is malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ChainPersistError> {
+        let mut source = Cursor::new(bytes);
+        let version: u32 = get(&mut source, "format version")?;
+        if version != TRANSACTION_LOG_FORMAT_VERSION {
+            return Err(ChainPersistError::UnsupportedVersion {
+                found:    version,
+                expected: TRANSACTION_LOG_FORMAT_VERSION,
+            });
+        }
+        let num_transactions: u32 = get(&mut source, "transactions")?;
+        let mut transactions = Vec::new();
+        for _ in 0..num_transactions {
+            let block_time: Timestamp = get(&mut source, "block time")?;
+            let micro_ccd_per_euro = get_rate(&mut source)?;
+            let euro_per_energy = get_rate(&mut source)?;
+            let tag: u8 = get(&mut source, "transaction type")?;
+            let signer = Signer::with_keys(get(&mut source, "number of keys")?)
+                .map_err(|e| ChainPersistError::Malformed(format!("signer: {e}")))?;
+            let transaction = match tag {
+                0 => {
+                    let sender = get(&mut source, "sender")?;
+                    let enable_debug = get(&mut source, "debug flag")?;
+                    let module_bytes: Vec<u8> = get(&mut source, "module")?;
+                    let module = common::from_bytes(&mut std::io::Cursor::new(module_bytes))
+                        .map_err(|e| ChainPersistError::Malformed(format!("module: {e}")))?;
+                    Transaction::ModuleDeploy {
+                        signer,
+                        sender,
+                        module,
+                        enable_debug,
+                    }
+                }
+                1 => {
+                    let sender = get(&mut source, "sender")?;
+                    let energy: u64 = get(&mut source, "energy")?;
+                    let mod_ref: ModuleReference = get(&mut source, "module reference")?;
+                    let init_name: OwnedContractName = get(&mut source, "contract name")?;
+                    let param: Vec<u8> = get(&mut source, "parameter")?;
+                    let amount = get(&mut source, "amount")?;
+                    Transaction::ContractInit {
+                        signer,
+                        sender,
+                        energy_reserved: Energy::from(energy),
+                        payload: InitContractPayload {
+                            amount,
+                            mod_ref,
+                            init_name,
+                            param: OwnedParameter::new_unchecked(param),
+                        },
+                    }
+                }
+                2 => {
+                    let invoker = get(&mut source, "invoker")?;
+                    let sender = get(&mut source, "sender")?;
+                    let energy: u64 = get(&mut source, "energy")?;
+                    let address = get(&mut source, "contract address")?;
+                    let receive_name: OwnedReceiveName = get(&mut source, "receive name")?;
+                    let message: Vec<u8> = get(&mut source, "parameter")?;
+                    let amount = get(&mut source, "amount")?;
+                    Transaction::ContractUpdate {
+                        signer,
+                        invoker,
+                        sender,
+                        energy_reserved: Energy::from(energy),
+                        payload: UpdateContractPayload {
+                            amount,
+                            address,
+                            receive_name,
+                            message: OwnedParameter::new_unchecked(message),
+                        },
+                    }
+                }
+                3 => Transaction::Transfer {
+                    signer,
+                    from: get(&mut source, "sender")?,
+                    to: get(&mut source, "receiver")?,
+                    amount: get(&mut source, "amount")?,
+                },
+                tag => {
+                    return Err(ChainPersistError::Malformed(format!(
+                        "unknown transaction type {tag}"
+                    )))
+                }
+            };
+            transactions.push(RecordedTransaction {
+                block_time,
+                micro_ccd_per_euro,
+                euro_per_energy,
+                transaction,
+            });
+        }
+        if source.offset != bytes.len() {
+            return Err(ChainPersistError::Malformed(
+                "unexpected bytes after the end of the transaction log".into(),
+            ));
+        }
+        Ok(Self {
+            transactions,
+        })
+    }
+}
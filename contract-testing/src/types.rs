@@ -1,22 +1,25 @@
+use crate::replay::TransactionLog;
 use concordium_rust_sdk as sdk;
 use concordium_rust_sdk::{
     base::{
-        base::{AccountAddressEq, Energy},
+        base::{AccountAddressEq, Energy, ProtocolVersion},
         common::types::{CredentialIndex, KeyIndex, Signature},
         constants::ED25519_SIGNATURE_LENGTH,
         contracts_common::{
-            self, AccountAddress, AccountBalance, Address, Amount, ContractAddress, Deserial,
-            EntrypointName, ExchangeRate, ModuleReference, OwnedContractName, OwnedEntrypointName,
-            OwnedPolicy, ParseResult, SlotTime, Timestamp,
+            self, constants::MAX_PARAMETER_LEN, schema, AccountAddress, AccountBalance, Address,
+            Amount, ContractAddress, Deserial, EntrypointName, ExchangeRate, ExchangeRates,
+            ModuleReference, NewContractNameError, NewReceiveNameError, OwnedContractName,
+            OwnedEntrypointName, OwnedPolicy, ParseResult, Serial, SlotTime, Timestamp,
         },
         hashes::BlockHash,
-        id::types::SchemeId,
+        id::types::{AccountKeys, SchemeId},
         smart_contracts::{
             ContractEvent, ContractTraceElement, InstanceUpdatedEvent, OwnedParameter,
             OwnedReceiveName, WasmVersion,
         },
         transactions::AccountAccessStructure,
     },
+    cis2::Event as Cis2Event,
     smart_contracts::engine::{
         v1::{
             self, trie, DebugTracker, EmittedDebugStatement, HostCall, HostFunctionV1, ReturnValue,
@@ -43,7 +46,7 @@ pub struct ContractModule {
 }
 
 /// The chain parameters.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct ChainParameters {
     /// The block time viewable inside the smart contracts.
     /// Defaults to `0`.
@@ -88,6 +91,124 @@ pub struct Chain {
     pub(crate) next_contract_index: u64,
     /// An optional connection to an external node.
     pub(crate) external_node_connection: Option<ExternalNodeConnection>,
+    /// The maximum length of a key in the state of a contract instance.
+    pub(crate) max_state_key_length: usize,
+    /// The maximum depth of nested contract calls, see
+    /// [`Chain::set_max_call_depth`].
+    pub(crate) max_call_depth: Option<usize>,
+    /// The maximum number of state entries an update may write, see
+    /// [`Chain::set_max_state_writes_per_update`].
+    pub(crate) max_state_writes: Option<usize>,
+    /// The event schema that the events logged in updates and invocations are
+    /// checked against, see [`Chain::set_strict_logs`].
+    pub(crate) strict_logs: Option<schema::Type>,
+    /// The balance of accounts created with [`Chain::create_funded_account`].
+    pub(crate) default_account_balance: Amount,
+    /// Balances returned when contracts query accounts that do not exist, set
+    /// via [`Chain::mock_account_balance`].
+    pub(crate) mocked_account_balances: BTreeMap<AccountAddressEq, AccountBalance>,
+    /// The block time at which each account was created, see
+    /// [`Chain::account_created_at`].
+    pub(crate) account_creation_times: BTreeMap<AccountAddressEq, Timestamp>,
+    /// The protocol version whose Wasm validation rules and cost model are
+    /// used when deploying modules, see [`ChainBuilder::protocol_version`].
+    pub(crate) protocol_version: ProtocolVersion,
+    /// Whether modules deployed with [`Chain::module_deploy_v1`] may use the
+    /// debug host functions, see [`Chain::enable_debug_output`].
+    pub(crate) debug_output: bool,
+    /// Exchange rates scheduled to take effect at a block time, set via
+    /// [`Chain::schedule_exchange_rates`].
+    pub(crate) scheduled_exchange_rates: BTreeMap<SlotTime, ExchangeRates>,
+    /// Checkpoints saved with a label via [`Chain::save`].
+    pub(crate) checkpoints: BTreeMap<String, Checkpoint>,
+    /// The maximum energy that the transactions in a block may use, set via
+    /// [`Chain::set_block_energy_limit`].
+    pub(crate) block_energy_limit: Option<Energy>,
+    /// The energy used by the transactions in the current block.
+    pub(crate) block_energy_used: Energy,
+    /// The height of the current block, see [`Chain::block_height`].
+    pub(crate) block_height: u64,
+    /// The modules deployed with a tag via [`Chain::deploy_tagged`].
+    pub(crate) module_tags: BTreeMap<String, ModuleReference>,
+    /// The events emitted by contracts, see [`Chain::event_log`].
+    pub(crate) event_log: Vec<ChainEvent>,
+    /// The number of transactions executed on the chain.
+    pub(crate) transaction_count: u64,
+    /// The transaction fees paid on the chain, see [`Chain::fees_collected`].
+    pub(crate) fees_collected: Amount,
+    /// Callbacks called during contract invocations.
+    pub(crate) hooks: ChainHooks,
+    /// The modification index checks made in the updates of the scenario run
+    /// by [`Chain::detect_reentrancy`], if one is running.
+    pub(crate) reentrancy_log: Option<Vec<ModificationCheck>>,
+    /// The transactions recorded since [`Chain::start_recording`] was called,
+    /// if the chain is recording.
+    pub(crate) recording: Option<TransactionLog>,
+}
+
+/// A callback called with a contract address and the balance the contract
+/// observes, see [`Chain::on_self_balance_query`].
+pub(crate) type SelfBalanceHook = dyn Fn(ContractAddress, Amount) + Send + Sync;
+
+/// A callback called when a contract upgrades its module, see
+/// [`Chain::on_upgrade`].
+pub(crate) type UpgradeHook = dyn Fn(&UpgradeEvent) + Send + Sync;
+
+/// The callbacks registered on a [`Chain`], which are called during contract
+/// invocations.
+///
+/// The callbacks are shared with forks of the chain.
+#[derive(Clone, Default)]
+pub(crate) struct ChainHooks {
+    /// Called whenever a contract is given its own balance.
+    pub(crate) self_balance_query: Option<Arc<SelfBalanceHook>>,
+    /// Called when a contract upgrades its module.
+    pub(crate) upgrade:            Option<Arc<UpgradeHook>>,
+}
+
+impl std::fmt::Debug for ChainHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChainHooks")
+            .field("self_balance_query", &self.self_balance_query.is_some())
+            .field("upgrade", &self.upgrade.is_some())
+            .finish()
+    }
+}
+
+/// A snapshot of the state of a [`Chain`], created with
+/// [`Chain::checkpoint`] and restored with [`Chain::restore`].
+///
+/// The snapshot includes the accounts with their creation times, the mocked
+/// account balances, modules, contracts, block time, exchange rates, block
+/// height and the energy used in the current block, but not the external node
+/// connection or the labelled checkpoints.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub(crate) parameters:               ChainParameters,
+    pub(crate) accounts:                 BTreeMap<AccountAddressEq, Account>,
+    pub(crate) modules:                  BTreeMap<ModuleReference, ContractModule>,
+    pub(crate) contracts:                BTreeMap<ContractAddress, Contract>,
+    pub(crate) next_contract_index:      u64,
+    pub(crate) mocked_account_balances:  BTreeMap<AccountAddressEq, AccountBalance>,
+    pub(crate) account_creation_times:   BTreeMap<AccountAddressEq, Timestamp>,
+    pub(crate) scheduled_exchange_rates: BTreeMap<SlotTime, ExchangeRates>,
+    pub(crate) block_energy_used:        Energy,
+    pub(crate) block_height:             u64,
+    pub(crate) module_tags:              BTreeMap<String, ModuleReference>,
+    pub(crate) event_log:                Vec<ChainEvent>,
+    pub(crate) transaction_count:        u64,
+    pub(crate) fees_collected:           Amount,
+}
+
+/// An event emitted by a contract, as recorded in [`Chain::event_log`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainEvent {
+    /// The index of the transaction which emitted the event.
+    pub transaction_index: u64,
+    /// The contract which emitted the event.
+    pub address:           ContractAddress,
+    /// The event itself.
+    pub event:             ContractEvent,
 }
 
 /// A builder for the [`Chain`].
@@ -112,6 +233,31 @@ pub struct ChainBuilder {
     pub(crate) block_time: Option<Timestamp>,
     /// Whether the block time should be set via the external node.
     pub(crate) block_time_from_external: bool,
+    /// The configured maximum length of a key in the state of a contract
+    /// instance.
+    pub(crate) max_state_key_length: Option<usize>,
+    /// The configured balance of accounts created with
+    /// [`Chain::create_funded_account`].
+    pub(crate) default_account_balance: Option<Amount>,
+    /// The configured protocol version.
+    pub(crate) protocol_version: Option<ProtocolVersion>,
+}
+
+/// The energy used by a batched operation compared to the energy used by
+/// performing the operation one item at a time, as returned by
+/// [`Chain::compare_batch_energy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchEnergyComparison {
+    /// The energy used by the batched operation.
+    pub batched:    Energy,
+    /// The total energy used by the individual operations.
+    pub individual: Energy,
+}
+
+impl BatchEnergyComparison {
+    /// Whether the batched operation used less energy in total than the
+    /// individual operations.
+    pub fn batch_is_cheaper(&self) -> bool { self.batched < self.individual }
 }
 
 /// A smart contract instance.
@@ -131,6 +277,173 @@ pub struct Contract {
     pub self_balance:     Amount,
 }
 
+/// A contract instance to insert with [`Chain::insert_contract_unchecked`],
+/// without running its init function.
+#[derive(Clone, Debug)]
+pub struct ContractSpec {
+    /// The module which contains the contract. It must be deployed.
+    pub module_reference: ModuleReference,
+    /// The name of the contract, including the `init_` prefix.
+    pub contract_name:    OwnedContractName,
+    /// The key-value entries of the initial state.
+    pub state:            Vec<(Vec<u8>, Vec<u8>)>,
+    /// The owner of the contract.
+    pub owner:            AccountAddress,
+    /// The balance of the contract.
+    pub balance:          Amount,
+}
+
+/// The differences between two chains, as returned by [`Chain::diff`].
+///
+/// Only the accounts and contracts which differ are included, ordered by
+/// their addresses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainDiff {
+    /// The block time of the first chain.
+    pub block_time_before: Timestamp,
+    /// The block time of the second chain.
+    pub block_time_after:  Timestamp,
+    /// The modules deployed on the second chain but not on the first.
+    pub modules_added:     Vec<ModuleReference>,
+    /// The modules deployed on the first chain but not on the second.
+    pub modules_removed:   Vec<ModuleReference>,
+    /// The accounts whose total balance differs, including the accounts only
+    /// existing on one of the chains.
+    pub accounts:          Vec<AccountDiff>,
+    /// The contract instances which differ.
+    pub contracts:         Vec<ContractDiff>,
+}
+
+/// The difference in the total balance of an account between two chains,
+/// see [`ChainDiff`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccountDiff {
+    /// The address of the account.
+    pub address:        AccountAddress,
+    /// The total balance on the first chain, or `None` if the account does
+    /// not exist there.
+    pub balance_before: Option<Amount>,
+    /// The total balance on the second chain, or `None` if the account does
+    /// not exist there.
+    pub balance_after:  Option<Amount>,
+}
+
+/// The differences in a contract instance between two chains, see
+/// [`ChainDiff`].
+///
+/// Fields for things which did not change are the same in the `before` and
+/// `after` versions, and `state` is empty if the state did not change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractDiff {
+    /// The address of the contract.
+    pub address:        ContractAddress,
+    /// The balance on the first chain, or `None` if the contract does not
+    /// exist there.
+    pub balance_before: Option<Amount>,
+    /// The balance on the second chain, or `None` if the contract does not
+    /// exist there.
+    pub balance_after:  Option<Amount>,
+    /// The module on the first chain, or `None` if the contract does not
+    /// exist there.
+    pub module_before:  Option<ModuleReference>,
+    /// The module on the second chain, or `None` if the contract does not
+    /// exist there.
+    pub module_after:   Option<ModuleReference>,
+    /// The changes to the state. A contract which only exists on one of the
+    /// chains is treated as having an empty state on the other.
+    pub state:          StateDiff,
+}
+
+/// The changes between two versions of the state of a contract, at the
+/// granularity of keys.
+///
+/// All the lists are ordered by keys.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    /// The entries only present in the new state.
+    pub added:   Vec<(Vec<u8>, Vec<u8>)>,
+    /// The entries only present in the old state.
+    pub removed: Vec<(Vec<u8>, Vec<u8>)>,
+    /// The keys present in both states with a different value.
+    pub changed: Vec<StateEntryChange>,
+}
+
+/// The number of state entries written by a contract update or invocation,
+/// see [`ContractInvokeSuccess::state_writes`].
+///
+/// Each call of a host function writing to the state of a contract counts as
+/// one write, across all the contracts called by the update, and including
+/// the calls of contracts which are rolled back later. An entry which is
+/// written several times therefore counts once for each write, and an entry
+/// which is created and then deleted again counts both as created and as
+/// deleted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StateWrites {
+    /// The number of entries created.
+    pub created:  usize,
+    /// The number of writes to the value of an entry, including resizing it.
+    pub modified: usize,
+    /// The number of deletions of an entry, or of all the entries with a
+    /// given prefix.
+    pub deleted:  usize,
+}
+
+impl StateWrites {
+    /// The total number of entries written.
+    pub fn total(&self) -> usize { self.created + self.modified + self.deleted }
+}
+
+/// A key whose value differs between two versions of a contract state, see
+/// [`StateDiff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateEntryChange {
+    /// The key of the entry.
+    pub key:    Vec<u8>,
+    /// The value in the old state.
+    pub before: Vec<u8>,
+    /// The value in the new state.
+    pub after:  Vec<u8>,
+}
+
+/// A read-only snapshot of the state of a contract instance, such as
+/// [`ContractInitSuccess::initial_state`].
+///
+/// The snapshot is not affected by later changes to the contract.
+#[derive(Clone, Debug)]
+pub struct StateView(pub(crate) trie::PersistentState);
+
+/// The size of the state of a contract instance, as returned by
+/// [`Chain::contract_state_size`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateSize {
+    /// The number of entries in the state.
+    pub entries:     usize,
+    /// The total number of bytes in the keys of the entries.
+    pub key_bytes:   u64,
+    /// The total number of bytes in the values of the entries.
+    pub value_bytes: u64,
+}
+
+impl StateSize {
+    /// The total number of bytes in the keys and values of the entries.
+    pub fn total_bytes(&self) -> u64 { self.key_bytes + self.value_bytes }
+}
+
+/// Information about a smart contract instance, as returned by
+/// [`Chain::contract_info`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractInfo {
+    /// The module which currently contains the contract. This reflects any
+    /// upgrades performed by the contract.
+    pub module_reference: ModuleReference,
+    /// The name of the contract.
+    pub contract_name:    OwnedContractName,
+    /// The owner of the contract, i.e., the account that initialized it.
+    pub owner:            AccountAddress,
+    /// The balance of the contract.
+    pub amount:           Amount,
+}
+
 /// An account.
 #[derive(Clone, Debug)]
 pub struct Account {
@@ -141,6 +454,19 @@ pub struct Account {
     pub policy:  OwnedPolicy,
     /// Account's public keys.
     pub keys:    AccountAccessStructure,
+    /// The nonce of the next transaction sent from the account, see
+    /// [`Chain::account_nonce`].
+    pub nonce:   u64,
+}
+
+/// The address and keys of an account exported from the Concordium browser
+/// wallet, see [`WalletExport::from_json`].
+#[derive(Debug)]
+pub struct WalletExport {
+    /// The address of the account.
+    pub address: AccountAddress,
+    /// The keys of the account, including the private keys.
+    pub keys:    AccountKeys,
 }
 
 /// A signature with account's keys.
@@ -264,6 +590,93 @@ pub struct ModuleDeploySuccess {
     pub transaction_fee:  Amount,
 }
 
+/// Options for changing the checks made when deploying a module with
+/// [`Chain::module_deploy_v1_with_options`]. The default options make the same
+/// checks as [`Chain::module_deploy_v1`].
+///
+/// Only checks made for convenience can be relaxed. The validation that the
+/// node enforces for safety, e.g., that the module only imports the allowed
+/// host functions and only uses the allowed instructions, is always made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeployOptions {
+    /// Require the module to embed a schema, such that the deployment fails
+    /// with [`ModuleDeployErrorKind::MissingSchema`] if it does not.
+    pub require_schema: bool,
+    /// Deploy the module even if it uses the debug host functions and debug
+    /// output has not been enabled with [`Chain::enable_debug_output`].
+    pub allow_debug:    bool,
+}
+
+/// A check of a module which was relaxed by the [`DeployOptions`] and would
+/// otherwise have failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum DeployWarning {
+    /// The module uses the debug host functions, but debug output is not
+    /// enabled.
+    #[error("The module uses the debug host functions, but debug output is not enabled")]
+    DebugHostFunctions,
+}
+
+/// Represents a successful deployment with
+/// [`Chain::module_deploy_v1_with_options`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct ModuleDeployWithOptionsSuccess {
+    /// The deployment.
+    pub deployment:          ModuleDeploySuccess,
+    /// The checks which would have failed without the [`DeployOptions`].
+    pub suppressed_warnings: Vec<DeployWarning>,
+}
+
+/// A report of a module that passed validation, see
+/// [`Chain::validate_module_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleValidationReport {
+    /// The reference the module gets when deployed.
+    pub module_reference:     ModuleReference,
+    /// The size of the module in bytes.
+    pub size:                 u64,
+    /// The total size of the custom sections in bytes. These do not count
+    /// towards the size used for the cost of loading the module.
+    pub custom_sections_size: u64,
+    /// The names of the exported functions, i.e., the contract init functions
+    /// and the receive functions, in sorted order.
+    pub exports:              Vec<String>,
+    /// The functions imported from the host, as pairs of the module name and
+    /// the function name, in the order they are imported.
+    pub imports:              Vec<(String, String)>,
+}
+
+/// Represents a successful transfer of CCD between two accounts with
+/// [`Chain::transfer`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct AccountTransferSuccess {
+    /// The energy used for the transfer.
+    pub energy_used:     Energy,
+    /// Cost of transaction.
+    pub transaction_fee: Amount,
+}
+
+/// An error that occurred during a [`Chain::transfer`].
+///
+/// The sender is not charged when a transfer fails.
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum AccountTransferError {
+    /// The sender or the receiver does not exist.
+    #[error("{0}")]
+    MissingAccount(#[from] AccountDoesNotExist),
+    /// The sender does not have enough funds to pay for the amount and the
+    /// transaction fee.
+    #[error("The sender does not have enough funds to pay for the amount and the transaction fee")]
+    InsufficientFunds,
+    /// The balance of the receiver overflowed.
+    #[error("The balance of the receiver overflowed")]
+    BalanceOverflow,
+    /// The energy of the transaction exceeds the energy remaining in the
+    /// current block, see [`Chain::set_block_energy_limit`].
+    #[error("The transaction does not fit in the energy remaining in the current block")]
+    OutOfBlockEnergy,
+}
+
 /// An error that occurred while deploying a [`ContractModule`].
 #[derive(Debug, Error)]
 #[error(
@@ -300,6 +713,50 @@ pub enum ModuleDeployErrorKind {
     /// The module version is not supported.
     #[error("Wasm version {0} is not supported")]
     UnsupportedModuleVersion(WasmVersion),
+    /// The module could not be parsed from the provided bytes.
+    #[error("Could not load the module due to: {0}")]
+    LoadModule(#[from] ModuleLoadErrorKind),
+    /// The energy of the transaction exceeds the energy remaining in the
+    /// current block, see [`Chain::set_block_energy_limit`].
+    #[error("The transaction does not fit in the energy remaining in the current block")]
+    OutOfBlockEnergy,
+    /// The module does not embed a schema, which is required by
+    /// [`Chain::module_deploy_v1_with_options`] if
+    /// [`DeployOptions::require_schema`] is set.
+    #[error("The module does not embed a schema")]
+    MissingSchema,
+    /// The tag given to [`Chain::deploy_tagged`] is already used for another
+    /// module.
+    #[error("The tag '{tag}' is already used for the module {module}")]
+    TagInUse {
+        /// The tag.
+        tag:    String,
+        /// The module deployed with the tag.
+        module: ModuleReference,
+    },
+}
+
+/// A malformed contract or entrypoint name, returned by
+/// [`init_payload`](crate::init_payload) and
+/// [`update_payload`](crate::update_payload) when building a call.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum NameError {
+    /// The name of the init function is not valid.
+    #[error("Invalid init name {name:?}: {error}")]
+    InitName {
+        /// The rejected name.
+        name:  String,
+        /// Why the name is not valid.
+        error: NewContractNameError,
+    },
+    /// The name of the receive function is not valid.
+    #[error("Invalid receive name {name:?}: {error}")]
+    ReceiveName {
+        /// The rejected name.
+        name:  String,
+        /// Why the name is not valid.
+        error: NewReceiveNameError,
+    },
 }
 
 /// An error that can occur while loading a smart contract module.
@@ -345,8 +802,79 @@ pub struct ModuleReadError(#[from] pub(crate) anyhow::Error);
 
 /// The error produced when trying to parse a smart contract module.
 #[derive(Debug, Error)]
-#[error("The module is invalid to: {0}")]
-pub struct ModuleInvalidError(#[from] pub(crate) anyhow::Error);
+#[error("The module is invalid to: {error}")]
+pub struct ModuleInvalidError {
+    /// The error reported by the validation.
+    pub(crate) error:  anyhow::Error,
+    /// The validation rule that the module broke, see
+    /// [`ModuleInvalidError::reason`].
+    pub(crate) reason: ModuleInvalidReason,
+}
+
+/// The validation rule that a module broke, as diagnosed by
+/// [`ModuleInvalidError::reason`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ModuleInvalidReason {
+    /// The module is not a well formed Wasm module, e.g., because it is
+    /// truncated.
+    #[error("The module is not a well formed Wasm module")]
+    Malformed,
+    /// The name of a custom section cannot be parsed.
+    #[error("The name of custom section number {index} cannot be parsed")]
+    MalformedCustomSection {
+        /// The position of the custom section among the custom sections of
+        /// the module, starting from 0.
+        index: usize,
+    },
+    /// The module imports a function which smart contracts may not use, e.g.,
+    /// a debug host function when debug output is not enabled.
+    #[error("The import {name} from the module {module} is not allowed")]
+    DisallowedImport {
+        /// The module of the import.
+        module: String,
+        /// The name of the imported function.
+        name:   String,
+    },
+    /// The initial or maximum size of the memory is not allowed.
+    #[error(
+        "The memory limits of {initial} initial pages and {maximum:?} maximum pages are not \
+         allowed"
+    )]
+    MemoryLimits {
+        /// The initial number of pages.
+        initial: u32,
+        /// The maximum number of pages, if given.
+        maximum: Option<u32>,
+    },
+    /// A function is exported which is neither a valid init function nor a
+    /// valid receive function, e.g., because of its name or its type.
+    #[error("The export {name} is not a valid init or receive function")]
+    InvalidExport {
+        /// The name of the export.
+        name: String,
+    },
+    /// A receive function is exported, but the init function of its contract
+    /// is not.
+    #[error("The receive function {receive_name} has no init function")]
+    MissingInit {
+        /// The name of the receive function.
+        receive_name: String,
+    },
+    /// The body of a function is not valid, e.g., because it uses an
+    /// instruction that is not allowed, such as a floating point instruction,
+    /// or because it does not type check.
+    #[error("The body of the function with index {index} is not valid")]
+    InvalidFunctionBody {
+        /// The index of the function, counting the imported functions.
+        index:  u32,
+        /// The name the function is exported with, if any.
+        export: Option<String>,
+    },
+    /// The reason could not be diagnosed, see the error message for the
+    /// details.
+    #[error("The reason could not be diagnosed")]
+    Other,
+}
 
 /// Represents a successful initialization of a contract.
 #[derive(Debug)]
@@ -361,6 +889,9 @@ pub struct ContractInitSuccess {
     pub transaction_fee:  Amount,
     /// Debug information emitted by the initialization method.
     pub debug_trace:      DebugTracker,
+    /// The state of the new instance, exactly as the initialization method
+    /// left it.
+    pub initial_state:    StateView,
 }
 
 /// An error that occurred in [`Chain::contract_init`].
@@ -418,6 +949,37 @@ pub enum ContractInitErrorKind {
     /// The parameter is too large.
     #[error("The provided parameter exceeds the maximum size allowed")]
     ParameterTooLarge,
+    /// All contract indices have been used, so the new instance cannot be
+    /// given an address.
+    #[error("No more contract indices are available")]
+    ContractIndexOverflow,
+    /// The energy of the transaction exceeds the energy remaining in the
+    /// current block, see [`Chain::set_block_energy_limit`].
+    #[error("The transaction does not fit in the energy remaining in the current block")]
+    OutOfBlockEnergy,
+    /// No module has been deployed with the tag given to
+    /// [`Chain::contract_init_tagged`].
+    #[error("No module has been deployed with the tag '{0}'")]
+    UnknownModuleTag(String),
+    /// The initial state contains a key longer than the maximum configured
+    /// with [`ChainBuilder::max_state_key_length`].
+    #[error("{0}")]
+    StateKeyTooLarge(StateKeyTooLarge),
+}
+
+/// The data needed for [`Chain::contract_init_tagged`], which is similar to
+/// [`InitContractPayload`](crate::InitContractPayload), except that the module
+/// is given by the tag it was deployed with in [`Chain::deploy_tagged`].
+#[derive(Debug, Clone)]
+pub struct InitContractTaggedPayload {
+    /// The amount to send to the contract.
+    pub amount:    Amount,
+    /// The tag of the module containing the contract.
+    pub tag:       String,
+    /// The init function to call.
+    pub init_name: OwnedContractName,
+    /// The parameter of the init function.
+    pub param:     OwnedParameter,
 }
 
 /// The reason for why a contract initialization failed during execution.
@@ -438,6 +1000,17 @@ pub enum InitExecutionError {
     OutOfEnergy,
 }
 
+/// A contract created a key in its state that is longer than the maximum
+/// configured with [`ChainBuilder::max_state_key_length`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("The state contains a key of length {key_length}, but at most {max_length} is allowed")]
+pub struct StateKeyTooLarge {
+    /// The length of the offending key.
+    pub key_length: usize,
+    /// The maximum key length configured for the [`Chain`].
+    pub max_length: usize,
+}
+
 /// An error that occurred while executing a contract init or receive function.
 #[derive(Debug, Error)]
 #[error("The contract execution halted due to: {0}")]
@@ -448,23 +1021,115 @@ pub struct ExecutionError(#[from] pub(crate) anyhow::Error);
 pub struct ContractInvokeSuccess {
     /// Host events that occurred. This includes interrupts, resumes, and
     /// upgrades.
-    pub trace_elements:     Vec<DebugTraceElement>,
+    pub trace_elements:      Vec<DebugTraceElement>,
     /// Energy used.
-    pub energy_used:        Energy,
+    pub energy_used:         Energy,
+    /// The energy remaining of the energy reserved for the update, i.e., the
+    /// reserved energy minus [`energy_used`](Self::energy_used).
+    pub remaining_energy:    Energy,
     /// The amount of [`Energy`] that was used to store the additional state
     /// generated by the contract.
-    pub storage_energy:     Energy,
+    pub storage_energy:      Energy,
     /// The amount of [`Energy`] that was charged based on the sizes of the Wasm
     /// modules invoked.
-    pub module_load_energy: Energy,
+    pub module_load_energy:  Energy,
+    /// The largest amount of [`Energy`] used by contract code between two
+    /// consecutive interrupts, across all the contracts invoked.
+    ///
+    /// This identifies the most expensive uninterrupted stretch of code
+    /// executed. The energy charged by the host for handling the interrupts,
+    /// e.g., for queries, is not included.
+    pub peak_segment_energy: Energy,
+    /// The checks of the modification indices of contracts, made each time a
+    /// contract resumed after calling a contract, in the order they occurred.
+    ///
+    /// This can be used to assert that a contract observed the changes, or lack
+    /// thereof, to its state during a call, e.g., when testing reentrancy
+    /// guards.
+    pub modification_checks: Vec<ModificationCheck>,
+    /// The invocations of contract entrypoints made in the update, in the
+    /// order they started, with the energy each of them used.
+    ///
+    /// The energy of an invocation includes the energy charged for handling
+    /// its interrupts, such as the cost of making a call, but not the energy
+    /// used by the called contracts. The energy used outside of any
+    /// invocation, e.g., for checking the transaction header and for storing
+    /// the changed states, is included in the first invocation, so the
+    /// energy of all the invocations adds up to
+    /// [`energy_used`](Self::energy_used).
+    pub call_frames:         Vec<CallFrame>,
+    /// The state entries written by the update, see
+    /// [`Chain::set_max_state_writes_per_update`].
+    pub state_writes:        StateWrites,
     /// Cost of transaction.
-    pub transaction_fee:    Amount,
+    pub transaction_fee:     Amount,
     /// The returned value.
-    pub return_value:       ReturnValue,
+    pub return_value:        ReturnValue,
     /// Whether the state of the invoked contract was changed.
-    pub state_changed:      bool,
+    pub state_changed:       bool,
+    /// The changes to the states of the contracts made by the update,
+    /// ordered by the contract addresses. Only the contracts whose states
+    /// end up different are included.
+    pub state_diffs:         Vec<(ContractAddress, StateDiff)>,
     /// The new balance of the smart contract.
-    pub new_balance:        Amount,
+    pub new_balance:         Amount,
+    /// The logged events that could not be decoded with the event schema set
+    /// with [`Chain::set_strict_logs`], in the order of
+    /// [`events`](Self::events). This is always empty if no schema is set.
+    pub log_warnings:        Vec<LogWarning>,
+}
+
+/// A logged event that could not be decoded with the event schema set with
+/// [`Chain::set_strict_logs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogWarning {
+    /// The contract that logged the event.
+    pub address: ContractAddress,
+    /// The raw event.
+    pub event:   ContractEvent,
+    /// The reason why decoding failed.
+    pub error:   String,
+}
+
+/// A check of whether the state of a contract was modified while it called
+/// another contract, made when the contract resumed.
+///
+/// Every change to the state of a contract during a transaction gives it a new
+/// modification index. The state is reported to the resumed contract as
+/// changed if its modification index differs from the one it had when it made
+/// the call. If the call failed, the changes are rolled back and the state is
+/// reported as unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModificationCheck {
+    /// The contract that resumed.
+    pub address:       ContractAddress,
+    /// The entrypoint of the contract that resumed.
+    pub entrypoint:    OwnedEntrypointName,
+    /// The call depth of the contract that resumed, where the contract
+    /// called by the transaction has depth 0.
+    pub depth:         usize,
+    /// The modification index of the contract when it made the call.
+    pub index_before:  u32,
+    /// The modification index of the contract when it resumed.
+    pub index_after:   u32,
+    /// Whether the state was reported to the contract as changed.
+    pub state_changed: bool,
+}
+
+/// The energy used by one invocation of a contract entrypoint in an update,
+/// see [`ContractInvokeSuccess::call_frames`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallFrame {
+    /// The contract invoked.
+    pub address:    ContractAddress,
+    /// The entrypoint invoked.
+    pub entrypoint: OwnedEntrypointName,
+    /// The call depth of the invocation, where the contract called by the
+    /// transaction has depth 0.
+    pub depth:      usize,
+    /// The energy used by the invocation itself, excluding the energy used by
+    /// the contracts it called.
+    pub energy:     Energy,
 }
 
 /// Represents a successful external contract invocation.
@@ -492,6 +1157,37 @@ pub struct DebugItem<'a> {
     /// `true` if this output is in the part of execution that has been rolled
     /// back.
     pub rolled_back: bool,
+    /// The number of contract calls the execution is nested in, which is `0`
+    /// for the contract invoked by the transaction.
+    pub depth:       usize,
+}
+
+/// A message printed by a contract with `concordium_dbg!`. This is the item
+/// returned by the [`debug_messages`](DebugInfoExt::debug_messages) method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugMessage {
+    /// The address of the instance that printed the message.
+    pub address:     ContractAddress,
+    /// The name of the entrypoint that printed the message.
+    pub entrypoint:  OwnedEntrypointName,
+    /// The number of contract calls the execution is nested in, which is `0`
+    /// for the contract invoked by the transaction.
+    pub depth:       usize,
+    /// The message.
+    pub message:     String,
+    /// `true` if the message was printed in the part of execution that has
+    /// been rolled back.
+    pub rolled_back: bool,
+}
+
+impl std::fmt::Display for DebugMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}:{} at depth {}", self.address, self.entrypoint, self.depth)?;
+        if self.rolled_back {
+            write!(f, ", rolled back")?;
+        }
+        write!(f, "] {}", self.message)
+    }
 }
 
 /// Information about an emitted host function call. This is the item returned
@@ -510,6 +1206,21 @@ pub struct HostCallInfo<'a> {
     pub rolled_back:   bool,
 }
 
+/// An upgrade of the module of a contract, passed to the callback registered
+/// with [`Chain::on_upgrade`].
+#[derive(Debug, Clone)]
+pub struct UpgradeEvent {
+    /// The contract which upgraded.
+    pub address: ContractAddress,
+    /// The module of the contract before the upgrade.
+    pub from:    ModuleReference,
+    /// The module of the contract after the upgrade.
+    pub to:      ModuleReference,
+    /// The state of the contract when it upgraded, including the changes it
+    /// made before the upgrade in the same invocation.
+    pub state:   StateView,
+}
+
 impl ContractInvokeSuccess {
     /// Extract all the events logged by all the contracts in the invocation.
     /// The events are returned in the order that they are emitted, and are
@@ -530,12 +1241,132 @@ impl ContractInvokeSuccess {
         })
     }
 
+    /// The raw events logged by all the contracts in the update, in the order
+    /// they were emitted.
+    ///
+    /// The order is the execution order: when a contract calls another
+    /// contract, the events it logged before the call come first, then the
+    /// events logged by the called contract (and the contracts it called), and
+    /// then the events the caller logged after the call returned. Events
+    /// logged by a nested call that failed are not included, since they were
+    /// rolled back, but the events logged before the failed call are. This is
+    /// the same order as [`Self::events`], flattened.
+    pub fn logs(&self) -> Vec<&[u8]> {
+        self.events().flat_map(|(_, events)| events.iter().map(AsRef::<[u8]>::as_ref)).collect()
+    }
+
+    /// The raw events logged by the contract at `address` in the update, in
+    /// the order they were emitted. See [`Self::logs`] for the ordering
+    /// guarantees.
+    pub fn logs_for(&self, address: ContractAddress) -> Vec<&[u8]> {
+        self.events()
+            .filter(|(source, _)| *source == address)
+            .flat_map(|(_, events)| events.iter().map(AsRef::<[u8]>::as_ref))
+            .collect()
+    }
+
+    /// Decode the return value as an [`InvokeResponseCode`]. This is useful
+    /// for testing contracts which return the response code they received
+    /// when calling another contract or transferring CCD.
+    ///
+    /// Returns `None` if the return value is not exactly 8 bytes long.
+    pub fn invoke_response_code(&self) -> Option<InvokeResponseCode> {
+        InvokeResponseCode::from_return_value(&self.return_value)
+    }
+
+    /// Parse the event at position `index` among all the events logged in the
+    /// invocation and assert that it is equal to `expected`.
+    ///
+    /// The events are indexed in the order returned by [`Self::events`],
+    /// starting from `0`. Only the event at `index` is parsed, and all of its
+    /// bytes must be read.
+    ///
+    /// # Panics
+    /// Panics if fewer than `index + 1` events were logged, if the event
+    /// cannot be parsed as an `E`, or if it differs from `expected`.
+    pub fn assert_log<E: Deserial + PartialEq + std::fmt::Debug>(&self, index: usize, expected: E) {
+        let mut events =
+            self.events().flat_map(|(address, events)| events.iter().map(move |e| (address, e)));
+        let Some((address, event)) = events.nth(index) else {
+            panic!(
+                "Expected an event at index {index}, but only {} events were logged.",
+                self.events().map(|(_, events)| events.len()).sum::<usize>()
+            );
+        };
+        let parsed: E = parse_event(event).unwrap_or_else(|_| {
+            panic!("The event at index {index} logged by {address} could not be parsed.")
+        });
+        assert_eq!(
+            parsed, expected,
+            "The event at index {index} logged by {address} differs from the expected event."
+        );
+    }
+
+    /// Decode all the events logged in the invocation using the provided
+    /// event schema.
+    ///
+    /// The events are returned in the order of [`Self::events`]. An event
+    /// that does not match the schema, including one with leftover bytes, is
+    /// returned as [`DecodedEvent::Raw`], such that one malformed event does
+    /// not prevent inspecting the rest.
+    ///
+    /// The event schema of a contract can be obtained with
+    /// [`VersionedModuleSchema::get_event_schema`](schema::VersionedModuleSchema::get_event_schema).
+    /// Note that the same schema is used for the events of all contracts.
+    pub fn parse_events(
+        &self,
+        event_schema: &schema::Type,
+    ) -> Vec<DecodedEvent<serde_json::Value>> {
+        self.decode_events(|event| {
+            let bytes: &[u8] = event.as_ref();
+            let mut cursor = contracts_common::Cursor::new(bytes);
+            let value = event_schema.to_json(&mut cursor).map_err(|e| e.to_string())?;
+            if cursor.offset != bytes.len() {
+                return Err(format!(
+                    "{} bytes were left over after decoding",
+                    bytes.len() - cursor.offset
+                ));
+            }
+            Ok(value)
+        })
+    }
+
+    /// Decode all the events logged in the invocation as CIS-2 events.
+    ///
+    /// This is like [`Self::parse_events`] except that the events are decoded
+    /// into a [`Cis2Event`], which does not require a schema.
+    pub fn parse_cis2_events(&self) -> Vec<DecodedEvent<Cis2Event>> {
+        self.decode_events(|event| parse_event(event).map_err(|e| e.to_string()))
+    }
+
+    /// Decode all the events logged in the invocation with `decode`, falling
+    /// back to [`DecodedEvent::Raw`] for the events that cannot be decoded.
+    fn decode_events<T>(
+        &self,
+        decode: impl Fn(&ContractEvent) -> Result<T, String>,
+    ) -> Vec<DecodedEvent<T>> {
+        self.events()
+            .flat_map(|(address, events)| events.iter().map(move |event| (address, event)))
+            .map(|(address, event)| match decode(event) {
+                Ok(decoded) => DecodedEvent::Decoded {
+                    address,
+                    event: decoded,
+                },
+                Err(error) => DecodedEvent::Raw {
+                    address,
+                    event: event.clone(),
+                    error,
+                },
+            })
+            .collect()
+    }
+
     /// Extract the transfers **to accounts** that occurred during
     /// invocation. The return value is an iterator over triples `(from, amount,
     /// to)` where `from` is the sender contract, and `to` is the receiver
     /// account. The transfers are returned in the order that they occurred.
     ///
-    /// Only tranfers from effective trace elements are included. See
+    /// Only transfers from effective trace elements are included. See
     /// [`Self::effective_trace_elements`] for more details.
     pub fn account_transfers(
         &self,
@@ -554,6 +1385,43 @@ impl ContractInvokeSuccess {
         })
     }
 
+    /// Extract all the transfers of CCD made by contracts during the
+    /// invocation, both to accounts and to other contracts. The return value
+    /// is a list of triples `(from, to, amount)`, where `from` is the sending
+    /// contract, in the order they appear in the trace.
+    ///
+    /// Transfers to accounts are included as they occur, while transfers to
+    /// contracts are included with the update of the receiving contract, i.e.,
+    /// when its entrypoint has finished. Calls to contracts that do not move
+    /// any CCD are omitted. The amount sent with the top-level update is not
+    /// included, as it is sent by the invoker and not by a contract.
+    ///
+    /// Only transfers from effective trace elements are included. See
+    /// [`Self::effective_trace_elements`] for more details.
+    pub fn transfers(&self) -> Vec<(ContractAddress, Address, Amount)> {
+        self.effective_trace_elements()
+            .filter_map(|cte| match cte {
+                ContractTraceElement::Transferred {
+                    from,
+                    amount,
+                    to,
+                } => Some((*from, Address::Account(*to), *amount)),
+                ContractTraceElement::Updated {
+                    data:
+                        InstanceUpdatedEvent {
+                            address,
+                            instigator: Address::Contract(from),
+                            amount,
+                            ..
+                        },
+                } if *amount > Amount::zero() => {
+                    Some((*from, Address::Contract(*address), *amount))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Get an iterator over references of all the [`ContractTraceElement`]s
     /// that have *not* been rolled back.
     ///
@@ -661,6 +1529,263 @@ impl ContractInvokeSuccess {
     }
 }
 
+/// The outcome of a contract update or invocation, reduced to what is
+/// observable by the caller: either the return value and logs of a successful
+/// invocation, or the reason it was rejected.
+///
+/// Constructed from the result of [`Chain::contract_update`] or
+/// [`Chain::contract_invoke`].
+#[derive(Debug)]
+pub enum InvocationOutcome {
+    /// The invocation succeeded.
+    Success {
+        /// The value returned by the invoked contract. This is empty if the
+        /// contract did not return a value.
+        return_value: Vec<u8>,
+        /// The events logged by all the contracts that were updated, in the
+        /// order they were logged.
+        logs:         Vec<Vec<u8>>,
+    },
+    /// The invocation failed.
+    Reject(ContractInvokeErrorKind),
+}
+
+impl InvocationOutcome {
+    /// Whether the invocation succeeded.
+    pub fn is_success(&self) -> bool { matches!(self, Self::Success { .. }) }
+
+    /// The events logged during the invocation. This is always empty for a
+    /// failed invocation, since logs are only committed on success.
+    pub fn logs(&self) -> &[Vec<u8>] {
+        match self {
+            Self::Success {
+                logs,
+                ..
+            } => logs,
+            Self::Reject(_) => &[],
+        }
+    }
+}
+
+impl From<Result<ContractInvokeSuccess, ContractInvokeError>> for InvocationOutcome {
+    fn from(result: Result<ContractInvokeSuccess, ContractInvokeError>) -> Self {
+        match result {
+            Ok(success) => Self::Success {
+                logs:         success.logs().into_iter().map(<[u8]>::to_vec).collect(),
+                return_value: success.return_value,
+            },
+            // The trace elements of a failed invocation can include events from nested
+            // invocations that succeeded before the failure. These are rolled back along with the
+            // rest of the invocation, so they are not part of the outcome.
+            Err(error) => Self::Reject(error.kind),
+        }
+    }
+}
+
+/// The response code returned to a contract by the `invoke` host function,
+/// e.g., when it calls another contract or transfers CCD to an account.
+///
+/// The code is a `u64` laid out as follows, from the most significant byte:
+///  - The first 3 bytes contain the index of the return value, if any. On
+///    success, the most significant bit of these is set if the state of the
+///    calling contract was changed by the call.
+///  - The 4th byte is zero on success and for logic errors. Otherwise, it is
+///    the kind of failure, see [`InvokeFailureKind`].
+///  - The last 4 bytes are zero unless the call was rejected by the called
+///    contract, in which case they contain the reject reason as an `i32`. See
+///    [`remaining_on_reject`](Self::remaining_on_reject).
+///
+/// The code is typically obtained from the return value of a test contract
+/// that returns the code it received, see
+/// [`ContractInvokeSuccess::invoke_response_code`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvokeResponseCode(pub u64);
+
+/// The kind of failure encoded in an [`InvokeResponseCode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvokeFailureKind {
+    /// The called contract rejected with a reject reason.
+    LogicReject,
+    /// The sender did not have enough funds for the transfer (`0x01`).
+    AmountTooLarge,
+    /// The account to transfer to does not exist (`0x02`).
+    MissingAccount,
+    /// The contract to call does not exist (`0x03`).
+    MissingContract,
+    /// The entrypoint to call does not exist (`0x04`).
+    MissingEntrypoint,
+    /// Sending the message to a V0 contract failed (`0x05`).
+    MessageFailed,
+    /// The called contract trapped (`0x06`).
+    Trap,
+}
+
+impl InvokeResponseCode {
+    /// Decode the response code from the little-endian bytes of a return
+    /// value. Returns `None` if the return value is not exactly 8 bytes long.
+    pub fn from_return_value(return_value: &[u8]) -> Option<Self> {
+        let bytes: [u8; 8] = return_value.try_into().ok()?;
+        Some(Self(u64::from_le_bytes(bytes)))
+    }
+
+    /// Whether the call succeeded.
+    pub fn is_success(self) -> bool { self.0 & 0xff_ffff_ffff == 0 }
+
+    /// The kind of failure, or `None` if the call succeeded.
+    ///
+    /// Returns `None` as well if the code is not one the node produces.
+    pub fn failure_kind(self) -> Option<InvokeFailureKind> {
+        if self.is_success() {
+            return None;
+        }
+        match (self.0 >> 32) as u8 {
+            0x00 => Some(InvokeFailureKind::LogicReject),
+            0x01 => Some(InvokeFailureKind::AmountTooLarge),
+            0x02 => Some(InvokeFailureKind::MissingAccount),
+            0x03 => Some(InvokeFailureKind::MissingContract),
+            0x04 => Some(InvokeFailureKind::MissingEntrypoint),
+            0x05 => Some(InvokeFailureKind::MessageFailed),
+            0x06 => Some(InvokeFailureKind::Trap),
+            _ => None,
+        }
+    }
+
+    /// The last 4 bytes of the code of a failed call, interpreted as an
+    /// `i32`, or `None` if the call succeeded.
+    ///
+    /// For a [`LogicReject`](InvokeFailureKind::LogicReject) this is the
+    /// reject reason of the called contract, which is never zero. For all the
+    /// other kinds of failure there is no reason, and this is `0`.
+    pub fn remaining_on_reject(self) -> Option<i32> {
+        if self.is_success() {
+            None
+        } else {
+            Some(self.0 as u32 as i32)
+        }
+    }
+
+    /// The index of the return value of the call, if there is one.
+    ///
+    /// Only successful calls and logic rejects have return values.
+    pub fn return_value_index(self) -> Option<u32> {
+        let index = (self.0 >> 40) as u32;
+        let index = if self.is_success() {
+            index & !0x80_0000
+        } else {
+            index
+        };
+        (index != 0).then_some(index)
+    }
+
+    /// Whether the state of the calling contract was changed by a successful
+    /// call. This is always `false` for failed calls.
+    pub fn state_changed(self) -> bool { self.is_success() && self.0 & (1 << 63) != 0 }
+}
+
+/// A contract event decoded by [`ContractInvokeSuccess::parse_events`] or
+/// [`ContractInvokeSuccess::parse_cis2_events`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedEvent<T> {
+    /// The event was decoded successfully.
+    Decoded {
+        /// The contract that logged the event.
+        address: ContractAddress,
+        /// The decoded event.
+        event:   T,
+    },
+    /// The event could not be decoded and is returned as logged.
+    Raw {
+        /// The contract that logged the event.
+        address: ContractAddress,
+        /// The raw event.
+        event:   ContractEvent,
+        /// The reason why decoding failed.
+        error:   String,
+    },
+}
+
+impl<T> DecodedEvent<T> {
+    /// The contract that logged the event.
+    pub fn address(&self) -> ContractAddress {
+        match self {
+            Self::Decoded {
+                address,
+                ..
+            } => *address,
+            Self::Raw {
+                address,
+                ..
+            } => *address,
+        }
+    }
+
+    /// The decoded event, or `None` if decoding failed.
+    pub fn decoded(&self) -> Option<&T> {
+        match self {
+            Self::Decoded {
+                event,
+                ..
+            } => Some(event),
+            Self::Raw {
+                ..
+            } => None,
+        }
+    }
+}
+
+/// Assert that a candidate flow logged the same decoded events as a baseline
+/// flow, in the same order.
+///
+/// The events are typically obtained with
+/// [`ContractInvokeSuccess::parse_events`] or
+/// [`ContractInvokeSuccess::parse_cis2_events`], possibly concatenated over
+/// several invocations.
+///
+/// # Panics
+/// Panics if the sequences differ. The panic message lists each position
+/// where the events differ, with the baseline and the candidate event.
+pub fn assert_events_eq<T: PartialEq + std::fmt::Debug>(
+    baseline: &[DecodedEvent<T>],
+    candidate: &[DecodedEvent<T>],
+) {
+    let mut diff = String::new();
+    for index in 0..baseline.len().max(candidate.len()) {
+        let (expected, actual) = (baseline.get(index), candidate.get(index));
+        if expected != actual {
+            let show = |event: Option<&DecodedEvent<T>>| {
+                event.map_or_else(|| "<no event>".to_string(), |e| format!("{e:?}"))
+            };
+            diff.push_str(&format!(
+                "\n  [{index}] baseline:  {}\n  [{index}] candidate: {}",
+                show(expected),
+                show(actual)
+            ));
+        }
+    }
+    assert!(
+        diff.is_empty(),
+        "The candidate logged {} events which differ from the {} baseline events:{diff}",
+        candidate.len(),
+        baseline.len()
+    );
+}
+
+/// Try to parse a contract event into a type that implements [`Deserial`].
+///
+/// Ensures that all bytes of the event are read.
+pub(crate) fn parse_event<T: Deserial>(event: &ContractEvent) -> ParseResult<T> {
+    use contracts_common::{Cursor, Get, ParseError};
+    let bytes: &[u8] = event.as_ref();
+    let mut cursor = Cursor::new(bytes);
+    let res = cursor.get()?;
+    // Check that all bytes have been read, as leftover bytes usually indicate
+    // errors.
+    if cursor.offset != bytes.len() {
+        return Err(ParseError::default());
+    }
+    Ok(res)
+}
+
 /// The different types of debug output that can be printed by the
 /// [`print_debug`](DebugInfoExt::print_debug) method.
 pub enum DebugOutputKind {
@@ -697,6 +1822,7 @@ pub trait DebugInfoExt: Sized {
                     entrypoint,
                     debug_trace,
                     rolled_back,
+                    ..
                 } in self.debug_events()
                 {
                     eprintln!(
@@ -716,6 +1842,7 @@ pub trait DebugInfoExt: Sized {
                     entrypoint,
                     debug_trace,
                     rolled_back,
+                    ..
                 } in self.debug_events()
                 {
                     eprintln!(
@@ -802,6 +1929,28 @@ pub trait DebugInfoExt: Sized {
         }))
     }
 
+    /// Get the messages printed with `concordium_dbg!` during the execution,
+    /// in the order they were printed.
+    ///
+    /// Messages printed in parts of the execution that were rolled back, such
+    /// as a call to another contract that failed, are included and marked as
+    /// such. The contracts can only print messages if they were deployed with
+    /// debug output enabled, see
+    /// [`Chain::enable_debug_output`](crate::Chain::enable_debug_output).
+    fn debug_messages(&self) -> Vec<DebugMessage> {
+        self.debug_events()
+            .flat_map(|de| {
+                de.debug_trace.emitted_events.iter().map(move |(_, statement)| DebugMessage {
+                    address:     de.address,
+                    entrypoint:  de.entrypoint.to_owned(),
+                    depth:       de.depth,
+                    message:     statement.msg.clone(),
+                    rolled_back: de.rolled_back,
+                })
+            })
+            .collect()
+    }
+
     /// Get  host function calls grouped by contract address that
     /// generated them. The value at each address and host function is the
     /// pair of the number of times the host function was called, and the total
@@ -887,6 +2036,9 @@ fn debug_events_worker(
     }
     struct DebugTraceElementsIter<'a> {
         stack: Vec<Next<'a>>,
+        /// The number of calls the current execution is nested in, tracked
+        /// via the interrupts and resumes.
+        depth: usize,
     }
 
     impl<'a> Iterator for DebugTraceElementsIter<'a> {
@@ -897,7 +2049,11 @@ fn debug_events_worker(
                 let top = self.stack.pop()?;
                 let top = match top {
                     Next::Remaining(top) => top,
-                    Next::Emit(v) => return Some(v),
+                    Next::Emit(v) => {
+                        // The failed execution is done, so return to its depth.
+                        self.depth = v.depth;
+                        return Some(v);
+                    }
                 };
                 let (first, rest) = top.1.split_first()?;
                 if !rest.is_empty() {
@@ -910,11 +2066,29 @@ fn debug_events_worker(
                         energy_used: _,
                         debug_trace,
                     } => {
+                        // The output before an interrupt is at the depth of the interrupted
+                        // contract, whereas the calls it makes are one deeper until it resumes.
+                        let depth = match trace_element {
+                            ContractTraceElement::Interrupted {
+                                ..
+                            } => {
+                                self.depth += 1;
+                                self.depth - 1
+                            }
+                            ContractTraceElement::Resumed {
+                                ..
+                            } => {
+                                self.depth = self.depth.saturating_sub(1);
+                                self.depth
+                            }
+                            _ => self.depth,
+                        };
                         return Some(DebugItem {
                             address: trace_element.affected_address(),
                             entrypoint: entrypoint.as_entrypoint_name(),
                             debug_trace,
-                            rolled_back: false,
+                            rolled_back: top.0,
+                            depth,
                         });
                     }
                     DebugTraceElement::WithFailures {
@@ -930,6 +2104,7 @@ fn debug_events_worker(
                             entrypoint: entrypoint.as_entrypoint_name(),
                             debug_trace,
                             rolled_back: true,
+                            depth: self.depth,
                         }));
                         if !trace_elements.is_empty() {
                             self.stack.push(Next::Remaining((true, trace_elements)));
@@ -939,12 +2114,14 @@ fn debug_events_worker(
                         entrypoint,
                         address,
                         debug_trace,
+                        ..
                     } => {
                         return Some(DebugItem {
                             address: *address,
                             entrypoint: entrypoint.as_entrypoint_name(),
                             debug_trace,
-                            rolled_back: false,
+                            rolled_back: top.0,
+                            depth: self.depth,
                         });
                     }
                 }
@@ -953,6 +2130,7 @@ fn debug_events_worker(
     }
     DebugTraceElementsIter {
         stack: vec![Next::Remaining((rolled_back, initial_events))],
+        depth: 0,
     }
 }
 
@@ -987,6 +2165,10 @@ pub enum DebugTraceElement {
         address:     ContractAddress,
         /// Events emitted until the interrupt.
         debug_trace: DebugTracker,
+        /// The response data that the contract receives when it resumes after
+        /// the query. This is `None` if the query failed or did not return
+        /// any data, such as a successful signature check.
+        response:    Option<Vec<u8>>,
     },
     /// One or multiple trace elements that fail. Useful for debugging.
     /// This variant also contains additional information, such as the error,
@@ -1094,6 +2276,25 @@ pub enum ContractInvokeErrorKind {
     /// The parameter is too large.
     #[error("The provided parameter exceeds the maximum size allowed")]
     ParameterTooLarge,
+    /// The energy of the transaction exceeds the energy remaining in the
+    /// current block, see [`Chain::set_block_energy_limit`].
+    #[error("The transaction does not fit in the energy remaining in the current block")]
+    OutOfBlockEnergy,
+    /// The update wrote more state entries than allowed, see
+    /// [`Chain::set_max_state_writes_per_update`].
+    #[error("The update wrote more than the maximum of {max_writes} state entries")]
+    StateWritesExceeded {
+        /// The state entries written before the write exceeding the maximum,
+        /// which add up to the maximum.
+        writes:     StateWrites,
+        /// The maximum number of writes configured for the [`Chain`].
+        max_writes: usize,
+    },
+    /// A contract called by the update created a key in its state which is
+    /// longer than the maximum configured with
+    /// [`ChainBuilder::max_state_key_length`].
+    #[error("{0}")]
+    StateKeyTooLarge(StateKeyTooLarge),
 }
 
 /// The error returned when external contract invocations fail.
@@ -1122,6 +2323,131 @@ pub enum ContractInvokeExternalError {
     },
 }
 
+/// The data needed for [`Chain::contract_update_json`], which is similar to
+/// [`UpdateContractPayload`](crate::UpdateContractPayload), except that the
+/// parameter is provided as JSON.
+#[derive(Debug, Clone)]
+pub struct UpdateContractJsonPayload {
+    /// The amount to send to the contract.
+    pub amount:       Amount,
+    /// The address of the contract to update.
+    pub address:      ContractAddress,
+    /// The receive function to call.
+    pub receive_name: OwnedReceiveName,
+    /// The parameter in JSON. It is serialized using the parameter schema of
+    /// the receive function.
+    pub parameter:    serde_json::Value,
+}
+
+/// The error returned by [`Chain::contract_update_json`].
+#[derive(Debug, Error)]
+pub enum ContractUpdateJsonError {
+    /// The schema does not contain a parameter schema for the receive
+    /// function.
+    #[error("Could not find the parameter schema: {0:?}")]
+    MissingParameterSchema(schema::VersionedSchemaError),
+    /// The JSON parameter could not be serialized with the parameter schema.
+    #[error("Could not serialize the JSON parameter: {0}")]
+    InvalidParameter(String),
+    /// The update was executed but failed.
+    #[error("{0}")]
+    Invoke(#[from] ContractInvokeError),
+}
+
+/// The error returned by [`Chain::contract_update_with_budget`].
+#[derive(Debug, Error)]
+pub enum ContractUpdateBudgetError {
+    /// The update succeeded, but used more energy than the budget allowed.
+    #[error("The update used {energy_used}NRG, which exceeds the budget of {max_energy_used}NRG")]
+    BudgetExceeded {
+        /// The energy used by the update.
+        energy_used:     Energy,
+        /// The budget for the energy used.
+        max_energy_used: Energy,
+        /// The result of the update, which has been saved.
+        success:         Box<ContractInvokeSuccess>,
+    },
+    /// The update failed.
+    #[error("{0}")]
+    Invoke(#[from] ContractInvokeError),
+}
+
+/// The initialization of the contract made by [`Chain::deploy_init_and_seed`].
+#[derive(Debug, Clone)]
+pub struct SeedInit {
+    /// The init function to call, e.g., `init_counter`.
+    pub contract_name: OwnedContractName,
+    /// The parameter for the init function.
+    pub parameter:     OwnedParameter,
+    /// The amount to send to the contract.
+    pub amount:        Amount,
+    /// The energy reserved for the initialization.
+    pub energy:        Energy,
+}
+
+/// An update made by [`Chain::deploy_init_and_seed`] to seed the state of a
+/// new contract.
+#[derive(Debug, Clone)]
+pub struct SeedUpdate {
+    /// The entrypoint to call.
+    pub entrypoint: OwnedEntrypointName,
+    /// The parameter for the entrypoint.
+    pub parameter:  OwnedParameter,
+    /// The amount to send to the contract.
+    pub amount:     Amount,
+    /// The energy reserved for the update.
+    pub energy:     Energy,
+}
+
+/// A contract deployed, initialized and seeded with
+/// [`Chain::deploy_init_and_seed`].
+#[derive(Debug, Clone, Copy)]
+pub struct SeededContract {
+    /// The reference of the deployed module.
+    pub module_reference: ModuleReference,
+    /// The address of the contract.
+    pub contract_address: ContractAddress,
+    /// The energy used by all the transactions.
+    pub energy_used:      Energy,
+    /// The fees of all the transactions.
+    pub transaction_fee:  Amount,
+}
+
+/// The error returned by [`Chain::deploy_init_and_seed`], naming the step
+/// which failed.
+///
+/// The changes made by the steps before the failing one are not undone.
+#[derive(Debug, Error)]
+pub enum SeedError {
+    /// Deploying the module failed.
+    #[error("Deploying the module failed: {0}")]
+    Deploy(#[from] ModuleDeployError),
+    /// Initializing the contract failed.
+    #[error("Initializing the contract failed: {0}")]
+    Init(#[from] ContractInitError),
+    /// One of the seeding updates failed.
+    #[error("Seed update {index} to entrypoint '{entrypoint}' failed: {error}")]
+    Update {
+        /// The index of the update in the list of seeding updates.
+        index:      usize,
+        /// The entrypoint of the update.
+        entrypoint: OwnedEntrypointName,
+        /// The error of the update.
+        error:      ContractInvokeError,
+    },
+}
+
+/// A positive or negative delta in for an [`Amount`].
+///
+/// Returned by [`Chain::track_balance`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AmountDelta {
+    /// A positive delta.
+    Positive(Amount),
+    /// A negative delta.
+    Negative(Amount),
+}
+
 /// A balance error which can occur when transferring [`Amount`]s.
 #[derive(Debug, PartialEq, Eq, Error)]
 pub(crate) enum BalanceError {
@@ -1169,6 +2495,35 @@ pub struct ModuleDoesNotExist {
     pub module_reference: ModuleReference,
 }
 
+/// An error that can occur when inserting a contract instance with
+/// [`Chain::insert_contract_unchecked`].
+#[derive(Debug, Error)]
+pub enum InsertContractError {
+    /// The module of the contract has not been deployed.
+    #[error("{0}")]
+    ModuleDoesNotExist(#[from] ModuleDoesNotExist),
+    /// All contract indices have been used, so the instance cannot be given
+    /// an address.
+    #[error("No more contract indices are available")]
+    ContractIndexOverflow,
+}
+
+/// An error that can occur when tagging a module with [`Chain::tag_module`].
+#[derive(Debug, Error)]
+pub enum TagModuleError {
+    /// The module has not been deployed.
+    #[error("{0}")]
+    ModuleDoesNotExist(#[from] ModuleDoesNotExist),
+    /// The tag is already used for another module.
+    #[error("The tag '{tag}' is already used for the module {module}")]
+    TagInUse {
+        /// The tag.
+        tag:    String,
+        /// The module registered with the tag.
+        module: ModuleReference,
+    },
+}
+
 /// The contract instance does not exist.
 #[derive(Debug, Error)]
 #[error("Contract instance '{address}' does not exist.")]
@@ -1178,7 +2533,7 @@ pub struct ContractDoesNotExist {
 }
 
 /// The account does not exist.
-#[derive(Debug, Error)]
+#[derive(Debug, PartialEq, Eq, Error)]
 #[error("Account '{address}' does not exist.")]
 pub struct AccountDoesNotExist {
     /// The address of the missing account.
@@ -1192,6 +2547,29 @@ pub struct AccountDoesNotExist {
 #[error("An exchange rate was too high.")]
 pub struct ExchangeRateError;
 
+/// An error that occurs while parsing a wallet export with
+/// [`WalletExport::from_json`].
+#[derive(Debug, Error)]
+pub enum WalletExportError {
+    /// The export is not valid JSON or does not contain the expected fields.
+    #[error("Invalid wallet export: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The export is not an account export from the browser wallet.
+    #[error("Unsupported wallet export type {0:?}")]
+    UnsupportedType(Option<String>),
+    /// The export is written with an unsupported version of the format.
+    #[error("Unsupported wallet export version {found:?}, expected {expected}")]
+    UnsupportedVersion {
+        /// The version of the export, if present.
+        found:    Option<u64>,
+        /// The supported version.
+        expected: u64,
+    },
+    /// The account address is not a valid base58 encoded address.
+    #[error("Invalid account address {0:?} in wallet export")]
+    InvalidAddress(String),
+}
+
 /// A [`Signer`] cannot be created with `0` keys.
 #[derive(Debug, Error)]
 #[error("Any signer must have at least one key.")]
@@ -1309,11 +2687,41 @@ pub enum ChainBuilderError {
     MissingExternalConnection,
 }
 
+/// No checkpoint with the label exists, see [`Chain::load`].
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("No checkpoint with the label '{label}' exists.")]
+pub struct UnknownCheckpoint {
+    /// The label of the missing checkpoint.
+    pub label: String,
+}
+
+/// The contract index is already in use, see
+/// [`Chain::set_next_contract_index`].
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("The contract index {index} is lower than the next available index {next_index}.")]
+pub struct ContractIndexInUse {
+    /// The index that was requested.
+    pub index:      u64,
+    /// The next available index on the chain.
+    pub next_index: u64,
+}
+
 /// The block time overflowed during a call to `Chain::tick_block_time`.
 #[derive(Debug, Error, PartialEq, Eq)]
 #[error("The block time overflowed during a call to `Chain::tick_block_time`.")]
 pub struct BlockTimeOverflow;
 
+/// The block height given to `Chain::warp_to_block_height` is below the
+/// current block height.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Cannot move the block height back from {current} to {requested}.")]
+pub struct BlockHeightDecrease {
+    /// The current block height.
+    pub current:   u64,
+    /// The block height that was requested.
+    pub requested: u64,
+}
+
 /// The contract address of an contract on an external node.
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ExternalContractAddress {
@@ -1380,3 +2788,274 @@ impl From<ExternalAccountAddress> for ExternalAddress {
 impl From<ExternalContractAddress> for ExternalAddress {
     fn from(addr: ExternalContractAddress) -> Self { Self::Contract(addr) }
 }
+
+/// The error returned when a parameter would be larger than the maximum size
+/// allowed by the protocol.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("The parameter size of {size} bytes exceeds the maximum of {max_size} bytes.")]
+pub struct ParameterTooLarge {
+    /// The size the parameter would have had.
+    pub size:     usize,
+    /// The maximum size of a parameter, i.e.,
+    /// [`MAX_PARAMETER_LEN`](contracts_common::constants::MAX_PARAMETER_LEN).
+    pub max_size: usize,
+}
+
+/// Extension methods for creating and extending an [`OwnedParameter`] while
+/// staying within the maximum parameter size.
+pub trait OwnedParameterExt {
+    /// Create a parameter with the serialization of `value`.
+    ///
+    /// Returns an error with the size of the serialization if it exceeds the
+    /// maximum parameter size. Use
+    /// [`OwnedParameter::from_serial`](OwnedParameter::from_serial) with an
+    /// `expect` for small parameters which are known to fit.
+    fn try_new<T: Serial + ?Sized>(value: &T) -> Result<Self, ParameterTooLarge>
+    where
+        Self: Sized;
+
+    /// Append the serialization of `value` to the parameter.
+    ///
+    /// Parameters, such as the parameter of a nested contract call, are
+    /// serialized with their two byte length prefix.
+    ///
+    /// Returns an error, and leaves the parameter unchanged, if the parameter
+    /// would become too large.
+    fn append<T: Serial + ?Sized>(&mut self, value: &T) -> Result<(), ParameterTooLarge>;
+
+    /// Append raw bytes to the parameter, without a length prefix.
+    ///
+    /// Returns an error, and leaves the parameter unchanged, if the parameter
+    /// would become too large.
+    fn append_bytes(&mut self, bytes: &[u8]) -> Result<(), ParameterTooLarge>;
+}
+
+impl OwnedParameterExt for OwnedParameter {
+    fn try_new<T: Serial + ?Sized>(value: &T) -> Result<Self, ParameterTooLarge> {
+        let bytes = contracts_common::to_bytes(value);
+        if bytes.len() > MAX_PARAMETER_LEN {
+            return Err(ParameterTooLarge {
+                size:     bytes.len(),
+                max_size: MAX_PARAMETER_LEN,
+            });
+        }
+        Ok(OwnedParameter::new_unchecked(bytes))
+    }
+
+    fn append<T: Serial + ?Sized>(&mut self, value: &T) -> Result<(), ParameterTooLarge> {
+        self.append_bytes(&contracts_common::to_bytes(value))
+    }
+
+    fn append_bytes(&mut self, bytes: &[u8]) -> Result<(), ParameterTooLarge> {
+        let size = self.as_ref().len() + bytes.len();
+        if size > MAX_PARAMETER_LEN {
+            return Err(ParameterTooLarge {
+                size,
+                max_size: MAX_PARAMETER_LEN,
+            });
+        }
+        let mut new_bytes = Vec::with_capacity(size);
+        new_bytes.extend_from_slice(self.as_ref());
+        new_bytes.extend_from_slice(bytes);
+        *self = OwnedParameter::new_unchecked(new_bytes);
+        Ok(())
+    }
+}
+
+/// Extension methods for formatting an [`Amount`] as a decimal number of
+/// CCD. Decimal numbers of CCD are parsed with [`str::parse`], see also the
+/// [`ccd!`](crate::ccd) macro.
+pub trait AmountExt {
+    /// Format the amount as a decimal number of CCD, without trailing zeros
+    /// in the decimals, e.g., `12.5` or `12`. The result is parsed back into
+    /// the same amount by [`str::parse`].
+    fn to_ccd_string(&self) -> String;
+}
+
+impl AmountExt for Amount {
+    fn to_ccd_string(&self) -> String {
+        let whole = self.micro_ccd / 1_000_000;
+        let decimals = self.micro_ccd % 1_000_000;
+        if decimals == 0 {
+            whole.to_string()
+        } else {
+            let decimals = format!("{decimals:06}");
+            format!("{whole}.{}", decimals.trim_end_matches('0'))
+        }
+    }
+}
+
+/// A builder for composing an [`OwnedParameter`] from typed values and raw
+/// bytes.
+///
+/// Each step fails with [`ParameterTooLarge`] if the parameter would exceed
+/// the maximum size.
+///
+/// # Example
+/// ```
+/// # use concordium_smart_contract_testing::*;
+/// # fn main() -> Result<(), ParameterTooLarge> {
+/// let parameter = ParameterBuilder::new()
+///     .append(&1u32)?
+///     .append(&ContractAddress::new(0, 0))?
+///     .append(&OwnedParameter::empty())?
+///     .append_bytes(&[1, 2, 3])?
+///     .build();
+/// assert_eq!(parameter.as_ref().len(), 4 + 16 + 2 + 3);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParameterBuilder {
+    parameter: OwnedParameter,
+}
+
+impl ParameterBuilder {
+    /// Create a builder for an empty parameter.
+    pub fn new() -> Self {
+        Self {
+            parameter: OwnedParameter::empty(),
+        }
+    }
+
+    /// Append the serialization of `value`, see
+    /// [`OwnedParameterExt::append`].
+    pub fn append<T: Serial + ?Sized>(mut self, value: &T) -> Result<Self, ParameterTooLarge> {
+        self.parameter.append(value)?;
+        Ok(self)
+    }
+
+    /// Append raw bytes, see [`OwnedParameterExt::append_bytes`].
+    pub fn append_bytes(mut self, bytes: &[u8]) -> Result<Self, ParameterTooLarge> {
+        self.parameter.append_bytes(bytes)?;
+        Ok(self)
+    }
+
+    /// The number of bytes in the parameter so far.
+    pub fn len(&self) -> usize { self.parameter.as_ref().len() }
+
+    /// Whether the parameter is empty so far.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Finish building the parameter.
+    pub fn build(self) -> OwnedParameter { self.parameter }
+}
+
+impl Default for ParameterBuilder {
+    fn default() -> Self { Self::new() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that appending to a parameter fails at the append that would
+    /// exceed the maximum size, and leaves the parameter unchanged.
+    #[test]
+    fn test_parameter_builder_size_limit() {
+        let builder = ParameterBuilder::new()
+            .append_bytes(&vec![0u8; MAX_PARAMETER_LEN - 4])
+            .expect("Within the limit");
+        let builder = builder.append(&1u32).expect("Exactly at the limit");
+        assert_eq!(builder.len(), MAX_PARAMETER_LEN);
+        assert_eq!(builder.clone().append(&0u8).unwrap_err(), ParameterTooLarge {
+            size:     MAX_PARAMETER_LEN + 1,
+            max_size: MAX_PARAMETER_LEN,
+        });
+
+        let mut parameter = OwnedParameter::new_unchecked(vec![1, 2]);
+        parameter.append_bytes(&vec![0u8; MAX_PARAMETER_LEN]).expect_err("Exceeds the limit");
+        assert_eq!(parameter.as_ref(), &[1, 2]);
+    }
+
+    /// Test that `try_new` reports the size of values which are too large to
+    /// be a parameter.
+    #[test]
+    fn test_parameter_try_new() {
+        let parameter =
+            OwnedParameter::try_new(&(1u32, ContractAddress::new(1, 0))).expect("Small values fit");
+        assert_eq!(
+            parameter,
+            OwnedParameter::from_serial(&(1u32, ContractAddress::new(1, 0)))
+                .expect("Parameter has valid size")
+        );
+        OwnedParameter::try_new(&vec![0u8; MAX_PARAMETER_LEN - 4]).expect("Exactly at the limit");
+
+        // The vector is serialized with a four byte length prefix.
+        assert_eq!(
+            OwnedParameter::try_new(&vec![0u8; MAX_PARAMETER_LEN]).unwrap_err(),
+            ParameterTooLarge {
+                size:     MAX_PARAMETER_LEN + 4,
+                max_size: MAX_PARAMETER_LEN,
+            }
+        );
+    }
+
+    /// Test that building a parameter from its parts gives the same bytes as
+    /// serializing a tuple of them.
+    #[test]
+    fn test_parameter_builder_matches_tuple() {
+        let address = ContractAddress::new(1, 0);
+        let inner = OwnedParameter::new_unchecked(vec![4, 5]);
+        let entrypoint = EntrypointName::new_unchecked("fail");
+        let built = (|| {
+            ParameterBuilder::new()
+                .append(&1u32)?
+                .append(&address)?
+                .append(&inner)?
+                .append(&entrypoint)?
+                .append(&Amount::zero())
+        })()
+        .expect("Parameter has valid size")
+        .build();
+        let expected =
+            OwnedParameter::from_serial(&(1u32, address, inner, entrypoint, Amount::zero()))
+                .expect("Parameter has valid size");
+        assert_eq!(built, expected);
+    }
+
+    /// Test the decoding of the response codes of the `invoke` host function.
+    #[test]
+    fn test_invoke_response_code() {
+        let success = InvokeResponseCode(0x8000_0200_0000_0000);
+        assert!(success.is_success());
+        assert_eq!(success.failure_kind(), None);
+        assert_eq!(success.remaining_on_reject(), None);
+        assert_eq!(success.return_value_index(), Some(2));
+        assert!(success.state_changed());
+        assert!(!InvokeResponseCode(0).state_changed());
+        assert_eq!(InvokeResponseCode(0).return_value_index(), None);
+
+        let logic = InvokeResponseCode(0x0100_ffff_ffef);
+        assert!(!logic.is_success());
+        assert_eq!(logic.failure_kind(), Some(InvokeFailureKind::LogicReject));
+        assert_eq!(logic.remaining_on_reject(), Some(-17));
+        assert_eq!(logic.return_value_index(), Some(1));
+        assert!(!logic.state_changed());
+
+        let trap = InvokeResponseCode(0x0006_0000_0000);
+        assert_eq!(trap.failure_kind(), Some(InvokeFailureKind::Trap));
+        assert_eq!(trap.remaining_on_reject(), Some(0));
+        assert_eq!(trap.return_value_index(), None);
+        assert_eq!(InvokeResponseCode(0x0007_0000_0000).failure_kind(), None);
+
+        assert_eq!(
+            InvokeResponseCode::from_return_value(&u64::to_le_bytes(0x0001_0000_0000)),
+            Some(InvokeResponseCode(0x0001_0000_0000))
+        );
+        assert_eq!(InvokeResponseCode::from_return_value(&[0; 4]), None);
+    }
+
+    /// Test that formatting amounts as decimal numbers of CCD omits trailing
+    /// zeros and is parsed back into the same amount.
+    #[test]
+    fn test_amount_to_ccd_string() {
+        assert_eq!(Amount::from_micro_ccd(12_500_000).to_ccd_string(), "12.5");
+        assert_eq!(Amount::from_ccd(12).to_ccd_string(), "12");
+        assert_eq!(Amount::from_micro_ccd(1).to_ccd_string(), "0.000001");
+        assert_eq!(Amount::zero().to_ccd_string(), "0");
+        for amount in [Amount::from_micro_ccd(1_234_560), Amount::from_ccd(12), Amount::zero()] {
+            assert_eq!(amount.to_ccd_string().parse::<Amount>().ok(), Some(amount));
+        }
+    }
+}
@@ -104,3 +104,88 @@ fn test() {
         .expect("Return value should be deserializable.");
     assert_eq!(rv, 0, "Signature check should succeed, the return value should be 0.");
 }
+
+/// Test that a signature which does not match the data is reported to the
+/// contract as a failed check, instead of making the contract trap.
+#[test]
+fn test_invalid_signature() {
+    let mut chain = Chain::new();
+    let mut csprng = rand::thread_rng();
+    let acc_keys = AccountKeys::generate(
+        AccountThreshold::ONE,
+        &[(0.into(), SignatureThreshold::ONE, &[0.into()])],
+        &mut csprng,
+    );
+    chain.create_account(Account::new_with_keys(
+        helpers::ACC_0,
+        AccountBalance {
+            total:  Amount::from_ccd(1000000),
+            staked: Amount::zero(),
+            locked: Amount::zero(),
+        },
+        (&acc_keys).into(),
+    ));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("account-signature-checks.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                init_name: OwnedContractName::new_unchecked("init_contract".into()),
+                mod_ref:   res_deploy.module_reference,
+
+                param:  OwnedParameter::empty(),
+                amount: Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+
+    let check_signature = |chain: &mut Chain, data: [u8; 34], signatures: &AccountSignatures| {
+        let res = chain
+            .contract_invoke(
+                helpers::ACC_0,
+                Address::Account(helpers::ACC_0),
+                Energy::from(100000),
+                UpdateContractPayload {
+                    address:      res_init.contract_address,
+                    receive_name: OwnedReceiveName::new_unchecked(
+                        "contract.check_signature".into(),
+                    ),
+                    message:      OwnedParameter::from_serial(&(helpers::ACC_0, data, signatures))
+                        .expect("Enough space."),
+                    amount:       Amount::zero(),
+                },
+            )
+            .expect("Querying contract should work");
+        contracts_common::from_bytes::<u64>(&res.return_value)
+            .expect("Return value should be deserializable.")
+    };
+
+    // A 30-element byte array with 4 byte length prefix (in little endian).
+    let mut data = [7u8; 34];
+    data[..4].copy_from_slice(&30u32.to_le_bytes());
+    let signatures = AccountSignatures::from(acc_keys.sign_data(&data[4..]));
+    assert_eq!(
+        check_signature(&mut chain, data, &signatures),
+        0,
+        "Signature check should succeed for the signed data."
+    );
+
+    // Change the data after signing it.
+    data[4] = 8;
+    assert_ne!(
+        check_signature(&mut chain, data, &signatures),
+        0,
+        "Signature check should fail for data that was not signed."
+    );
+}
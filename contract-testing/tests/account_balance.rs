@@ -0,0 +1,59 @@
+//! This module contains tests for the [`AccountBalance`] of accounts with
+//! staked or locked amounts.
+use concordium_smart_contract_testing::*;
+mod helpers;
+
+/// Test that a transfer is paid from the available balance, and that the
+/// staked and locked amounts cannot be spent.
+#[test]
+fn test_transfer_from_available_balance() {
+    let mut chain = Chain::new();
+    let balance =
+        AccountBalance::new(Amount::from_ccd(1000), Amount::from_ccd(300), Amount::from_ccd(200))
+            .expect("The stake and the locked amount fit in the total");
+    chain.create_account(Account::new_with_balance(helpers::ACC_0, balance));
+    chain.create_account(Account::new(helpers::ACC_1, Amount::zero()));
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), Some(Amount::from_ccd(500)));
+
+    let amount = Amount::from_ccd(100);
+    let res = chain
+        .transfer(Signer::with_one_key(), helpers::ACC_0, helpers::ACC_1, amount)
+        .expect("Transferring less than the available balance should work");
+    let after = chain.account_balance(helpers::ACC_0).expect("The account exists");
+    assert_eq!(after.total, balance.total - amount - res.transaction_fee);
+    assert_eq!(after.available(), balance.available() - amount - res.transaction_fee);
+    assert_eq!(after.staked, balance.staked);
+    assert_eq!(after.locked, balance.locked);
+    assert_eq!(chain.account_balance_available(helpers::ACC_1), Some(amount));
+
+    // Less than the total balance, but more than what is available.
+    assert_eq!(
+        chain.transfer(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            helpers::ACC_1,
+            after.available() + Amount::from_micro_ccd(1)
+        ),
+        Err(AccountTransferError::InsufficientFunds)
+    );
+    assert_eq!(chain.account_balance(helpers::ACC_0), Some(after));
+}
+
+/// Test the constructors for accounts with staked or locked amounts.
+#[test]
+fn test_account_balance_constructors() {
+    let total = Amount::from_ccd(10);
+    let account = Account::new(helpers::ACC_0, total);
+    assert_eq!(account.balance.available(), total);
+
+    let account = Account::new_with_locked(helpers::ACC_0, total, Amount::from_ccd(4))
+        .expect("The locked amount fits in the total");
+    assert_eq!(account.balance.locked, Amount::from_ccd(4));
+    assert_eq!(account.balance.available(), Amount::from_ccd(6));
+    assert!(Account::new_with_locked(helpers::ACC_0, total, Amount::from_ccd(11)).is_none());
+
+    let account = Account::new_with_stake(helpers::ACC_0, total, Amount::from_ccd(3))
+        .expect("The stake fits in the total");
+    assert_eq!(account.balance.staked, Amount::from_ccd(3));
+    assert_eq!(account.balance.available(), Amount::from_ccd(7));
+}
@@ -0,0 +1,89 @@
+//! This module tests that the nonces of accounts are incremented by the
+//! transactions they send. It uses the counter contract, which maintains a
+//! 64-bit counter in its state.
+
+use concordium_smart_contract_testing::*;
+mod helpers;
+
+#[test]
+fn test_account_nonce() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    chain.create_account(Account::new(helpers::ACC_1, Amount::from_ccd(1000000)));
+    assert_eq!(chain.account_nonce(helpers::ACC_0), Some(1));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("call-counter.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    assert_eq!(chain.account_nonce(helpers::ACC_0), Some(2));
+
+    let address = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_counter".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address;
+    assert_eq!(chain.account_nonce(helpers::ACC_0), Some(3));
+
+    let payload = |receive_name: &str| UpdateContractPayload {
+        address,
+        receive_name: OwnedReceiveName::new_unchecked(receive_name.into()),
+        message: OwnedParameter::empty(),
+        amount: Amount::zero(),
+    };
+
+    // The invoker of an update is the one whose nonce is incremented, also if
+    // the sender differs or the update fails.
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_1,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            payload("counter.inc"),
+        )
+        .expect("Updating valid contract should work");
+    assert_eq!(chain.account_nonce(helpers::ACC_0), Some(3));
+    assert_eq!(chain.account_nonce(helpers::ACC_1), Some(2));
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_1,
+            Address::Account(helpers::ACC_1),
+            Energy::from(10000),
+            payload("counter.missing"),
+        )
+        .expect_err("Entrypoint does not exist");
+    assert_eq!(chain.account_nonce(helpers::ACC_1), Some(3));
+
+    // Invocations are not transactions.
+    chain
+        .contract_invoke(
+            helpers::ACC_1,
+            Address::Account(helpers::ACC_1),
+            Energy::from(10000),
+            payload("counter.inc"),
+        )
+        .expect("Invoking valid contract should work");
+    assert_eq!(chain.account_nonce(helpers::ACC_1), Some(3));
+
+    chain
+        .transfer(Signer::with_one_key(), helpers::ACC_1, helpers::ACC_0, Amount::from_ccd(1))
+        .expect("Transfer should work");
+    assert_eq!(chain.account_nonce(helpers::ACC_1), Some(4));
+    assert_eq!(chain.account_nonce(helpers::ACC_0), Some(3));
+    assert_eq!(chain.account_nonce(AccountAddress([2; 32])), None);
+}
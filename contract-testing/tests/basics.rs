@@ -93,6 +93,10 @@ fn basics() {
     assert_eq!(update.energy_used, 7787.into());
     assert_eq!(view.energy_used, 301.into());
 
+    // The remaining energy is what is left of the energy reserved.
+    assert_eq!(update.remaining_energy, Energy::from(100000 - 7787));
+    assert_eq!(view.remaining_energy, Energy::from(10000 - 301));
+
     // Check that the amounts charged matches the node.
     assert_eq!(deployment.transaction_fee, Amount::from_micro_ccd(2_685_078));
     assert_eq!(init.transaction_fee, Amount::from_micro_ccd(1_902_454));
@@ -0,0 +1,126 @@
+//! This module tests the block energy limit, which rejects transactions that
+//! do not fit in the energy remaining in the current block.
+use concordium_smart_contract_testing::*;
+mod helpers;
+
+/// Test that an update whose reserved energy exceeds the energy remaining in
+/// the block is rejected before execution, and that it succeeds in a new
+/// block.
+#[test]
+fn test_update_out_of_block_energy() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let contract_address = helpers::deploy_and_init_counter(&mut chain).contract_address;
+
+    let energy_reserved = Energy::from(10000);
+    chain.new_block();
+    chain.set_block_energy_limit(Some(energy_reserved + Energy::from(100)));
+    assert_eq!(chain.block_energy_used(), Energy::from(0));
+
+    // The first update fits in the block.
+    let res_update = helpers::try_increment(&mut chain, contract_address, energy_reserved)
+        .expect("Update fits in block");
+    assert_eq!(chain.block_energy_used(), res_update.energy_used);
+
+    // An update uses more than 100 energy, so the next one does not fit.
+    let balance_before = chain.account_balance_available(helpers::ACC_0);
+    let err = helpers::try_increment(&mut chain, contract_address, energy_reserved)
+        .expect_err("Update does not fit in block");
+    assert!(matches!(err.kind, ContractInvokeErrorKind::OutOfBlockEnergy));
+    assert_eq!(err.transaction_fee, Amount::zero());
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), balance_before);
+    assert_eq!(chain.block_energy_used(), res_update.energy_used);
+    assert_eq!(
+        chain.contract_state_lookup(contract_address, &[0; 8]),
+        Some(1u64.to_le_bytes().to_vec())
+    );
+
+    // In a new block, the update succeeds.
+    chain.new_block();
+    helpers::try_increment(&mut chain, contract_address, energy_reserved)
+        .expect("Update fits in new block");
+    assert_eq!(
+        chain.contract_state_lookup(contract_address, &[0; 8]),
+        Some(2u64.to_le_bytes().to_vec())
+    );
+}
+
+/// Test that deployments and transfers also count towards the block energy
+/// limit.
+#[test]
+fn test_deploy_and_transfer_out_of_block_energy() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    chain.create_account(Account::new(helpers::ACC_1, Amount::from_ccd(1000000)));
+    chain.set_block_energy_limit(Some(Energy::from(1)));
+
+    let err = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("call-counter.wasm"))
+                .expect("module should exist"),
+        )
+        .expect_err("Deployment does not fit in block");
+    assert!(matches!(err.kind, ModuleDeployErrorKind::OutOfBlockEnergy));
+    assert_eq!(
+        chain.transfer(Signer::with_one_key(), helpers::ACC_0, helpers::ACC_1, Amount::from_ccd(1)),
+        Err(AccountTransferError::OutOfBlockEnergy)
+    );
+    assert_eq!(chain.block_energy_used(), Energy::from(0));
+
+    chain.set_block_energy_limit(None);
+    let res_transfer = chain
+        .transfer(Signer::with_one_key(), helpers::ACC_0, helpers::ACC_1, Amount::from_ccd(1))
+        .expect("Transfer succeeds without a limit");
+    assert_eq!(chain.block_energy_used(), res_transfer.energy_used);
+    assert_eq!(chain.block_energy_remaining(), None);
+}
+
+/// Test that the outcomes of the transactions in a simulated block depend on
+/// their order when they compete for the block energy.
+#[test]
+fn test_simulate_block_ordering() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    chain.create_account(Account::new(helpers::ACC_1, Amount::from_ccd(1000000)));
+    let contract_address = helpers::deploy_and_init_counter(&mut chain).contract_address;
+
+    let energy_reserved = Energy::from(10000);
+    chain.set_block_energy_limit(Some(energy_reserved + Energy::from(100)));
+    let update = Transaction::ContractUpdate {
+        signer: Signer::with_one_key(),
+        invoker: helpers::ACC_0,
+        sender: Address::Account(helpers::ACC_0),
+        energy_reserved,
+        payload: UpdateContractPayload {
+            address:      contract_address,
+            receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+            message:      OwnedParameter::empty(),
+            amount:       Amount::zero(),
+        },
+    };
+    let transfer = Transaction::Transfer {
+        signer: Signer::with_one_key(),
+        from:   helpers::ACC_1,
+        to:     helpers::ACC_0,
+        amount: Amount::from_ccd(1),
+    };
+
+    // The update leaves enough energy in the block for the transfer.
+    let res = chain.simulate_block(vec![update.clone(), transfer.clone()]);
+    assert!(res.outcomes.iter().all(|outcome| outcome.success));
+    assert_eq!(res.energy_used, res.outcomes[0].energy_used + res.outcomes[1].energy_used);
+    assert_eq!(chain.block_energy_used(), res.energy_used);
+
+    // The transfer leaves less energy than is reserved for the update.
+    let res = chain.simulate_block(vec![transfer, update]);
+    assert!(res.outcomes[0].success);
+    assert!(!res.outcomes[1].success);
+    assert_eq!(res.outcomes[1].transaction_fee, Amount::zero());
+    assert_eq!(res.energy_used, res.outcomes[0].energy_used);
+    assert_eq!(
+        chain.contract_state_lookup(contract_address, &[0; 8]),
+        Some(1u64.to_le_bytes().to_vec())
+    );
+}
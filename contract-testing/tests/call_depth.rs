@@ -0,0 +1,192 @@
+//! This module tests the maximum depth of nested contract calls configured
+//! with `Chain::set_max_call_depth`. It uses a small module whose `rec.call`
+//! entrypoint calls a contract with its own parameter as the invoke payload,
+//! which makes it possible to nest calls to any depth.
+
+use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder, I32, I64};
+mod helpers;
+
+/// A v1 module with the contract `rec`, including the prefix of 4 version
+/// bytes and 4 module length bytes.
+///
+/// If its parameter is empty, the entrypoint `rec.call` returns. Otherwise
+/// it invokes a contract, using the parameter as the payload of the call.
+fn recursive_module() -> Vec<u8> {
+    let mut module = ModuleBuilder::new();
+    let get_parameter_size = module.import("get_parameter_size", &[I32], &[I32]);
+    let get_parameter_section = module.import("get_parameter_section", &[I32; 4], &[I32]);
+    let invoke = module.import("invoke", &[I32; 3], &[I64]);
+    module.memory(1);
+    module.entrypoint("init_rec", &[], Body::new().i32_const(0));
+    // The local holds the parameter size.
+    let call = Body::new()
+        // Return 0 if the parameter is empty.
+        .i32_const(0)
+        .call(get_parameter_size)
+        .local_tee(1)
+        .i32_eqz()
+        .if_then()
+        .i32_const(0)
+        .return_value()
+        .end()
+        // Copy the parameter to memory.
+        .i32_const(0)
+        .i32_const(0)
+        .local_get(1)
+        .i32_const(0)
+        .call(get_parameter_section)
+        .drop_value()
+        // Invoke a contract with the parameter as the payload and return 0.
+        .i32_const(1)
+        .i32_const(0)
+        .local_get(1)
+        .call(invoke)
+        .drop_value()
+        .i32_const(0);
+    module.entrypoint("rec.call", &[I32], call);
+    module.build()
+}
+
+/// The parameter which makes `rec.call` of the contract at `address` call
+/// itself `calls` times in a nested fashion, such that the deepest call is at
+/// depth `calls`.
+fn nested_parameter(address: ContractAddress, calls: usize) -> OwnedParameter {
+    let mut parameter = OwnedParameter::empty();
+    for _ in 0..calls {
+        parameter = OwnedParameter::from_serial(&(
+            address,
+            parameter,
+            EntrypointName::new_unchecked("call"),
+            Amount::zero(),
+        ))
+        .expect("Parameter has valid size");
+    }
+    parameter
+}
+
+#[test]
+fn test_max_call_depth() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    let res_deploy = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &recursive_module())
+        .expect("Deploying valid module should work");
+    let address = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_rec".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address;
+    let update = |chain: &mut Chain, calls: usize| {
+        chain.contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(100000),
+            UpdateContractPayload {
+                address,
+                receive_name: OwnedReceiveName::new_unchecked("rec.call".into()),
+                message: nested_parameter(address, calls),
+                amount: Amount::zero(),
+            },
+        )
+    };
+
+    // Without a limit, the nesting is only limited by the energy.
+    assert_eq!(chain.max_call_depth(), None);
+    update(&mut chain, 10).expect("Deeply nested calls are allowed");
+
+    // With a limit of 3, the calls can be nested 3 times.
+    chain.set_max_call_depth(Some(3));
+    let success = update(&mut chain, 3).expect("Calls up to the maximum depth are allowed");
+    assert_eq!(
+        success
+            .effective_trace_elements()
+            .filter(|cte| matches!(cte, ContractTraceElement::Interrupted { .. }))
+            .count(),
+        3
+    );
+    assert!(!has_failed_call(&success));
+
+    // The call beyond the maximum depth fails for the contract making it,
+    // which ignores the failure and returns.
+    let success = update(&mut chain, 4).expect("The failed call is returned to the caller");
+    assert!(has_failed_call(&success));
+
+    // A limit of 0 prevents all calls.
+    chain.set_max_call_depth(Some(0));
+    let success = update(&mut chain, 0).expect("No calls are made");
+    assert!(!has_failed_call(&success));
+    let success = update(&mut chain, 1).expect("The failed call is returned to the caller");
+    assert!(has_failed_call(&success));
+}
+
+/// Whether a contract resumed after a failed call in the update.
+fn has_failed_call(success: &ContractInvokeSuccess) -> bool {
+    success.effective_trace_elements().any(|cte| {
+        matches!(cte, ContractTraceElement::Resumed {
+            success: false,
+            ..
+        })
+    })
+}
+
+/// Test that the energy of a chain of three nested calls is attributed to
+/// each of the invocations, and that it adds up to the energy used.
+#[test]
+fn test_call_frames() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    let res_deploy = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &recursive_module())
+        .expect("Deploying valid module should work");
+    let address = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_rec".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address;
+    let success = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(100000),
+            UpdateContractPayload {
+                address,
+                receive_name: OwnedReceiveName::new_unchecked("rec.call".into()),
+                message: nested_parameter(address, 2),
+                amount: Amount::zero(),
+            },
+        )
+        .expect("Nested calls are allowed");
+
+    let frames = &success.call_frames;
+    assert_eq!(frames.iter().map(|frame| frame.depth).collect::<Vec<_>>(), [0, 1, 2]);
+    assert!(frames.iter().all(|frame| frame.address == address
+        && frame.entrypoint == OwnedEntrypointName::new_unchecked("call".into())));
+    let total: u64 = frames.iter().map(|frame| frame.energy.energy).sum();
+    assert_eq!(total, success.energy_used.energy);
+    // The first invocation also pays for the transaction itself, and the last
+    // one returns without making a call.
+    assert!(frames[0].energy > frames[1].energy);
+    assert!(frames[1].energy > frames[2].energy);
+    assert!(frames[2].energy > Energy::from(0));
+}
@@ -0,0 +1,162 @@
+//! This module tests calling CIS-2 entrypoints with `Cis2Client`. It uses a
+//! small module whose entrypoints return fixed responses, so that the tests
+//! check the serialization of the parameters and the parsing of the responses
+//! rather than the logic of a token contract.
+
+use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder, I32};
+mod helpers;
+
+/// The parameter of a `balanceOf` query for the balance of `ACC_0` of the
+/// token with the empty ID: one query, the empty token ID and the account
+/// address.
+fn balance_of_parameter() -> Vec<u8> { [&[1, 0, 0, 0][..], &[0; 32]].concat() }
+
+/// The parameter of an `operatorOf` query for whether `ACC_1` is an operator
+/// of `ACC_0`: one query, and the two account addresses.
+fn operator_of_parameter() -> Vec<u8> { [&[1, 0, 0][..], &[0; 32], &[0], &[1; 32]].concat() }
+
+/// A v1 module with the contract `cis2`, including the prefix of 4 version
+/// bytes and 4 module length bytes.
+///
+/// The entrypoint `cis2.balanceOf` returns a single balance of 300 tokens and
+/// `cis2.operatorOf` returns a single `true`, but they reject with `-1` unless
+/// the parameter is [`balance_of_parameter`] and [`operator_of_parameter`],
+/// respectively. The entrypoint `cis2.transfer` succeeds without doing
+/// anything.
+fn cis2_module() -> Vec<u8> {
+    let mut module = ModuleBuilder::new();
+    let get_parameter_size = module.import("get_parameter_size", &[I32], &[I32]);
+    let get_parameter_section = module.import("get_parameter_section", &[I32; 4], &[I32]);
+    let write_output = module.import("write_output", &[I32; 3], &[I32]);
+    module.memory(1);
+    // The responses, each prefixed by the number of results as two bytes: one
+    // amount of 300 encoded as LEB128 at address 0, and one `true` at address 4.
+    module.data(0, b"\x01\x00\xac\x02\x01\x00\x01");
+    // The expected parameters.
+    module.data(100, &balance_of_parameter());
+    module.data(200, &operator_of_parameter());
+    // Reject with -1 unless the parameter equals the `length` bytes at
+    // `address`, which is checked byte by byte after reading the parameter to
+    // address 1000.
+    let check_parameter = |address: i32, length: i32| {
+        let mut body = Body::new()
+            .i32_const(0)
+            .call(get_parameter_size)
+            .i32_const(length)
+            .i32_ne()
+            .if_then()
+            .i32_const(-1)
+            .return_value()
+            .end()
+            .i32_const(0)
+            .i32_const(1000)
+            .i32_const(length)
+            .i32_const(0)
+            .call(get_parameter_section)
+            .drop_value();
+        for offset in 0..length {
+            body = body
+                .i32_const(1000 + offset)
+                .i32_load8_u()
+                .i32_const(address + offset)
+                .i32_load8_u()
+                .i32_ne()
+                .if_then()
+                .i32_const(-1)
+                .return_value()
+                .end();
+        }
+        body
+    };
+    // Write `length` bytes from `address` as the output and return 0.
+    let respond = |body: Body, address, length| {
+        body.i32_const(address)
+            .i32_const(length)
+            .i32_const(0)
+            .call(write_output)
+            .drop_value()
+            .i32_const(0)
+    };
+    module.entrypoint("init_cis2", &[], Body::new().i32_const(0));
+    let balance_of = check_parameter(100, balance_of_parameter().len() as i32);
+    module.entrypoint("cis2.balanceOf", &[], respond(balance_of, 0, 4));
+    let operator_of = check_parameter(200, operator_of_parameter().len() as i32);
+    module.entrypoint("cis2.operatorOf", &[], respond(operator_of, 4, 3));
+    module.entrypoint("cis2.transfer", &[], Body::new().i32_const(0));
+    module.build()
+}
+
+#[test]
+fn test_cis2_client() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    let res_deploy = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &cis2_module())
+        .expect("Deploying valid module should work");
+    let contract = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_cis2".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address;
+
+    let token_id = TokenId::new_unchecked(Vec::new());
+    let owner = Address::Account(helpers::ACC_0);
+    let mut client = Cis2Client::new(&mut chain, helpers::ACC_0);
+    // The queries only succeed if the parameters are serialized as expected.
+    let balance = client.balance_of(contract, &token_id, owner).expect("The query succeeds");
+    assert_eq!(balance, from_bytes::<TokenAmount>(&[0xac, 0x02]).expect("Valid amount"));
+    assert!(client
+        .operator_of(contract, owner, Address::Account(helpers::ACC_1))
+        .expect("The query succeeds"));
+    // The contract rejects other parameters.
+    assert!(matches!(
+        client.balance_of(contract, &token_id, Address::Account(helpers::ACC_1)),
+        Err(Cis2Error::Invoke(error)) if error.reject_code() == Some(-1)
+    ));
+
+    let success = client
+        .transfer(contract, Cis2Transfer {
+            token_id,
+            amount: from_bytes::<TokenAmount>(&[5]).expect("Valid amount"),
+            from: owner,
+            to: Cis2Receiver::Account(helpers::ACC_0),
+            data: Vec::new(),
+        })
+        .expect("The transfer succeeds");
+    let message = success
+        .effective_trace_elements_cloned()
+        .into_iter()
+        .find_map(|element| match element {
+            ContractTraceElement::Updated {
+                data,
+            } => Some(data.message),
+            _ => None,
+        })
+        .expect("The contract is updated");
+    // One transfer of 5 tokens with the empty token ID, between two accounts
+    // and without additional data.
+    let mut expected = vec![1, 0, 0, 5, 0];
+    expected.extend_from_slice(&[0; 32]);
+    expected.push(0);
+    expected.extend_from_slice(&[0; 32]);
+    expected.extend_from_slice(&[0, 0]);
+    assert_eq!(message.as_ref(), &expected[..]);
+
+    // Calls to contracts that do not exist fail before calling anything.
+    assert!(matches!(
+        client.operator_of(ContractAddress::new(100, 0), owner, owner),
+        Err(Cis2Error::ContractDoesNotExist(ContractDoesNotExist {
+            address,
+        })) if address == ContractAddress::new(100, 0)
+    ));
+}
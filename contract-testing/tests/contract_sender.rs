@@ -0,0 +1,107 @@
+//! This module tests contract updates where the sender is a contract rather
+//! than the invoking account. It uses a small module whose `sender.log`
+//! entrypoint logs the sender it observes.
+
+use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder, I32};
+mod helpers;
+
+/// A v1 module with the contract `sender`, including the prefix of 4 version
+/// bytes and 4 module length bytes.
+///
+/// The entrypoint `sender.log` writes the sender to memory with
+/// `get_receive_sender` and logs the first 33 bytes of memory as an event.
+fn sender_module() -> Vec<u8> {
+    let mut module = ModuleBuilder::new();
+    let get_receive_sender = module.import("get_receive_sender", &[I32], &[]);
+    let log_event = module.import("log_event", &[I32; 2], &[I32]);
+    module.memory(1);
+    module.entrypoint("init_sender", &[], Body::new().i32_const(0));
+    let log = Body::new()
+        .i32_const(0)
+        .call(get_receive_sender)
+        .i32_const(0)
+        .i32_const(33)
+        .call(log_event)
+        .drop_value()
+        .i32_const(0);
+    module.entrypoint("sender.log", &[], log);
+    module.build()
+}
+
+#[test]
+fn test_contract_sender() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    let res_deploy = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &sender_module())
+        .expect("Deploying valid module should work");
+    let mut init = || {
+        chain
+            .contract_init(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Energy::from(10000),
+                InitContractPayload {
+                    mod_ref:   res_deploy.module_reference,
+                    init_name: OwnedContractName::new_unchecked("init_sender".into()),
+                    param:     OwnedParameter::empty(),
+                    amount:    Amount::zero(),
+                },
+            )
+            .expect("Initializing valid contract should work")
+            .contract_address
+    };
+    let sender_contract = init();
+    let callee = init();
+
+    let update = |chain: &mut Chain, sender: Address| {
+        chain.contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            sender,
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      callee,
+                receive_name: OwnedReceiveName::new_unchecked("sender.log".into()),
+                message:      OwnedParameter::empty(),
+                amount:       Amount::zero(),
+            },
+        )
+    };
+    // The logged event starts with the serialized sender.
+    let logged_sender = |success: &ContractInvokeSuccess| {
+        let (address, events) = success.events().next().expect("An event is logged");
+        assert_eq!(address, callee);
+        events[0].as_ref().to_vec()
+    };
+
+    // The callee sees the contract as the sender, while the invoker pays.
+    let balance_before = chain.account_balance_available(helpers::ACC_0).expect("Account exists");
+    let success = update(&mut chain, Address::Contract(sender_contract))
+        .expect("Updating with a contract sender should work");
+    let expected = to_bytes(&Address::Contract(sender_contract));
+    assert_eq!(logged_sender(&success)[..expected.len()], expected[..]);
+    assert_eq!(
+        chain.account_balance_available(helpers::ACC_0),
+        Some(balance_before - success.transaction_fee)
+    );
+    assert_eq!(chain.contract_balance(sender_contract), Some(Amount::zero()));
+
+    // With an account sender, the callee sees the account.
+    let success = update(&mut chain, Address::Account(helpers::ACC_0))
+        .expect("Updating with an account sender should work");
+    assert_eq!(logged_sender(&success), to_bytes(&Address::Account(helpers::ACC_0)));
+
+    // A contract sender which does not exist is rejected without charging.
+    let missing = ContractAddress::new(100, 0);
+    let balance_before = chain.account_balance_available(helpers::ACC_0);
+    let err = update(&mut chain, Address::Contract(missing))
+        .expect_err("Updating with a missing sender should fail");
+    assert!(matches!(
+        err.kind,
+        ContractInvokeErrorKind::SenderDoesNotExist(Address::Contract(address)) if address == missing
+    ));
+    assert_eq!(err.transaction_fee, Amount::zero());
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), balance_before);
+}
@@ -3,6 +3,7 @@
 //! 64-bit counter in its state.
 
 use concordium_smart_contract_testing::*;
+use helpers::assert_counter_state;
 mod helpers;
 
 #[test]
@@ -90,11 +91,587 @@ fn test_counter() {
     assert_counter_state(&mut chain, res_init.contract_address, 12);
 }
 
-/// Looks up in the root of the state trie and compares the value with the
-/// `expected`.
-fn assert_counter_state(chain: &mut Chain, contract_address: ContractAddress, expected: u64) {
+/// Test that the peak segment energy of an update in which the counter calls
+/// itself ten times, and which is therefore split into 21 segments, is bounded
+/// by the energy of the invocation the segment belongs to.
+#[test]
+fn test_peak_segment_energy() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let counter = helpers::deploy_and_init_counter(&mut chain).contract_address;
+
+    let parameter =
+        (counter, OwnedParameter::empty(), EntrypointName::new_unchecked("inc"), Amount::zero());
+    let res_update = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      counter,
+                receive_name: OwnedReceiveName::new_unchecked("counter.inc10".into()),
+                message:      OwnedParameter::from_serial(&parameter)
+                    .expect("Parameter has valid size"),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Updating valid contract should work");
+
+    // Each invocation runs one segment, plus one more each time it resumes
+    // after a call: the 11 invocations and the 10 interrupts of the caller
+    // give 21 segments.
+    let interrupts = res_update
+        .effective_trace_elements()
+        .filter(|cte| matches!(cte, ContractTraceElement::Interrupted { .. }))
+        .count();
+    assert_eq!(res_update.call_frames.len(), 11);
+    assert_eq!(interrupts, 10);
+    assert_eq!(res_update.call_frames.len() + interrupts, 21);
+
+    // A segment is part of a single invocation, so it uses at most the energy
+    // of the largest invocation, which is less than the energy of the update
+    // since every invocation uses some energy.
+    let peak = res_update.peak_segment_energy;
+    let largest_frame =
+        res_update.call_frames.iter().map(|frame| frame.energy).max().expect("There are frames");
+    assert!(res_update.call_frames.iter().all(|frame| frame.energy > Energy::from(0)));
+    assert!(peak > Energy::from(0));
+    assert!(peak <= largest_frame);
+    assert!(largest_frame < res_update.energy_used);
+}
+
+/// Test that the modification index checks made when the counter resumes
+/// after its reentrant calls show that the calls changed its state.
+#[test]
+fn test_modification_checks() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let counter = helpers::deploy_and_init_counter(&mut chain).contract_address;
+
+    // A direct update makes no calls, and thus no checks.
+    let res_update = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      counter,
+                receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+                message:      OwnedParameter::empty(),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Updating valid contract should work");
+    assert!(res_update.modification_checks.is_empty());
+
+    // Make the counter call `inc` on itself ten times.
+    let parameter =
+        (counter, OwnedParameter::empty(), EntrypointName::new_unchecked("inc"), Amount::zero());
+    let res_update = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      counter,
+                receive_name: OwnedReceiveName::new_unchecked("counter.inc10".into()),
+                message:      OwnedParameter::from_serial(&parameter)
+                    .expect("Parameter has valid size"),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Updating valid contract should work");
+
+    // Each reentrant call changed the state of the counter, which it observes
+    // when resuming.
+    let checks = &res_update.modification_checks;
+    assert_eq!(checks.len(), 10);
+    assert!(checks.iter().all(|check| check.address == counter
+        && check.entrypoint == OwnedEntrypointName::new_unchecked("inc10".into())
+        && check.index_before < check.index_after
+        && check.state_changed));
+    assert!(checks.windows(2).all(|pair| pair[0].index_after < pair[1].index_after));
+}
+
+/// Test that `contract_update_with_budget` checks the energy used, and still
+/// saves the update when the budget is exceeded.
+#[test]
+fn test_update_with_budget() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let counter = helpers::deploy_and_init_counter(&mut chain).contract_address;
+    let update = |chain: &mut Chain, max_energy_used: Energy| {
+        chain.contract_update_with_budget(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      counter,
+                receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+                message:      OwnedParameter::empty(),
+                amount:       Amount::zero(),
+            },
+            max_energy_used,
+        )
+    };
+
+    // The budget is checked against the energy used, not the energy reserved.
+    let energy_used = update(&mut chain, Energy::from(10000)).expect("Within budget").energy_used;
+    assert_counter_state(&mut chain, counter, 1);
+    update(&mut chain, energy_used).expect("Using exactly the budget is allowed");
+    assert_counter_state(&mut chain, counter, 2);
+
+    let err = update(&mut chain, energy_used - Energy::from(1)).expect_err("Exceeds the budget");
+    assert!(matches!(
+        err,
+        ContractUpdateBudgetError::BudgetExceeded { energy_used: used, max_energy_used, .. }
+            if used == energy_used && max_energy_used == energy_used - Energy::from(1)
+    ));
+    assert_counter_state(&mut chain, counter, 3);
+}
+
+/// Test that the state of the counter is rendered with the state schema if
+/// present, and hex encoded otherwise.
+#[test]
+fn test_dump_contract_state() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let counter = helpers::deploy_and_init_counter(&mut chain).contract_address;
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      counter,
+                receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+                message:      OwnedParameter::empty(),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Updating valid contract should work");
+
+    let with_state_schema = schema::VersionedModuleSchema::V0(schema::ModuleV0 {
+        contracts: [("counter".to_string(), schema::ContractV0 {
+            state:   Some(schema::Type::U64),
+            init:    None,
+            receive: Default::default(),
+        })]
+        .into(),
+    });
+    let dump = chain.dump_contract_state(counter, &with_state_schema).expect("Contract exists");
+    let dump: serde_json::Value = serde_json::from_str(&dump).expect("The dump is JSON");
+    assert_eq!(dump["0000000000000000"], serde_json::json!(1));
+
+    let without_state_schema = schema::VersionedModuleSchema::V3(schema::ModuleV3 {
+        contracts: Default::default(),
+    });
+    let dump = chain.dump_contract_state(counter, &without_state_schema).expect("Contract exists");
+    let dump: serde_json::Value = serde_json::from_str(&dump).expect("The dump is JSON");
+    assert_eq!(dump["0000000000000000"], serde_json::json!("0100000000000000"));
+
+    assert_eq!(
+        chain.dump_contract_state(ContractAddress::new(100, 0), &without_state_schema),
+        None
+    );
+}
+
+/// Test that a counter inserted with a seeded state can be updated like an
+/// initialized one.
+#[test]
+fn test_insert_contract_unchecked() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let res_deploy = helpers::deploy_counter(&mut chain);
+
+    let spec = ContractSpec::new(
+        res_deploy.module_reference,
+        OwnedContractName::new_unchecked("init_counter".into()),
+        helpers::ACC_1,
+    )
+    .with_state_entry([0u8; 8], 41u64.to_le_bytes())
+    .with_balance(Amount::from_ccd(5));
+    let contract_address =
+        chain.insert_contract_unchecked(spec).expect("Inserting contract should work");
+    assert_eq!(chain.contract_balance(contract_address), Some(Amount::from_ccd(5)));
+    assert_counter_state(&mut chain, contract_address, 41);
+
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+                message:      OwnedParameter::empty(),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Updating inserted contract should work");
+    assert_counter_state(&mut chain, contract_address, 42);
+
+    // The module must be deployed.
+    let spec = ContractSpec::new(
+        ModuleReference::from([0u8; 32]),
+        OwnedContractName::new_unchecked("init_counter".into()),
+        helpers::ACC_0,
+    );
+    assert!(matches!(
+        chain.insert_contract_unchecked(spec),
+        Err(InsertContractError::ModuleDoesNotExist(_))
+    ));
+
+    // The last contract index is never used.
+    chain.set_next_contract_index(u64::MAX - 1).expect("Index is not in use");
+    let spec = ContractSpec::new(
+        res_deploy.module_reference,
+        OwnedContractName::new_unchecked("init_counter".into()),
+        helpers::ACC_0,
+    );
+    assert_eq!(
+        chain.insert_contract_unchecked(spec.clone()).expect("Index is available"),
+        ContractAddress::new(u64::MAX - 1, 0)
+    );
+    assert!(matches!(
+        chain.insert_contract_unchecked(spec),
+        Err(InsertContractError::ContractIndexOverflow)
+    ));
+}
+
+/// Test that the fee estimated from the energy used by an update matches the
+/// fee charged for performing the same update again.
+#[test]
+fn test_estimate_fee() {
+    let mut chain = Chain::builder()
+        .micro_ccd_per_euro(ExchangeRate::new_unchecked(7, 3))
+        .euro_per_energy(ExchangeRate::new_unchecked(1, 11))
+        .build()
+        .unwrap();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let res_deploy = helpers::deploy_counter(&mut chain);
+    assert_eq!(chain.estimate_fee(res_deploy.energy_used), res_deploy.transaction_fee);
+
+    let res_init = helpers::init_counter(&mut chain, res_deploy.module_reference);
+
+    let increment = |chain: &mut Chain| {
+        chain
+            .contract_update(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Address::Account(helpers::ACC_0),
+                Energy::from(10000),
+                UpdateContractPayload {
+                    address:      res_init.contract_address,
+                    receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+                    message:      OwnedParameter::empty(),
+                    amount:       Amount::zero(),
+                },
+            )
+            .expect("Updating valid contract should work")
+    };
+    let estimate = chain.estimate_fee(increment(&mut chain).energy_used);
+
+    let balance_before = chain.account_balance_available(helpers::ACC_0).unwrap();
+    let res_update = increment(&mut chain);
+    assert_eq!(res_update.transaction_fee, estimate);
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), Some(balance_before - estimate));
+}
+
+/// Test that the initial state returned from the initialization is the state
+/// written by the constructor, and that it is not affected by later updates.
+#[test]
+fn test_initial_state() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let res_init = helpers::deploy_and_init_counter(&mut chain);
+    assert_eq!(res_init.initial_state.entries(), [(vec![0u8; 8], u64::to_le_bytes(0).to_vec())]);
+
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      res_init.contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+                message:      OwnedParameter::empty(),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Updating valid contract should work");
+    assert_counter_state(&mut chain, res_init.contract_address, 1);
+    assert_eq!(res_init.initial_state.lookup(&[0u8; 8]), Some(u64::to_le_bytes(0).to_vec()));
+}
+
+/// Test that `detect_reentrancy` reports the resumes of the counter after it
+/// called itself, but not the updates without calls.
+#[test]
+fn test_detect_reentrancy() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let counter = helpers::deploy_and_init_counter(&mut chain).contract_address;
+
+    let update = |chain: &mut Chain, receive_name: &str, message: OwnedParameter| {
+        chain
+            .contract_update(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Address::Account(helpers::ACC_0),
+                Energy::from(10000),
+                UpdateContractPayload {
+                    address: counter,
+                    receive_name: OwnedReceiveName::new_unchecked(receive_name.into()),
+                    message,
+                    amount: Amount::zero(),
+                },
+            )
+            .expect("Updating valid contract should work");
+    };
+
+    let reentrancies = chain.detect_reentrancy(counter, |chain| {
+        update(chain, "counter.inc", OwnedParameter::empty());
+    });
+    assert!(reentrancies.is_empty());
+
+    let parameter =
+        (counter, OwnedParameter::empty(), EntrypointName::new_unchecked("inc"), Amount::zero());
+    let reentrancies = chain.detect_reentrancy(counter, |chain| {
+        update(
+            chain,
+            "counter.inc10",
+            OwnedParameter::from_serial(&parameter).expect("Parameter has valid size"),
+        );
+    });
+    assert_eq!(reentrancies.len(), 10);
+    assert!(reentrancies.iter().all(|check| check.depth == 0
+        && check.entrypoint == OwnedEntrypointName::new_unchecked("inc10".into())));
+
+    // Other contracts are not reported.
+    let reentrancies = chain.detect_reentrancy(ContractAddress::new(100, 0), |chain| {
+        update(
+            chain,
+            "counter.inc10",
+            OwnedParameter::from_serial(&parameter).expect("Parameter has valid size"),
+        );
+    });
+    assert!(reentrancies.is_empty());
+}
+
+/// Test that replaying the recorded transactions on a fresh chain gives the
+/// same outcomes and leaves the chain in the same state, also after the log
+/// has been serialized.
+#[test]
+fn test_replay() {
+    let setup = || {
+        let mut chain = Chain::new();
+        chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+        chain
+    };
+    let mut chain = setup();
+    chain.start_recording();
+    let res_deploy = helpers::deploy_counter(&mut chain);
+    let res_init = helpers::init_counter(&mut chain, res_deploy.module_reference);
+    chain.tick_block_time(Duration::from_seconds(10)).expect("Block time does not overflow");
+    let res_update = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      res_init.contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+                message:      OwnedParameter::empty(),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Updating valid contract should work");
+    let res_missing = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      res_init.contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("counter.missing".into()),
+                message:      OwnedParameter::empty(),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect_err("Updating a missing entrypoint fails");
+    assert_counter_state(&mut chain, res_init.contract_address, 1);
+
+    let mut bytes = chain.recorded().to_bytes();
+    let log = TransactionLog::from_bytes(&bytes).expect("The serialized log is valid");
+    assert_eq!(log.transactions.len(), 4);
+    bytes.push(0);
+    assert!(matches!(TransactionLog::from_bytes(&bytes), Err(ChainPersistError::Malformed(_))));
+    let mut replayed = setup();
+    let outcomes = replayed.replay(&log);
+    let energy: Vec<_> =
+        outcomes.iter().map(|outcome| (outcome.success, outcome.energy_used)).collect();
+    assert_eq!(energy, [
+        (true, res_deploy.energy_used),
+        (true, res_init.energy_used),
+        (true, res_update.energy_used),
+        (false, res_missing.energy_used),
+    ]);
+    assert_eq!(outcomes[3].transaction_fee, res_missing.transaction_fee);
+    assert_counter_state(&mut replayed, res_init.contract_address, 1);
+    assert!(chain.diff(&replayed).is_empty());
+
+    // The replay itself is not recorded.
+    assert!(replayed.recorded().transactions.is_empty());
+}
+
+/// Test that state entries set directly on the chain are seen by subsequent
+/// updates, and that deleted entries are gone.
+#[test]
+fn test_set_contract_state_entry_unchecked() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let counter = helpers::deploy_and_init_counter(&mut chain).contract_address;
+
+    chain
+        .set_contract_state_entry_unchecked(counter, vec![0; 8], u64::to_le_bytes(41).to_vec())
+        .expect("The contract exists");
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      counter,
+                receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+                message:      OwnedParameter::empty(),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Updating valid contract should work");
+    assert_counter_state(&mut chain, counter, 42);
+
+    chain
+        .set_contract_state_entry_unchecked(counter, vec![1], vec![2])
+        .expect("The contract exists");
+    assert_eq!(chain.contract_state_lookup(counter, &[1]), Some(vec![2]));
+    assert_eq!(chain.delete_contract_state_entry_unchecked(counter, &[1]).ok(), Some(true));
+    assert_eq!(chain.delete_contract_state_entry_unchecked(counter, &[1]).ok(), Some(false));
+    assert_eq!(chain.contract_state_lookup(counter, &[1]), None);
+    assert_counter_state(&mut chain, counter, 42);
+
+    let missing = ContractAddress::new(100, 0);
+    assert!(chain.set_contract_state_entry_unchecked(missing, vec![1], vec![2]).is_err());
+    assert!(chain.delete_contract_state_entry_unchecked(missing, &[1]).is_err());
+}
+
+/// Test that an update which runs out of energy after the counter has called
+/// itself a few times leaves no trace of the increments made by the calls.
+#[test]
+fn test_out_of_energy_rollback() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let counter = helpers::deploy_and_init_counter(&mut chain).contract_address;
+
+    let parameter =
+        (counter, OwnedParameter::empty(), EntrypointName::new_unchecked("inc"), Amount::zero());
+    let inc10 = |chain: &mut Chain, energy: Energy| {
+        chain.contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            energy,
+            UpdateContractPayload {
+                address:      counter,
+                receive_name: OwnedReceiveName::new_unchecked("counter.inc10".into()),
+                message:      OwnedParameter::from_serial(&parameter)
+                    .expect("Parameter has valid size"),
+                amount:       Amount::zero(),
+            },
+        )
+    };
+
+    // Find the energy needed for all the calls on a fork, and give the update
+    // half of it, so that it runs out of energy after some of the calls.
+    let energy_needed = inc10(&mut chain.fork(), Energy::from(100000))
+        .expect("Updating valid contract should work")
+        .energy_used;
+    let err = inc10(&mut chain, Energy::from(energy_needed.energy / 2))
+        .expect_err("The update runs out of energy");
+    assert!(err.is_out_of_energy());
+    assert!(!err.is_reject());
+    assert_eq!(err.energy_used, Energy::from(energy_needed.energy / 2));
+    assert_counter_state(&mut chain, counter, 0);
+    assert_eq!(chain.contract_state_size(counter).map(|size| size.entries), Some(1));
+
+    // With enough energy all the increments are saved.
+    inc10(&mut chain, energy_needed).expect("Updating with enough energy should work");
+    assert_counter_state(&mut chain, counter, 10);
+}
+
+/// Test that `deploy_init_and_seed` runs the seeding updates on the new
+/// contract, and names the update which failed.
+#[test]
+fn test_deploy_init_and_seed() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let module = || {
+        module_load_v1_raw(helpers::wasm_test_file("call-counter.wasm"))
+            .expect("module should exist")
+    };
+    let init = SeedInit {
+        contract_name: OwnedContractName::new_unchecked("init_counter".into()),
+        parameter:     OwnedParameter::empty(),
+        amount:        Amount::zero(),
+        energy:        Energy::from(10000),
+    };
+    let inc = SeedUpdate {
+        entrypoint: OwnedEntrypointName::new_unchecked("inc".into()),
+        parameter:  OwnedParameter::empty(),
+        amount:     Amount::zero(),
+        energy:     Energy::from(10000),
+    };
+
+    let before = chain.account_balance_available(helpers::ACC_0).expect("The account exists");
+    let seeded = chain
+        .deploy_init_and_seed(Signer::with_one_key(), helpers::ACC_0, module(), init.clone(), &[
+            inc.clone(),
+            inc.clone(),
+            inc.clone(),
+        ])
+        .expect("Seeding the counter should work");
+    assert_counter_state(&mut chain, seeded.contract_address, 3);
+    assert!(chain.module_exists(seeded.module_reference));
     assert_eq!(
-        chain.contract_state_lookup(contract_address, &[0, 0, 0, 0, 0, 0, 0, 0]).unwrap(),
-        u64::to_le_bytes(expected)
+        chain.account_balance_available(helpers::ACC_0),
+        Some(before - seeded.transaction_fee)
     );
+    assert!(seeded.energy_used > Energy::from(0));
+
+    // The module is already deployed, so a fresh chain is needed.
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let missing = SeedUpdate {
+        entrypoint: OwnedEntrypointName::new_unchecked("missing".into()),
+        ..inc.clone()
+    };
+    let err = chain
+        .deploy_init_and_seed(Signer::with_one_key(), helpers::ACC_0, module(), init, &[
+            inc, missing,
+        ])
+        .expect_err("The second update fails");
+    assert!(matches!(
+        err,
+        SeedError::Update { index: 1, ref entrypoint, .. } if entrypoint.as_entrypoint_name().to_string() == "missing"
+    ));
+    assert!(err.to_string().contains("Seed update 1 to entrypoint 'missing'"));
 }
@@ -0,0 +1,244 @@
+//! This module tests reading the messages printed by contracts with
+//! `concordium_dbg!`, once debug output has been enabled with
+//! `Chain::enable_debug_output`. It uses a small module whose `dbg.call`
+//! entrypoint prints a message and, like the module in `call_depth.rs`, calls
+//! a contract with its own parameter as the invoke payload.
+
+use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder, I32, I64};
+mod helpers;
+
+/// A v1 module with the contract `dbg`, see [`debug_module_builder`],
+/// including the prefix of 4 version bytes and 4 module length bytes.
+fn debug_module() -> Vec<u8> { debug_module_builder().build() }
+
+/// A builder of a v1 module with the contract `dbg`.
+///
+/// If its parameter is empty, the entrypoint `dbg.call` prints "leaf" and
+/// rejects. Otherwise it prints "node", invokes a contract using the
+/// parameter as the payload of the call and returns, ignoring the outcome of
+/// the call.
+fn debug_module_builder() -> ModuleBuilder {
+    let mut module = ModuleBuilder::new();
+    let debug_print = module.import("debug_print", &[I32; 6], &[]);
+    let get_parameter_size = module.import("get_parameter_size", &[I32], &[I32]);
+    let get_parameter_section = module.import("get_parameter_section", &[I32; 4], &[I32]);
+    let invoke = module.import("invoke", &[I32; 3], &[I64]);
+    module.memory(1);
+    // The messages "leaf" and "node" and the file name "lib.rs".
+    module.data(1000, b"leafnodelib.rs");
+    // Print the 4 byte message at `address`, as if from line 1 and column 1 of
+    // "lib.rs".
+    let print = |body: Body, address| {
+        body.i32_const(address)
+            .i32_const(4)
+            .i32_const(1008)
+            .i32_const(6)
+            .i32_const(1)
+            .i32_const(1)
+            .call(debug_print)
+    };
+    module.entrypoint("init_dbg", &[], Body::new().i32_const(0));
+    // The local holds the parameter size.
+    let call = Body::new()
+        // Print "leaf" and reject with -1 if the parameter is empty.
+        .i32_const(0)
+        .call(get_parameter_size)
+        .local_tee(1)
+        .i32_eqz()
+        .if_then();
+    let call = print(call, 1000).i32_const(-1).return_value().end();
+    // Print "node".
+    let call = print(call, 1004)
+        // Copy the parameter to memory.
+        .i32_const(0)
+        .i32_const(0)
+        .local_get(1)
+        .i32_const(0)
+        .call(get_parameter_section)
+        .drop_value()
+        // Invoke a contract with the parameter as the payload and return 0.
+        .i32_const(1)
+        .i32_const(0)
+        .local_get(1)
+        .call(invoke)
+        .drop_value()
+        .i32_const(0);
+    module.entrypoint("dbg.call", &[I32], call);
+    module
+}
+
+#[test]
+fn test_debug_messages() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+
+    // Without debug output, modules that print cannot be deployed.
+    assert!(!chain.debug_output_enabled());
+    let err = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &debug_module())
+        .expect_err("Deploying a module with debug output should fail");
+    assert!(matches!(
+        err.kind,
+        ModuleDeployErrorKind::InvalidModule(ref error)
+            if error.reason() == &ModuleInvalidReason::DisallowedImport {
+                module: "concordium".into(),
+                name:   "debug_print".into(),
+            }
+    ));
+
+    chain.enable_debug_output();
+    let res_deploy = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &debug_module())
+        .expect("Deploying with debug output enabled should work");
+    let address = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_dbg".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address;
+
+    // The contract calls itself, and the nested call prints and rejects.
+    let parameter = OwnedParameter::from_serial(&(
+        address,
+        OwnedParameter::empty(),
+        EntrypointName::new_unchecked("call"),
+        Amount::zero(),
+    ))
+    .expect("Parameter has valid size");
+    let success = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address,
+                receive_name: OwnedReceiveName::new_unchecked("dbg.call".into()),
+                message: parameter,
+                amount: Amount::zero(),
+            },
+        )
+        .expect("Updating valid contract should work");
+
+    let entrypoint = OwnedEntrypointName::new_unchecked("call".into());
+    assert_eq!(success.debug_messages(), [
+        DebugMessage {
+            address,
+            entrypoint: entrypoint.clone(),
+            depth: 0,
+            message: "node".into(),
+            rolled_back: false,
+        },
+        DebugMessage {
+            address,
+            entrypoint,
+            depth: 1,
+            message: "leaf".into(),
+            rolled_back: true,
+        },
+    ]);
+    assert_eq!(
+        success.debug_messages()[1].to_string(),
+        format!("[{address}:call at depth 1, rolled back] leaf")
+    );
+
+    // Messages of a failed update are also available.
+    let err = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address,
+                receive_name: OwnedReceiveName::new_unchecked("dbg.call".into()),
+                message: OwnedParameter::empty(),
+                amount: Amount::zero(),
+            },
+        )
+        .expect_err("Updating with an empty parameter rejects");
+    let messages = err.debug_messages();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].message, "leaf");
+    assert_eq!(messages[0].depth, 0);
+    assert!(messages[0].rolled_back);
+}
+
+/// Test that the default deploy options check like `module_deploy_v1`, that
+/// the debug check is only relaxed and the schema only required when set in
+/// the options, and that the relaxed checks are reported.
+#[test]
+fn test_deploy_with_options() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    let module = || module_load_v1_from_bytes(&debug_module()).expect("The module is well formed");
+
+    let err = chain
+        .module_deploy_v1_with_options(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module(),
+            DeployOptions::default(),
+        )
+        .expect_err("The module uses the debug host functions");
+    assert!(matches!(err.kind, ModuleDeployErrorKind::InvalidModule(_)));
+
+    // Relaxing the debug check does not relax the schema requirement.
+    let err = chain
+        .module_deploy_v1_with_options(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module(),
+            DeployOptions {
+                require_schema: true,
+                allow_debug:    true,
+            },
+        )
+        .expect_err("The module has no schema");
+    assert!(matches!(err.kind, ModuleDeployErrorKind::MissingSchema));
+    assert_eq!(err.transaction_fee, Amount::zero());
+
+    let res = chain
+        .module_deploy_v1_with_options(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module(),
+            DeployOptions {
+                require_schema: false,
+                allow_debug:    true,
+            },
+        )
+        .expect("The debug check is relaxed");
+    assert_eq!(res.suppressed_warnings, vec![DeployWarning::DebugHostFunctions]);
+    assert!(chain.module_exists(res.deployment.module_reference));
+
+    // With a schema and debug output enabled, there is nothing to relax.
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    chain.enable_debug_output();
+    let mut with_schema = debug_module_builder();
+    with_schema.custom_section("concordium-schema", &[]);
+    let with_schema =
+        module_load_v1_from_bytes(&with_schema.build()).expect("The module is well formed");
+    let res = chain
+        .module_deploy_v1_with_options(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            with_schema,
+            DeployOptions {
+                require_schema: true,
+                allow_debug:    false,
+            },
+        )
+        .expect("The module has a schema");
+    assert!(res.suppressed_warnings.is_empty());
+}
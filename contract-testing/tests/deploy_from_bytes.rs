@@ -0,0 +1,141 @@
+//! This module contains tests for deploying modules from in-memory bytes.
+use concordium_smart_contract_testing::*;
+mod helpers;
+
+/// Construct the bytes of a versioned v1 module from the raw wasm file, i.e.,
+/// prefix the contents with the version and length.
+fn versioned_module_bytes(file_name: &str) -> Vec<u8> {
+    let raw = std::fs::read(helpers::wasm_test_file(file_name)).expect("module should exist");
+    let mut bytes = vec![0, 0, 0, 1];
+    bytes.extend_from_slice(&(raw.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&raw);
+    bytes
+}
+
+/// Test that deploying from bytes gives the same module reference as deploying
+/// the module loaded from a file.
+#[test]
+fn test_deploy_from_bytes() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    let mut other_chain = chain.fork();
+
+    let res_bytes = chain
+        .module_deploy_v1_from_bytes(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            &versioned_module_bytes("call-counter.wasm"),
+        )
+        .expect("Deploying valid module from bytes should work");
+
+    let res_file = other_chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("call-counter.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+
+    assert_eq!(res_bytes.module_reference, res_file.module_reference);
+    assert_eq!(res_bytes.energy_used, res_file.energy_used);
+    assert_eq!(res_bytes.transaction_fee, res_file.transaction_fee);
+}
+
+/// Test that invalid bytes are rejected without charging the sender.
+#[test]
+fn test_deploy_from_invalid_bytes() {
+    let mut chain = Chain::new();
+    let initial_balance = Amount::from_ccd(1000);
+    chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+
+    // Version 0 modules are not supported.
+    let mut bytes = versioned_module_bytes("call-counter.wasm");
+    bytes[3] = 0;
+    let err = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &bytes)
+        .expect_err("Deploying a v0 module should fail");
+    assert!(matches!(
+        err.kind,
+        ModuleDeployErrorKind::LoadModule(ModuleLoadErrorKind::UnsupportedModuleVersion(
+            WasmVersion::V0
+        ))
+    ));
+
+    // The length prefix does not match the contents.
+    let bytes = [0, 0, 0, 1, 0, 0, 0, 10, 0, 1];
+    let err = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &bytes)
+        .expect_err("Deploying truncated bytes should fail");
+    assert!(matches!(
+        err.kind,
+        ModuleDeployErrorKind::LoadModule(ModuleLoadErrorKind::ReadModule(_))
+    ));
+    assert_eq!(err.transaction_fee, Amount::zero());
+
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), Some(initial_balance));
+}
+
+/// Test that modules are validated with the rules of the configured protocol
+/// version.
+#[test]
+fn test_deploy_with_protocol_version() {
+    let deploy = |protocol_version| {
+        let mut chain = Chain::builder().protocol_version(protocol_version).build().unwrap();
+        chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+        chain.module_deploy_v1_from_bytes(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            &versioned_module_bytes("upgrading_0.wasm"),
+        )
+    };
+
+    // Upgrades are only supported from protocol version 5.
+    let err = deploy(ProtocolVersion::P4).expect_err("Deploying in P4 should fail");
+    assert!(matches!(
+        err.kind,
+        ModuleDeployErrorKind::InvalidModule(ref error)
+            if error.reason() == &ModuleInvalidReason::DisallowedImport {
+                module: "concordium".into(),
+                name:   "upgrade".into(),
+            }
+    ));
+    assert!(err.to_string().contains("protocol version 4"));
+
+    // Version 1 modules are only supported from protocol version 4.
+    let err = deploy(ProtocolVersion::P3).expect_err("Deploying in P3 should fail");
+    assert!(matches!(err.kind, ModuleDeployErrorKind::InvalidModule(_)));
+
+    deploy(ProtocolVersion::P5).expect("Deploying in P5 should work");
+    deploy(ProtocolVersion::P6).expect("Deploying in P6 should work");
+    deploy(ProtocolVersion::P7).expect("Deploying in P7 should work");
+}
+
+/// Test that deploying the same bytes twice fails with
+/// [`ModuleDeployErrorKind::DuplicateModule`] and does not add a module.
+#[test]
+fn test_deploy_duplicate_module() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    let bytes = versioned_module_bytes("call-counter.wasm");
+
+    let res_deploy = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &bytes)
+        .expect("Deploying valid module from bytes should work");
+    assert!(chain.module_exists(res_deploy.module_reference));
+
+    let before = chain.fork();
+    let err = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &bytes)
+        .expect_err("Deploying the module again should fail");
+    assert!(matches!(
+        err.kind,
+        ModuleDeployErrorKind::DuplicateModule(module_reference)
+            if module_reference == res_deploy.module_reference
+    ));
+    let diff = before.diff(&chain);
+    assert!(diff.modules_added.is_empty());
+    assert!(diff.modules_removed.is_empty());
+    assert!(chain.module_exists(res_deploy.module_reference));
+    assert!(!chain.module_exists(ModuleReference::from([0; 32])));
+}
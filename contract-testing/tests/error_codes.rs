@@ -3,6 +3,7 @@
 //! contract.
 
 use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder, I32, I64};
 mod helpers;
 
 #[test]
@@ -65,6 +66,9 @@ fn test_error_codes() {
         )
         .expect("Updating valid contract should work");
     assert_eq!(res_update_0.return_value, u64::to_le_bytes(0x0100_ffff_ffef));
+    let code_0 = res_update_0.invoke_response_code().expect("The return value is a response code");
+    assert_eq!(code_0.failure_kind(), Some(InvokeFailureKind::LogicReject));
+    assert_eq!(code_0.remaining_on_reject(), Some(-17));
 
     // Invoke an entrypoint that tries to transfer an amount that it does not have
     // via contract invoke. The expected return code is
@@ -96,6 +100,9 @@ fn test_error_codes() {
         )
         .expect("Updating valid contract should work");
     assert_eq!(res_update_1.return_value, u64::to_le_bytes(0x0001_0000_0000));
+    let code_1 = res_update_1.invoke_response_code().expect("The return value is a response code");
+    assert_eq!(code_1.failure_kind(), Some(InvokeFailureKind::AmountTooLarge));
+    assert_eq!(code_1.remaining_on_reject(), Some(0));
 
     // Invoke an entrypoint that traps
     // The expected return code is
@@ -125,6 +132,9 @@ fn test_error_codes() {
         )
         .expect("Updating valid contract should work");
     assert_eq!(res_update_2.return_value, u64::to_le_bytes(0x0002_0000_0000));
+    let code_2 = res_update_2.invoke_response_code().expect("The return value is a response code");
+    assert_eq!(code_2.failure_kind(), Some(InvokeFailureKind::MissingAccount));
+    assert_eq!(code_2.remaining_on_reject(), Some(0));
 
     // Invoke an entrypoint that tries to invoke a non-existing contract.
     // The expected return code is
@@ -156,6 +166,9 @@ fn test_error_codes() {
         )
         .expect("Updating valid contract should work");
     assert_eq!(res_update_3.return_value, u64::to_le_bytes(0x0003_0000_0000));
+    let code_3 = res_update_3.invoke_response_code().expect("The return value is a response code");
+    assert_eq!(code_3.failure_kind(), Some(InvokeFailureKind::MissingContract));
+    assert_eq!(code_3.remaining_on_reject(), Some(0));
 
     // Invoke an entrypoint that tries to invoke a non-existing entrypoint.
     // The expected return code is
@@ -187,6 +200,20 @@ fn test_error_codes() {
         )
         .expect("Updating valid contract should work");
     assert_eq!(res_update_4.return_value, u64::to_le_bytes(0x0004_0000_0000));
+    let code_4 = res_update_4.invoke_response_code().expect("The return value is a response code");
+    assert_eq!(code_4.failure_kind(), Some(InvokeFailureKind::MissingEntrypoint));
+    assert_eq!(code_4.remaining_on_reject(), Some(0));
+    // The engine's view matches that of the chain.
+    assert!(!chain.contract_has_entrypoint(
+        res_init.contract_address,
+        EntrypointName::new_unchecked("nonexisting")
+    ));
+    assert!(chain
+        .contract_has_entrypoint(res_init.contract_address, EntrypointName::new_unchecked("fail")));
+    assert!(!chain.contract_has_entrypoint(
+        ContractAddress::new(1234, 0),
+        EntrypointName::new_unchecked("fail")
+    ));
 
     // Test 5 is omitted as it uses a v0 contract which is not supported in this
     // library.
@@ -221,4 +248,526 @@ fn test_error_codes() {
         )
         .expect("Updating valid contract should work");
     assert_eq!(res_update_6.return_value, u64::to_le_bytes(0x0006_0000_0000));
+    let code_6 = res_update_6.invoke_response_code().expect("The return value is a response code");
+    assert_eq!(code_6.failure_kind(), Some(InvokeFailureKind::Trap));
+    assert_eq!(code_6.remaining_on_reject(), Some(0));
+}
+
+/// Test that the reject code of a contract is decoded into the name of the
+/// error using the error schema, and that the numeric code is used when there
+/// is no error schema.
+#[test]
+fn test_reject_reason_pretty() {
+    let mut chain = Chain::new();
+    let initial_balance = Amount::from_ccd(1000000);
+    chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("caller.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_caller".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+
+    // The "fail" entrypoint rejects with the code -17, which corresponds to the
+    // 17th variant of the error enum.
+    let error_schema = schema::Type::Enum(
+        (0..16)
+            .map(|i| (format!("Error{i}"), schema::Fields::None))
+            .chain(std::iter::once(("InsufficientBalance".into(), schema::Fields::None)))
+            .collect(),
+    );
+    let schema_with_error = caller_schema(Some(error_schema));
+    let schema_without_error = caller_schema(None);
+
+    let receive_name = OwnedReceiveName::new_unchecked("caller.fail".into());
+    let err = chain
+        .contract_update_json(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            &schema_with_error,
+            UpdateContractJsonPayload {
+                amount:       Amount::zero(),
+                address:      res_init.contract_address,
+                receive_name: receive_name.clone(),
+                parameter:    serde_json::json!(1),
+            },
+        )
+        .expect_err("Contract rejects");
+    let err = match err {
+        ContractUpdateJsonError::Invoke(err) => err,
+        other => panic!("Expected the update to be executed, got: {other}"),
+    };
+    assert_eq!(err.reject_code(), Some(-17));
+    assert_eq!(
+        err.reject_reason_pretty(&schema_with_error, receive_name.as_receive_name()),
+        Some("InsufficientBalance".to_string())
+    );
+    assert_eq!(
+        err.reject_reason_pretty(&schema_without_error, receive_name.as_receive_name()),
+        Some("-17".to_string())
+    );
+
+    // A parameter that does not match the schema is rejected before execution.
+    let res = chain.contract_update_json(
+        Signer::with_one_key(),
+        helpers::ACC_0,
+        Address::Account(helpers::ACC_0),
+        Energy::from(10000),
+        &schema_with_error,
+        UpdateContractJsonPayload {
+            amount: Amount::zero(),
+            address: res_init.contract_address,
+            receive_name,
+            parameter: serde_json::json!("not a number"),
+        },
+    );
+    assert!(matches!(res, Err(ContractUpdateJsonError::InvalidParameter(_))));
+}
+
+/// Construct a module schema for the "caller" contract with a `u8` parameter
+/// and the given error schema for the "fail" entrypoint.
+fn caller_schema(error: Option<schema::Type>) -> schema::VersionedModuleSchema {
+    let receive = [("fail".to_string(), schema::FunctionV2 {
+        parameter: Some(schema::Type::U8),
+        return_value: None,
+        error,
+    })]
+    .into_iter()
+    .collect();
+    let contracts = [("caller".to_string(), schema::ContractV3 {
+        init: None,
+        receive,
+        event: None,
+    })]
+    .into_iter()
+    .collect();
+    schema::VersionedModuleSchema::V3(schema::ModuleV3 {
+        contracts,
+    })
+}
+
+/// Test that the amount attached to an update is refunded to the invoker when
+/// the contract rejects, such that only the transaction fee is charged.
+#[test]
+fn test_reject_refunds_amount() {
+    let mut chain = Chain::new();
+    let initial_balance = Amount::from_ccd(1000000);
+    chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("caller.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_caller".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+
+    let balance_before = chain.account_balance_available(helpers::ACC_0).expect("Account exists");
+
+    // The "fail" entrypoint always rejects, so any amount exceeds what it accepts.
+    let amount = Amount::from_ccd(1000);
+    let err = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address: res_init.contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("caller.fail".into()),
+                message: OwnedParameter::empty(),
+                amount,
+            },
+        )
+        .expect_err("Contract rejects");
+
+    assert_eq!(err.reject_code(), Some(-17));
+    // Only the transaction fee is charged, and the amount is refunded.
+    assert_eq!(
+        chain.account_balance_available(helpers::ACC_0),
+        Some(balance_before - err.transaction_fee)
+    );
+    assert_eq!(chain.contract_balance(res_init.contract_address), Some(Amount::zero()));
+}
+
+/// Test that failed updates can be classified, and that the energy used is
+/// reported for the failures that are charged.
+#[test]
+fn test_error_classification() {
+    let mut chain = Chain::new();
+    let initial_balance = Amount::from_ccd(1000000);
+    chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("caller.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_caller".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+
+    let mut update_fail = |energy_reserved: Energy, amount: Amount| {
+        chain
+            .contract_update(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Address::Account(helpers::ACC_0),
+                energy_reserved,
+                UpdateContractPayload {
+                    address: res_init.contract_address,
+                    receive_name: OwnedReceiveName::new_unchecked("caller.fail".into()),
+                    message: OwnedParameter::empty(),
+                    amount,
+                },
+            )
+            .expect_err("Update fails")
+    };
+
+    // The contract rejects, and the energy used is charged.
+    let err = update_fail(Energy::from(10000), Amount::zero());
+    assert!(err.is_reject());
+    assert!(!err.is_out_of_energy() && !err.is_trap() && !err.is_precondition_failure());
+    assert!(err.energy_used > Energy::from(0));
+    assert!(err.transaction_fee > Amount::zero());
+
+    // Too little energy is reserved.
+    let err = update_fail(Energy::from(10), Amount::zero());
+    assert!(err.is_out_of_energy());
+    assert!(!err.is_reject() && !err.is_precondition_failure());
+
+    // The invoker cannot pay for the amount, so the contract is not executed.
+    let err = update_fail(Energy::from(10000), initial_balance);
+    assert!(matches!(err.kind, ContractInvokeErrorKind::AmountTooLarge));
+    assert!(err.is_precondition_failure());
+    assert!(!err.is_reject() && !err.is_out_of_energy());
+}
+
+/// Test that a contract trying to send more CCD than it owns observes a
+/// failed transfer instead of the test panicking, and that a transfer which
+/// would overflow the balance of the recipient is reported as an error.
+#[test]
+fn test_over_send() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    chain.create_account(Account::new(helpers::ACC_1, Amount::from_micro_ccd(u64::MAX - 5)));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("caller.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    let contract_address = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_caller".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address;
+
+    let transfer = |chain: &mut Chain, to: AccountAddress, amount: Amount, deposit: Amount| {
+        chain.contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address:      contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("caller.call".into()),
+                message:      OwnedParameter::from_serial(&(0u32, to, amount))
+                    .expect("Parameter has valid size"),
+                amount:       deposit,
+            },
+        )
+    };
+
+    // The contract owns nothing, so the transfer fails with "insufficient
+    // funds" (hence 01), which the contract returns.
+    let res_update =
+        transfer(&mut chain, helpers::ACC_0, Amount::from_micro_ccd(10_000), Amount::zero())
+            .expect("The failed transfer is observed by the contract");
+    assert_eq!(res_update.return_value, u64::to_le_bytes(0x0001_0000_0000));
+    assert!(res_update.transfers().is_empty());
+
+    // The recipient cannot hold the amount.
+    let res_update = transfer(
+        &mut chain,
+        helpers::ACC_1,
+        Amount::from_micro_ccd(10),
+        Amount::from_micro_ccd(10),
+    );
+    assert!(matches!(
+        res_update,
+        Err(ContractInvokeError {
+            kind: ContractInvokeErrorKind::BalanceOverflow,
+            ..
+        })
+    ));
+    assert_eq!(
+        chain.account_balance_available(helpers::ACC_1),
+        Some(Amount::from_micro_ccd(u64::MAX - 5))
+    );
+    assert_eq!(chain.contract_balance(contract_address), Some(Amount::zero()));
+}
+
+/// Test that updating a missing contract fails with a top-level error, which
+/// is distinct from the response code a contract gets when it calls a missing
+/// contract.
+#[test]
+fn test_missing_contract_top_level() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("caller.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_caller".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+    let missing = ContractAddress::new(1234, 5678);
+    let update = |chain: &mut Chain, address, message| {
+        chain.contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address,
+                receive_name: OwnedReceiveName::new_unchecked("caller.call".into()),
+                message,
+                amount: Amount::zero(),
+            },
+        )
+    };
+
+    // The target of the update does not exist.
+    let err = update(&mut chain, missing, OwnedParameter::empty())
+        .expect_err("Updating a missing contract should fail");
+    assert!(matches!(
+        err.kind,
+        ContractInvokeErrorKind::ContractDoesNotExist(ContractDoesNotExist { address }) if address == missing
+    ));
+    assert!(err.trace_elements.is_empty());
+
+    // The target exists, but it calls a missing contract.
+    let parameter = OwnedParameter::from_serial(&(
+        1u32, // instruction
+        missing,
+        OwnedParameter::empty(),
+        EntrypointName::new_unchecked("fail"),
+        Amount::zero(),
+    ))
+    .expect("Parameter has valid size");
+    let res = update(&mut chain, res_init.contract_address, parameter)
+        .expect("The calling contract handles the failed call");
+    let code = res.invoke_response_code().expect("The return value is a response code");
+    assert_eq!(code.failure_kind(), Some(InvokeFailureKind::MissingContract));
+}
+
+/// Test that `assert_rejects_insufficient_funds` returns the error when the
+/// contract rejects after a transfer with insufficient funds, as a contract
+/// using `concordium-std` does when it propagates the error with `?`.
+#[test]
+fn test_assert_rejects_insufficient_funds() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    // The receive function transfers 1 CCD to `ACC_0` from the contract, which
+    // has no CCD, and rejects with the code of `TransferError::AmountTooLarge`.
+    let mut module = ModuleBuilder::new();
+    let invoke = module.import("invoke", &[I32; 3], &[I64]);
+    module.memory(1);
+    module.data(0, &[&helpers::ACC_0.0[..], &1_000_000u64.to_le_bytes()].concat());
+    module.entrypoint("init_transfer", &[], Body::new().i32_const(0));
+    module.entrypoint(
+        "transfer.send",
+        &[],
+        Body::new()
+            .i32_const(0) // The transfer tag.
+            .i32_const(0)
+            .i32_const(40)
+            .call(invoke)
+            .drop_value()
+            .i32_const(-2147483635),
+    );
+    let res_deploy = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &module.build())
+        .expect("Deploying valid module should work");
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_transfer".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+
+    let error = chain.assert_rejects_insufficient_funds(
+        Signer::with_one_key(),
+        helpers::ACC_0,
+        Address::Account(helpers::ACC_0),
+        Energy::from(10000),
+        UpdateContractPayload {
+            address:      res_init.contract_address,
+            receive_name: OwnedReceiveName::new_unchecked("transfer.send".into()),
+            message:      OwnedParameter::empty(),
+            amount:       Amount::zero(),
+        },
+    );
+    assert!(error.is_insufficient_funds_reject());
+    assert_eq!(error.reject_code(), Some(-2147483635));
+}
+
+/// Test that `assert_rejects_insufficient_funds` panics when the contract
+/// handles a transfer with insufficient funds instead of rejecting.
+#[test]
+#[should_panic(expected = "so it handled the failure instead of rejecting")]
+fn test_assert_rejects_insufficient_funds_handled() {
+    let (mut chain, address) = init_caller(Amount::from_ccd(1000000));
+    let parameter = OwnedParameter::from_serial(&(
+        1u32, // instruction
+        address,
+        OwnedParameter::empty(),
+        EntrypointName::new_unchecked("fail"),
+        Amount::from_micro_ccd(10_000),
+    ))
+    .expect("Parameter has valid size");
+    chain.assert_rejects_insufficient_funds(
+        Signer::with_one_key(),
+        helpers::ACC_0,
+        Address::Account(helpers::ACC_0),
+        Energy::from(10000),
+        UpdateContractPayload {
+            address,
+            receive_name: OwnedReceiveName::new_unchecked("caller.call".into()),
+            message: parameter,
+            amount: Amount::zero(),
+        },
+    );
+}
+
+/// Test that `assert_rejects_insufficient_funds` panics when it is the invoker
+/// that cannot pay for the update, which is not a failure of the contract.
+#[test]
+#[should_panic(expected = "could not pay for the update, so no contract was run")]
+fn test_assert_rejects_insufficient_funds_invoker() {
+    let (mut chain, address) = init_caller(Amount::from_ccd(1000000));
+    chain.create_account(Account::new(helpers::ACC_1, Amount::from_micro_ccd(1)));
+    chain.assert_rejects_insufficient_funds(
+        Signer::with_one_key(),
+        helpers::ACC_1,
+        Address::Account(helpers::ACC_1),
+        Energy::from(10000),
+        UpdateContractPayload {
+            address,
+            receive_name: OwnedReceiveName::new_unchecked("caller.call".into()),
+            message: OwnedParameter::empty(),
+            amount: Amount::zero(),
+        },
+    );
+}
+
+/// Deploy and initialize the caller contract, with `ACC_0` having the given
+/// balance.
+fn init_caller(balance: Amount) -> (Chain, ContractAddress) {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, balance));
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("caller.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_caller".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+    (chain, res_init.contract_address)
 }
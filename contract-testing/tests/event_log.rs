@@ -0,0 +1,143 @@
+//! This module tests the global log of contract events, `Chain::event_log`.
+//! It uses the contract inspection contract, whose `get_module_reference`
+//! entrypoint logs the module reference of a contract as an event, and rejects
+//! if the contract does not exist.
+
+use concordium_smart_contract_testing::*;
+mod helpers;
+
+#[test]
+fn test_event_log() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(100)));
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("queries-contract-inspection.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(100000),
+            InitContractPayload {
+                init_name: OwnedContractName::new_unchecked("init_contract".into()),
+                mod_ref:   res_deploy.module_reference,
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+    let address = res_init.contract_address;
+    // The deployment and the initialization are transactions 0 and 1.
+    assert!(chain.event_log().iter().all(|event| event.transaction_index == 1));
+    let num_events = chain.event_log().len();
+
+    let get_module_ref = |chain: &mut Chain, target: ContractAddress| {
+        chain.contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(100000),
+            UpdateContractPayload {
+                address,
+                receive_name: OwnedReceiveName::new_unchecked(
+                    "contract.get_module_reference".into(),
+                ),
+                message: OwnedParameter::from_serial(&target).expect("Parameter has valid size"),
+                amount: Amount::zero(),
+            },
+        )
+    };
+
+    let success = get_module_ref(&mut chain, address).expect("Contract exists");
+    let expected = ChainEvent {
+        transaction_index: 2,
+        address,
+        event: success.events().next().expect("An event is logged").1[0].clone(),
+    };
+    assert_eq!(&chain.event_log()[num_events..], &[expected.clone()]);
+
+    // A failed update logs nothing, but still counts as a transaction.
+    let checkpoint = chain.checkpoint();
+    get_module_ref(&mut chain, ContractAddress::new(10, 0)).expect_err("Contract does not exist");
+    assert_eq!(&chain.event_log()[num_events..], &[expected.clone()]);
+    get_module_ref(&mut chain, address).expect("Contract exists");
+    assert_eq!(chain.event_log()[num_events + 1].transaction_index, 4);
+
+    // Restoring a checkpoint removes the events of the transactions rolled back.
+    chain.restore(&checkpoint);
+    assert_eq!(&chain.event_log()[num_events..], &[expected]);
+    get_module_ref(&mut chain, address).expect("Contract exists");
+    assert_eq!(chain.event_log()[num_events + 1].transaction_index, 3);
+}
+
+/// Test that events which cannot be decoded with the event schema set with
+/// `Chain::set_strict_logs` are reported as warnings.
+#[test]
+fn test_strict_logs() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(100)));
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("queries-contract-inspection.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    let address = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(100000),
+            InitContractPayload {
+                init_name: OwnedContractName::new_unchecked("init_contract".into()),
+                mod_ref:   res_deploy.module_reference,
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address;
+
+    let get_module_ref = |chain: &mut Chain| {
+        chain
+            .contract_update(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Address::Account(helpers::ACC_0),
+                Energy::from(100000),
+                UpdateContractPayload {
+                    address,
+                    receive_name: OwnedReceiveName::new_unchecked(
+                        "contract.get_module_reference".into(),
+                    ),
+                    message: OwnedParameter::from_serial(&address)
+                        .expect("Parameter has valid size"),
+                    amount: Amount::zero(),
+                },
+            )
+            .expect("Contract exists")
+    };
+
+    // Without a schema, nothing is checked.
+    assert!(get_module_ref(&mut chain).log_warnings.is_empty());
+
+    // The logged module reference matches a schema of 32 bytes.
+    chain.set_strict_logs(Some(schema::Type::ByteArray(32)));
+    assert!(get_module_ref(&mut chain).log_warnings.is_empty());
+
+    // With a schema of one byte, bytes are left over.
+    chain.set_strict_logs(Some(schema::Type::U8));
+    let success = get_module_ref(&mut chain);
+    let event = success.events().next().expect("An event is logged").1[0].clone();
+    assert_eq!(success.log_warnings, [LogWarning {
+        address,
+        event,
+        error: "31 bytes were left over after decoding".into(),
+    }]);
+}
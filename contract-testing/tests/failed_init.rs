@@ -0,0 +1,230 @@
+//! This module tests that a failed contract initialization leaves no trace on
+//! the chain, apart from the transaction fee charged to the sender.
+
+use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder};
+mod helpers;
+
+#[test]
+fn test_failed_init_leaves_no_trace() {
+    let mut chain = Chain::new();
+    let initial_balance = Amount::from_ccd(1000000);
+    chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("call-counter.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    let payload = InitContractPayload {
+        mod_ref:   res_deploy.module_reference,
+        init_name: OwnedContractName::new_unchecked("init_counter".into()),
+        param:     OwnedParameter::empty(),
+        amount:    Amount::zero(),
+    };
+
+    // Find the energy needed for initializing the contract on a fork.
+    let energy_needed = chain
+        .fork()
+        .contract_init(Signer::with_one_key(), helpers::ACC_0, Energy::from(10000), payload.clone())
+        .expect("Initializing valid contract should work")
+        .energy_used;
+
+    let next_address = chain.next_contract_address();
+    let balance_before = chain.account_balance_available(helpers::ACC_0).expect("Account exists");
+
+    // With one energy too little, the initialization runs out of energy after
+    // executing the init function, while charging for the instance creation.
+    let err = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            energy_needed - Energy::from(1),
+            payload.clone(),
+        )
+        .expect_err("Initializing with too little energy should fail");
+    assert!(matches!(err.kind, ContractInitErrorKind::OutOfEnergy { .. }));
+
+    // No contract was created, no address was consumed, and only the
+    // transaction fee was charged.
+    assert_eq!(chain.contracts_by_module(res_deploy.module_reference), Vec::new());
+    assert_eq!(chain.next_contract_address(), next_address);
+    assert!(!chain.contract_exists(next_address));
+    assert_eq!(
+        chain.account_balance_available(helpers::ACC_0),
+        Some(balance_before - err.transaction_fee)
+    );
+
+    // The next successful initialization gets the address.
+    let res_init = chain
+        .contract_init(Signer::with_one_key(), helpers::ACC_0, Energy::from(10000), payload)
+        .expect("Initializing valid contract should work");
+    assert_eq!(res_init.contract_address, next_address);
+    assert_eq!(chain.contracts_by_module(res_deploy.module_reference), vec![next_address]);
+    assert_eq!(chain.next_contract_address(), ContractAddress::new(next_address.index + 1, 0));
+}
+
+/// Test that contracts can be initialized at large indices, and that
+/// initialization fails cleanly once all the indices have been used.
+#[test]
+fn test_contract_index_overflow() {
+    let mut chain = Chain::new();
+    let initial_balance = Amount::from_ccd(1000000);
+    chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("call-counter.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    let payload = InitContractPayload {
+        mod_ref:   res_deploy.module_reference,
+        init_name: OwnedContractName::new_unchecked("init_counter".into()),
+        param:     OwnedParameter::empty(),
+        amount:    Amount::zero(),
+    };
+
+    chain.set_next_contract_index(u64::MAX - 1).expect("Index is not in use");
+    assert_eq!(
+        chain.set_next_contract_index(0),
+        Err(ContractIndexInUse {
+            index:      0,
+            next_index: u64::MAX - 1,
+        })
+    );
+
+    let res_init = chain
+        .contract_init(Signer::with_one_key(), helpers::ACC_0, Energy::from(10000), payload.clone())
+        .expect("Initializing valid contract should work");
+    assert_eq!(res_init.contract_address, ContractAddress::new(u64::MAX - 1, 0));
+    assert!(chain.contract_exists(res_init.contract_address));
+
+    let balance_before = chain.account_balance_available(helpers::ACC_0).expect("Account exists");
+    let err = chain
+        .contract_init(Signer::with_one_key(), helpers::ACC_0, Energy::from(10000), payload)
+        .expect_err("No more contract indices are available");
+    assert!(matches!(err.kind, ContractInitErrorKind::ContractIndexOverflow));
+    assert_eq!(err.transaction_fee, Amount::zero());
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), Some(balance_before));
+    assert_eq!(chain.contracts_by_module(res_deploy.module_reference), vec![
+        res_init.contract_address
+    ]);
+}
+
+/// Test that the predicted contract address matches the assigned addresses,
+/// and that initializations failing before execution do not consume an
+/// address either.
+#[test]
+fn test_next_contract_address() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("call-counter.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    let payload = InitContractPayload {
+        mod_ref:   res_deploy.module_reference,
+        init_name: OwnedContractName::new_unchecked("init_counter".into()),
+        param:     OwnedParameter::empty(),
+        amount:    Amount::zero(),
+    };
+
+    for index in 0..3 {
+        let predicted = chain.next_contract_address();
+        assert_eq!(predicted, ContractAddress::new(index, 0));
+        let res_init = chain
+            .contract_init(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Energy::from(10000),
+                payload.clone(),
+            )
+            .expect("Initializing valid contract should work");
+        assert_eq!(res_init.contract_address, predicted);
+    }
+
+    let predicted = chain.next_contract_address();
+    let err = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                init_name: OwnedContractName::new_unchecked("init_missing".into()),
+                ..payload
+            },
+        )
+        .expect_err("Initializing a missing contract should fail");
+    assert!(matches!(err.kind, ContractInitErrorKind::ContractNotPresentInModule { .. }));
+    assert_eq!(chain.next_contract_address(), predicted);
+}
+
+/// A v1 module with the contracts `trap`, whose init function always traps,
+/// and `reject`, whose init function always rejects with the code `-1`.
+///
+/// It includes the prefix of 4 version bytes and 4 module length bytes.
+fn failing_init_module() -> Vec<u8> {
+    let mut module = ModuleBuilder::new();
+    module.entrypoint("init_trap", &[], Body::new().unreachable());
+    module.entrypoint("init_reject", &[], Body::new().i32_const(-1));
+    module.build()
+}
+
+/// Test that an init function which traps or rejects creates no contract,
+/// and that the error describes the failure.
+#[test]
+fn test_trapping_and_rejecting_init() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let res_deploy = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &failing_init_module())
+        .expect("Deploying valid module should work");
+    let next_address = chain.next_contract_address();
+    let contracts_before = chain.contracts.keys().copied().collect::<Vec<_>>();
+
+    let init = |chain: &mut Chain, init_name: &str| {
+        chain
+            .contract_init(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Energy::from(10000),
+                InitContractPayload {
+                    mod_ref:   res_deploy.module_reference,
+                    init_name: OwnedContractName::new_unchecked(init_name.into()),
+                    param:     OwnedParameter::empty(),
+                    amount:    Amount::from_ccd(1),
+                },
+            )
+            .expect_err("Initialization should fail")
+    };
+
+    let err = init(&mut chain, "init_trap");
+    assert!(err.is_trap());
+    assert!(!err.is_reject());
+    assert!(!err.is_out_of_energy());
+    assert_eq!(err.reject_code(), None);
+    assert!(err.energy_used > Energy::from(0));
+
+    let err = init(&mut chain, "init_reject");
+    assert!(err.is_reject());
+    assert!(!err.is_trap());
+    assert_eq!(err.reject_code(), Some(-1));
+    assert_eq!(err.return_value(), Some(&[][..]));
+    assert!(err.energy_used > Energy::from(0));
+
+    // Neither initialization created a contract or consumed an address.
+    assert_eq!(chain.contracts.keys().copied().collect::<Vec<_>>(), contracts_before);
+    assert_eq!(chain.contracts_by_module(res_deploy.module_reference), Vec::new());
+    assert_eq!(chain.next_contract_address(), next_address);
+}
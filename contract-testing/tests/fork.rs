@@ -0,0 +1,160 @@
+//! This module tests forking a chain with `Chain::fork` and running scenarios
+//! on the forks in parallel. It uses the counter contract, which maintains a
+//! 64-bit counter in its state.
+
+use concordium_smart_contract_testing::*;
+mod helpers;
+
+#[test]
+fn test_fork_in_parallel() {
+    let mut chain = Chain::new();
+    let initial_balance = Amount::from_ccd(1000000);
+    chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+
+    let address = helpers::deploy_and_init_counter(&mut chain).contract_address;
+    helpers::increment(&mut chain, address);
+    let balance = chain.account_balance_available(helpers::ACC_0);
+
+    // Each scenario increments the counter on its own fork a different number
+    // of times.
+    let handles: Vec<_> = (1..=4u64)
+        .map(|increments| {
+            let mut fork = chain.fork();
+            std::thread::spawn(move || {
+                for _ in 0..increments {
+                    helpers::increment(&mut fork, address);
+                }
+                helpers::assert_counter_state(&fork, address, 1 + increments);
+                fork
+            })
+        })
+        .collect();
+    let forks: Vec<Chain> =
+        handles.into_iter().map(|handle| handle.join().expect("Scenario should succeed")).collect();
+
+    // The forks are isolated from each other.
+    for (fork, increments) in forks.iter().zip(1..=4u64) {
+        helpers::assert_counter_state(fork, address, 1 + increments);
+        assert!(fork.account_balance_available(helpers::ACC_0) < balance);
+    }
+
+    // And from the parent chain.
+    helpers::assert_counter_state(&chain, address, 1);
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), balance);
+
+    // Changes to the parent chain do not affect the forks either.
+    helpers::increment(&mut chain, address);
+    helpers::assert_counter_state(&chain, address, 2);
+    helpers::assert_counter_state(&forks[0], address, 2);
+    helpers::assert_counter_state(&forks[1], address, 3);
+}
+
+/// Test that a fork shares the deployed modules, such that contracts can be
+/// initialized from them, without affecting the parent.
+#[test]
+fn test_fork_modules_and_contracts() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let res_deploy = helpers::deploy_counter(&mut chain);
+
+    let mut fork = chain.fork();
+    let res_init = helpers::init_counter(&mut fork, res_deploy.module_reference);
+
+    assert!(fork.contract_exists(res_init.contract_address));
+    assert!(!chain.contract_exists(res_init.contract_address));
+}
+
+/// Test that the diff between a chain and a fork on which the counter was
+/// incremented reports the changed state entry and the fee paid, and nothing
+/// else.
+#[test]
+fn test_diff() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    chain.create_account(Account::new(helpers::ACC_1, Amount::from_ccd(1000000)));
+    let res_deploy = helpers::deploy_counter(&mut chain);
+    let address = helpers::init_counter(&mut chain, res_deploy.module_reference).contract_address;
+    assert!(chain.diff(&chain.fork()).is_empty());
+
+    let mut fork = chain.fork();
+    helpers::increment(&mut fork, address);
+    fork.tick_block_time(Duration::from_millis(10)).expect("No overflow");
+
+    let diff = chain.diff(&fork);
+    assert_eq!(diff.block_time_delta(), Some(Duration::from_millis(10)));
+    assert!(diff.modules_added.is_empty() && diff.modules_removed.is_empty());
+    // Only the invoker paid a fee.
+    assert_eq!(diff.accounts.len(), 1);
+    assert_eq!(diff.accounts[0].address, helpers::ACC_0);
+    assert_eq!(
+        diff.accounts[0].balance_before,
+        chain.account_balance(helpers::ACC_0).map(|b| b.total)
+    );
+    assert_eq!(
+        diff.accounts[0].balance_after,
+        fork.account_balance(helpers::ACC_0).map(|b| b.total)
+    );
+    assert_eq!(diff.contracts, [ContractDiff {
+        address,
+        balance_before: Some(Amount::zero()),
+        balance_after: Some(Amount::zero()),
+        module_before: Some(res_deploy.module_reference),
+        module_after: Some(res_deploy.module_reference),
+        state: StateDiff {
+            added:   Vec::new(),
+            removed: Vec::new(),
+            changed: vec![StateEntryChange {
+                key:    vec![0; 8],
+                before: u64::to_le_bytes(0).to_vec(),
+                after:  u64::to_le_bytes(1).to_vec(),
+            }],
+        },
+    }]);
+
+    // In the other direction, the block time moved backwards.
+    assert_eq!(fork.diff(&chain).block_time_delta(), None);
+}
+
+/// Test that several threads can invoke a contract on a shared chain at the
+/// same time, and that the invocations do not change the chain.
+#[test]
+fn test_invoke_from_shared_chain() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let address = helpers::deploy_and_init_counter(&mut chain).contract_address;
+    helpers::increment(&mut chain, address);
+    let balance = chain.account_balance_available(helpers::ACC_0);
+
+    let chain = std::sync::Arc::new(chain);
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let chain = std::sync::Arc::clone(&chain);
+            std::thread::spawn(move || {
+                for _ in 0..10 {
+                    let res = chain
+                        .contract_invoke(
+                            helpers::ACC_0,
+                            Address::Account(helpers::ACC_0),
+                            Energy::from(10000),
+                            UpdateContractPayload {
+                                address,
+                                receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+                                message: OwnedParameter::empty(),
+                                amount: Amount::zero(),
+                            },
+                        )
+                        .expect("Invoking valid contract should work");
+                    // The invocation changes the state, but the change is discarded.
+                    assert!(res.state_changed);
+                    helpers::assert_counter_state(&chain, address, 1);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().expect("Query thread should succeed");
+    }
+
+    helpers::assert_counter_state(&chain, address, 1);
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), balance);
+}
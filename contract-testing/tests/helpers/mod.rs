@@ -2,6 +2,8 @@
 //! folder.
 use concordium_smart_contract_testing::*;
 
+pub(crate) mod wasm;
+
 /// Relative path to the wasm test contracts.
 pub(crate) const WASM_TEST_FOLDER: &str =
     "../concordium-rust-sdk/concordium-base/smart-contracts/testdata/contracts/v1";
@@ -23,3 +25,80 @@ pub(crate) const ACC_1: AccountAddress = AccountAddress([1; 32]);
 pub(crate) fn wasm_test_file(file_name: &str) -> String {
     format!("{WASM_TEST_FOLDER}/{file_name}")
 }
+
+/// Deploy the counter module `call-counter.wasm` from `ACC_0`.
+#[allow(dead_code)]
+pub(crate) fn deploy_counter(chain: &mut Chain) -> ModuleDeploySuccess {
+    chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            ACC_0,
+            module_load_v1_raw(wasm_test_file("call-counter.wasm")).expect("module should exist"),
+        )
+        .expect("Deploying valid module should work")
+}
+
+/// Initialize a counter from the deployed `module`, sent by `ACC_0`.
+#[allow(dead_code)]
+pub(crate) fn init_counter(chain: &mut Chain, module: ModuleReference) -> ContractInitSuccess {
+    chain
+        .contract_init(Signer::with_one_key(), ACC_0, Energy::from(10000), InitContractPayload {
+            mod_ref:   module,
+            init_name: OwnedContractName::new_unchecked("init_counter".into()),
+            param:     OwnedParameter::empty(),
+            amount:    Amount::zero(),
+        })
+        .expect("Initializing valid contract should work")
+}
+
+/// Deploy the counter module and initialize a counter from it, both sent by
+/// `ACC_0`.
+#[allow(dead_code)]
+pub(crate) fn deploy_and_init_counter(chain: &mut Chain) -> ContractInitSuccess {
+    let res_deploy = deploy_counter(chain);
+    init_counter(chain, res_deploy.module_reference)
+}
+
+/// Increment the counter by calling the `inc` entrypoint from `ACC_0` with
+/// the given energy reserved.
+#[allow(dead_code)]
+pub(crate) fn try_increment(
+    chain: &mut Chain,
+    contract_address: ContractAddress,
+    energy_reserved: Energy,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    chain.contract_update(
+        Signer::with_one_key(),
+        ACC_0,
+        Address::Account(ACC_0),
+        energy_reserved,
+        UpdateContractPayload {
+            address:      contract_address,
+            receive_name: OwnedReceiveName::new_unchecked("counter.inc".into()),
+            message:      OwnedParameter::empty(),
+            amount:       Amount::zero(),
+        },
+    )
+}
+
+/// Increment the counter by calling the `inc` entrypoint, see
+/// [`try_increment`].
+#[allow(dead_code)]
+pub(crate) fn increment(chain: &mut Chain, contract_address: ContractAddress) {
+    try_increment(chain, contract_address, Energy::from(10000))
+        .expect("Updating valid contract should work");
+}
+
+/// Looks up in the root of the state trie of the counter and compares the
+/// value with the `expected`.
+#[allow(dead_code)]
+pub(crate) fn assert_counter_state(
+    chain: &Chain,
+    contract_address: ContractAddress,
+    expected: u64,
+) {
+    assert_eq!(
+        chain.contract_state_lookup(contract_address, &[0, 0, 0, 0, 0, 0, 0, 0]).unwrap(),
+        u64::to_le_bytes(expected)
+    );
+}
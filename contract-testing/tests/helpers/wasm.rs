@@ -0,0 +1,396 @@
+//! A builder for small v1 smart contract modules, so that tests can define the
+//! contracts they need function by function and instruction by instruction,
+//! instead of relying on compiled contracts or hand-encoded bytes.
+//!
+//! The modules only use the subset of wasm needed by the tests: imported
+//! functions, a single memory, data segments and custom sections. The section
+//! sizes and the module length are computed by the builder.
+// Each test module only uses some of the helpers.
+#![allow(dead_code)]
+
+/// The value type `i32`.
+pub(crate) const I32: u8 = 0x7f;
+
+/// The value type `i64`.
+pub(crate) const I64: u8 = 0x7e;
+
+/// The body of a function, built instruction by instruction. The final `end`
+/// of the body is added by [`ModuleBuilder`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Body(Vec<u8>);
+
+impl Body {
+    /// An empty body.
+    pub(crate) fn new() -> Self { Self::default() }
+
+    /// `i32.const value`.
+    pub(crate) fn i32_const(mut self, value: i32) -> Self {
+        self.0.push(0x41);
+        write_i32(&mut self.0, value);
+        self
+    }
+
+    /// `call function`, where `function` is the index returned by
+    /// [`ModuleBuilder::import`] or [`ModuleBuilder::function`].
+    pub(crate) fn call(mut self, function: u32) -> Self {
+        self.0.push(0x10);
+        write_u32(&mut self.0, function);
+        self
+    }
+
+    /// `local.get local`.
+    pub(crate) fn local_get(mut self, local: u32) -> Self {
+        self.0.push(0x20);
+        write_u32(&mut self.0, local);
+        self
+    }
+
+    /// `local.set local`.
+    pub(crate) fn local_set(mut self, local: u32) -> Self {
+        self.0.push(0x21);
+        write_u32(&mut self.0, local);
+        self
+    }
+
+    /// `local.tee local`.
+    pub(crate) fn local_tee(mut self, local: u32) -> Self {
+        self.0.push(0x22);
+        write_u32(&mut self.0, local);
+        self
+    }
+
+    /// `drop`.
+    pub(crate) fn drop_value(mut self) -> Self {
+        self.0.push(0x1a);
+        self
+    }
+
+    /// `i32.eqz`.
+    pub(crate) fn i32_eqz(mut self) -> Self {
+        self.0.push(0x45);
+        self
+    }
+
+    /// `i32.sub`.
+    pub(crate) fn i32_sub(mut self) -> Self {
+        self.0.push(0x6b);
+        self
+    }
+
+    /// `i32.ne`.
+    pub(crate) fn i32_ne(mut self) -> Self {
+        self.0.push(0x47);
+        self
+    }
+
+    /// `i32.load8_u`, which loads the byte at the address on the stack.
+    pub(crate) fn i32_load8_u(mut self) -> Self {
+        // The alignment and the offset are both 0.
+        self.0.extend_from_slice(&[0x2d, 0x00, 0x00]);
+        self
+    }
+
+    /// `if` without a result, which is closed with [`Body::end`].
+    pub(crate) fn if_then(mut self) -> Self {
+        self.0.extend_from_slice(&[0x04, 0x40]);
+        self
+    }
+
+    /// `loop` without a result, which is closed with [`Body::end`].
+    pub(crate) fn loop_block(mut self) -> Self {
+        self.0.extend_from_slice(&[0x03, 0x40]);
+        self
+    }
+
+    /// `br_if depth`, which branches to the start of the enclosing loop if
+    /// `depth` is 0.
+    pub(crate) fn br_if(mut self, depth: u32) -> Self {
+        self.0.push(0x0d);
+        write_u32(&mut self.0, depth);
+        self
+    }
+
+    /// `end`, which closes a block started with [`Body::if_then`] or
+    /// [`Body::loop_block`].
+    pub(crate) fn end(mut self) -> Self {
+        self.0.push(0x0b);
+        self
+    }
+
+    /// `return`.
+    pub(crate) fn return_value(mut self) -> Self {
+        self.0.push(0x0f);
+        self
+    }
+
+    /// `unreachable`, which traps.
+    pub(crate) fn unreachable(mut self) -> Self {
+        self.0.push(0x00);
+        self
+    }
+
+    /// Instructions given by their encoding, e.g., instructions that smart
+    /// contracts may not use.
+    pub(crate) fn raw(mut self, instructions: &[u8]) -> Self {
+        self.0.extend_from_slice(instructions);
+        self
+    }
+}
+
+/// A function defined by a module.
+#[derive(Debug, Clone)]
+struct Function {
+    /// The index of the type of the function.
+    type_index: u32,
+    /// The types of the locals, apart from the parameters.
+    locals:     Vec<u8>,
+    /// The body of the function, without the final `end`.
+    body:       Body,
+}
+
+/// A builder for v1 smart contract modules.
+///
+/// Functions are identified by their index, which is returned when they are
+/// added. As in wasm, the imported functions come first, so all imports must
+/// be added before the first function is defined.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ModuleBuilder {
+    /// The function types, as pairs of the parameter and the result types.
+    types:           Vec<(Vec<u8>, Vec<u8>)>,
+    /// The imported functions, as triples of the module, the name and the
+    /// type index.
+    imports:         Vec<(String, String, u32)>,
+    /// The functions defined by the module.
+    functions:       Vec<Function>,
+    /// The exports, as triples of the name, the kind and the index.
+    exports:         Vec<(String, u8, u32)>,
+    /// The initial number of pages of the memory, if it has one.
+    memory_pages:    Option<u32>,
+    /// The data segments, as pairs of the address and the bytes.
+    data:            Vec<(i32, Vec<u8>)>,
+    /// The custom sections, as pairs of the name and the contents.
+    custom_sections: Vec<(String, Vec<u8>)>,
+}
+
+impl ModuleBuilder {
+    /// A module without any functions or memory.
+    pub(crate) fn new() -> Self { Self::default() }
+
+    /// Import the host function `name` from the `concordium` module with the
+    /// given type and return its function index.
+    ///
+    /// # Panics
+    /// Panics if a function has already been defined.
+    pub(crate) fn import(&mut self, name: &str, params: &[u8], results: &[u8]) -> u32 {
+        self.import_from("concordium", name, params, results)
+    }
+
+    /// Like [`ModuleBuilder::import`], but importing from `module`, e.g., to
+    /// test that only host functions can be imported.
+    pub(crate) fn import_from(
+        &mut self,
+        module: &str,
+        name: &str,
+        params: &[u8],
+        results: &[u8],
+    ) -> u32 {
+        assert!(self.functions.is_empty(), "Imports must be added before the functions");
+        let type_index = self.type_index(params, results);
+        self.imports.push((module.into(), name.into(), type_index));
+        self.imports.len() as u32 - 1
+    }
+
+    /// Define a function with the given type, additional locals and body, and
+    /// return its function index.
+    pub(crate) fn function(
+        &mut self,
+        params: &[u8],
+        results: &[u8],
+        locals: &[u8],
+        body: Body,
+    ) -> u32 {
+        let type_index = self.type_index(params, results);
+        self.functions.push(Function {
+            type_index,
+            locals: locals.to_vec(),
+            body,
+        });
+        (self.imports.len() + self.functions.len()) as u32 - 1
+    }
+
+    /// Define an init or receive function, which has the type `(i64) -> i32`,
+    /// export it as `name` and return its function index.
+    pub(crate) fn entrypoint(&mut self, name: &str, locals: &[u8], body: Body) -> u32 {
+        let index = self.function(&[I64], &[I32], locals, body);
+        self.export_function(name, index);
+        index
+    }
+
+    /// Export the function with the index `function` as `name`.
+    pub(crate) fn export_function(&mut self, name: &str, function: u32) {
+        self.exports.push((name.into(), 0x00, function));
+    }
+
+    /// Give the module a memory of `pages` pages of 64 KiB, exported as
+    /// `memory` as required by the host functions.
+    pub(crate) fn memory(&mut self, pages: u32) {
+        self.memory_pages = Some(pages);
+        self.exports.push(("memory".into(), 0x02, 0));
+    }
+
+    /// Initialize the memory at `address` with `bytes`.
+    pub(crate) fn data(&mut self, address: i32, bytes: &[u8]) {
+        self.data.push((address, bytes.to_vec()));
+    }
+
+    /// Add a custom section with the given name and contents, e.g., an
+    /// embedded schema.
+    pub(crate) fn custom_section(&mut self, name: &str, contents: &[u8]) {
+        self.custom_sections.push((name.into(), contents.to_vec()));
+    }
+
+    /// The encoded wasm module.
+    pub(crate) fn wasm(&self) -> Vec<u8> {
+        let mut out = b"\x00asm\x01\x00\x00\x00".to_vec();
+        if !self.types.is_empty() {
+            write_section(&mut out, 1, self.types.iter(), |out, (params, results)| {
+                out.push(0x60);
+                write_vec(out, params);
+                write_vec(out, results);
+            });
+        }
+        if !self.imports.is_empty() {
+            write_section(&mut out, 2, self.imports.iter(), |out, (module, name, ty)| {
+                write_name(out, module);
+                write_name(out, name);
+                out.push(0x00);
+                write_u32(out, *ty);
+            });
+        }
+        if !self.functions.is_empty() {
+            write_section(&mut out, 3, self.functions.iter(), |out, f| {
+                write_u32(out, f.type_index)
+            });
+        }
+        if let Some(pages) = self.memory_pages {
+            write_section(&mut out, 5, [pages].iter(), |out, pages| {
+                out.push(0x00);
+                write_u32(out, *pages);
+            });
+        }
+        if !self.exports.is_empty() {
+            write_section(&mut out, 7, self.exports.iter(), |out, export| {
+                let (name, kind, index) = export;
+                write_name(out, name);
+                out.push(*kind);
+                write_u32(out, *index);
+            });
+        }
+        if !self.functions.is_empty() {
+            write_section(&mut out, 10, self.functions.iter(), |out, f| {
+                let mut code = Vec::new();
+                write_u32(&mut code, f.locals.len() as u32);
+                for local in &f.locals {
+                    code.extend_from_slice(&[1, *local]);
+                }
+                code.extend_from_slice(&f.body.0);
+                code.push(0x0b);
+                write_vec(out, &code);
+            });
+        }
+        if !self.data.is_empty() {
+            write_section(&mut out, 11, self.data.iter(), |out, (address, bytes)| {
+                // An active segment for memory 0 at the constant address.
+                out.extend_from_slice(&[0x00, 0x41]);
+                write_i32(out, *address);
+                out.push(0x0b);
+                write_vec(out, bytes);
+            });
+        }
+        for (name, contents) in &self.custom_sections {
+            let mut section = Vec::new();
+            write_name(&mut section, name);
+            section.extend_from_slice(contents);
+            out.push(0);
+            write_vec(&mut out, &section);
+        }
+        out
+    }
+
+    /// The encoded wasm module, with the prefix of 4 version bytes and 4
+    /// module length bytes, as output by `cargo concordium build`.
+    pub(crate) fn build(&self) -> Vec<u8> {
+        let wasm = self.wasm();
+        let mut out = vec![0, 0, 0, 1];
+        out.extend_from_slice(&(wasm.len() as u32).to_be_bytes());
+        out.extend_from_slice(&wasm);
+        out
+    }
+
+    /// The index of the type `params -> results`, which is added if it does
+    /// not exist yet.
+    fn type_index(&mut self, params: &[u8], results: &[u8]) -> u32 {
+        let ty = (params.to_vec(), results.to_vec());
+        let index = match self.types.iter().position(|existing| *existing == ty) {
+            Some(index) => index,
+            None => {
+                self.types.push(ty);
+                self.types.len() - 1
+            }
+        };
+        index as u32
+    }
+}
+
+/// Write a section with the given id, consisting of a vector of the `items`,
+/// each written by `write_item`.
+fn write_section<T>(
+    out: &mut Vec<u8>,
+    id: u8,
+    items: impl ExactSizeIterator<Item = T>,
+    mut write_item: impl FnMut(&mut Vec<u8>, T),
+) {
+    let mut contents = Vec::new();
+    write_u32(&mut contents, items.len() as u32);
+    for item in items {
+        write_item(&mut contents, item);
+    }
+    out.push(id);
+    write_vec(out, &contents);
+}
+
+/// Write bytes prefixed by their length.
+fn write_vec(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_u32(out, bytes.len() as u32);
+    out.extend_from_slice(bytes);
+}
+
+/// Write a name prefixed by its length.
+fn write_name(out: &mut Vec<u8>, name: &str) { write_vec(out, name.as_bytes()) }
+
+/// Write an unsigned LEB128 encoded `u32`.
+fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Write a signed LEB128 encoded `i32`.
+fn write_i32(out: &mut Vec<u8>, mut value: i32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let sign_bit = byte & 0x40 != 0;
+        if (value == 0 && !sign_bit) || (value == -1 && sign_bit) {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
@@ -0,0 +1,283 @@
+//! This module tests inspecting and decoding the events logged in an update,
+//! e.g., with `ContractInvokeSuccess::logs` and `assert_log`. It uses small
+//! modules whose entrypoints log fixed events, so that the tests control the
+//! exact bytes logged, including malformed events.
+
+use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder, I32, I64};
+mod helpers;
+
+/// A builder for a module with the contract `events`, whose entrypoints log
+/// fixed events.
+struct EventsModule {
+    module:       ModuleBuilder,
+    log_event:    u32,
+    invoke:       u32,
+    /// The address in memory where the next stored bytes are placed.
+    next_address: i32,
+}
+
+impl EventsModule {
+    /// A module with only the init function `init_events`.
+    fn new() -> Self {
+        let mut module = ModuleBuilder::new();
+        let log_event = module.import("log_event", &[I32; 2], &[I32]);
+        let invoke = module.import("invoke", &[I32; 3], &[I64]);
+        module.memory(1);
+        module.entrypoint("init_events", &[], Body::new().i32_const(0));
+        Self {
+            module,
+            log_event,
+            invoke,
+            next_address: 0,
+        }
+    }
+
+    /// Store `bytes` in the memory and return their address.
+    fn store(&mut self, bytes: &[u8]) -> i32 {
+        let address = self.next_address;
+        self.module.data(address, bytes);
+        self.next_address += bytes.len() as i32;
+        address
+    }
+
+    /// Add the instructions logging each of the `events`, in order, to `body`.
+    fn log(&mut self, mut body: Body, events: &[Vec<u8>]) -> Body {
+        for event in events {
+            let address = self.store(event);
+            body = body
+                .i32_const(address)
+                .i32_const(event.len() as i32)
+                .call(self.log_event)
+                .drop_value();
+        }
+        body
+    }
+
+    /// Add the instructions calling the entrypoint `entrypoint` of the
+    /// contract `address` without a parameter or an amount to `body`.
+    fn call(&mut self, body: Body, address: ContractAddress, entrypoint: &str) -> Body {
+        let parameter = OwnedParameter::from_serial(&(
+            address,
+            OwnedParameter::empty(),
+            EntrypointName::new_unchecked(entrypoint),
+            Amount::zero(),
+        ))
+        .expect("Parameter has valid size");
+        let address = self.store(parameter.as_ref());
+        body.i32_const(1) // The call tag.
+            .i32_const(address)
+            .i32_const(parameter.as_ref().len() as i32)
+            .call(self.invoke)
+            .drop_value()
+    }
+
+    /// Add the entrypoint `events.{name}`, which runs `body` and returns
+    /// `code`, i.e., succeeds if `code` is 0 and rejects otherwise.
+    fn entrypoint(&mut self, name: &str, body: Body, code: i32) {
+        self.module.entrypoint(&format!("events.{name}"), &[], body.i32_const(code));
+    }
+
+    /// Deploy the module and initialize `count` instances of the contract,
+    /// which get the addresses `<0, 0>`, `<1, 0>`, and so on.
+    fn deploy(self, count: usize) -> Chain {
+        let mut chain = Chain::new();
+        chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+        let res_deploy = chain
+            .module_deploy_v1_from_bytes(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                &self.module.build(),
+            )
+            .expect("Deploying valid module should work");
+        for _ in 0..count {
+            chain
+                .contract_init(
+                    Signer::with_one_key(),
+                    helpers::ACC_0,
+                    Energy::from(10000),
+                    InitContractPayload {
+                        mod_ref:   res_deploy.module_reference,
+                        init_name: OwnedContractName::new_unchecked("init_events".into()),
+                        param:     OwnedParameter::empty(),
+                        amount:    Amount::zero(),
+                    },
+                )
+                .expect("Initializing valid contract should work");
+        }
+        chain
+    }
+}
+
+/// Update the entrypoint `events.{entrypoint}` of the contract at `address`.
+fn update(
+    chain: &mut Chain,
+    address: ContractAddress,
+    entrypoint: &str,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    chain.contract_update(
+        Signer::with_one_key(),
+        helpers::ACC_0,
+        Address::Account(helpers::ACC_0),
+        Energy::from(100000),
+        UpdateContractPayload {
+            address,
+            receive_name: OwnedReceiveName::new_unchecked(format!("events.{entrypoint}")),
+            message: OwnedParameter::empty(),
+            amount: Amount::zero(),
+        },
+    )
+}
+
+/// Deploy a module whose entrypoint `events.log` logs `events`, initialize
+/// one contract and update the entrypoint.
+fn log_events(events: &[Vec<u8>]) -> ContractInvokeSuccess {
+    let mut module = EventsModule::new();
+    let body = module.log(Body::new(), events);
+    module.entrypoint("log", body, 0);
+    let mut chain = module.deploy(1);
+    update(&mut chain, ContractAddress::new(0, 0), "log").expect("Updating should work")
+}
+
+/// Test that `logs` preserves the order in which the events were logged when
+/// a contract calls another, and that `logs_for` only returns the logs of the
+/// given contract.
+#[test]
+fn test_logs_for() {
+    let caller = ContractAddress::new(0, 0);
+    let callee = ContractAddress::new(1, 0);
+    let mut module = EventsModule::new();
+    let body = module.log(Body::new(), &[vec![2]]);
+    module.entrypoint("log", body, 0);
+    // Log an event, call the callee, which logs another, and log a third.
+    let body = module.log(Body::new(), &[vec![1]]);
+    let body = module.call(body, callee, "log");
+    let body = module.log(body, &[vec![3]]);
+    module.entrypoint("call", body, 0);
+    let mut chain = module.deploy(2);
+
+    let success = update(&mut chain, caller, "call").expect("Updating should work");
+    assert_eq!(success.logs(), vec![&[1u8][..], &[2], &[3]]);
+    assert_eq!(success.logs_for(caller), vec![&[1u8][..], &[3]]);
+    assert_eq!(success.logs_for(callee), vec![&[2u8][..]]);
+    assert!(success.logs_for(ContractAddress::new(2, 0)).is_empty());
+}
+
+/// Test that `assert_log` parses the event at the given index, counting
+/// the events of all the updated contracts.
+#[test]
+fn test_assert_log() {
+    let caller = ContractAddress::new(0, 0);
+    let callee = ContractAddress::new(1, 0);
+    let mut module = EventsModule::new();
+    let body = module.log(Body::new(), &[to_bytes(&(2u8, 3u64))]);
+    module.entrypoint("log", body, 0);
+    let body = module.log(Body::new(), &[to_bytes(&1u32)]);
+    let body = module.call(body, callee, "log");
+    module.entrypoint("call", body, 0);
+    let mut chain = module.deploy(2);
+
+    let success = update(&mut chain, caller, "call").expect("Updating should work");
+    success.assert_log(0, 1u32);
+    // The second event is logged by the callee.
+    success.assert_log(1, (2u8, 3u64));
+}
+
+/// Test that `assert_log` panics if the event differs from the expected
+/// one.
+#[test]
+#[should_panic(expected = "differs from the expected event")]
+fn test_assert_log_different_event() { log_events(&[to_bytes(&1u32)]).assert_log(0, 2u32); }
+
+/// Test that `assert_log` panics if there is no event at the given index.
+#[test]
+#[should_panic(expected = "only 1 events were logged")]
+fn test_assert_log_missing_event() { log_events(&[to_bytes(&1u32)]).assert_log(1, 1u32); }
+
+/// Test that `parse_events` decodes the events matching the schema and
+/// returns the others as raw events.
+#[test]
+fn test_parse_events() {
+    let address = ContractAddress::new(0, 0);
+    let success = log_events(&[to_bytes(&1u32), vec![1, 2], to_bytes(&2u32)]);
+    let events = success.parse_events(&schema::Type::U32);
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0], DecodedEvent::Decoded {
+        address,
+        event: serde_json::json!(1),
+    });
+    assert!(matches!(
+        &events[1],
+        DecodedEvent::Raw { address: raw_address, event, .. }
+            if *raw_address == address && event.as_ref() == [1, 2]
+    ));
+    assert_eq!(events[2].decoded(), Some(&serde_json::json!(2)));
+}
+
+/// Test that `parse_cis2_events` decodes CIS-2 events and returns the
+/// malformed ones as raw events.
+#[test]
+fn test_parse_cis2_events() {
+    // A mint event with tag 254, token id `01`, amount 1 and an account
+    // owner.
+    let mint = [&[254, 1, 1, 1, 0][..], &[0; 32]].concat();
+    let events = log_events(&[mint, Vec::new()]).parse_cis2_events();
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0].decoded(), Some(Cis2Event::Mint { .. })));
+    assert!(matches!(events[1], DecodedEvent::Raw { .. }));
+    assert_eq!(events[1].address(), ContractAddress::new(0, 0));
+}
+
+/// Test that `assert_events_eq` accepts two updates logging the same events,
+/// and reports the differing and missing events otherwise.
+#[test]
+fn test_assert_events_eq() {
+    let flow = |events: &[u32]| {
+        log_events(&events.iter().map(to_bytes).collect::<Vec<_>>())
+            .parse_events(&schema::Type::U32)
+    };
+    assert_events_eq(&flow(&[1, 2]), &flow(&[1, 2]));
+
+    let (baseline, candidate) = (flow(&[1, 2]), flow(&[1, 3, 4]));
+    let message = std::panic::catch_unwind(|| assert_events_eq(&baseline, &candidate))
+        .expect_err("The events differ")
+        .downcast::<String>()
+        .expect("The panic message is a string");
+    // Only the second and third events differ.
+    assert!(!message.contains("[0]"));
+    assert!(message.contains("[1] baseline:"));
+    assert!(message.contains("[2] baseline:  <no event>"));
+}
+
+/// Test that the outcome of an update contains the logs of all the updated
+/// contracts if it succeeds, and none if it fails, even though the events
+/// were logged before the failure.
+#[test]
+fn test_invocation_outcome() {
+    let callee = ContractAddress::new(1, 0);
+    let mut module = EventsModule::new();
+    let body = module.log(Body::new(), &[vec![4]]);
+    module.entrypoint("log", body, 0);
+    let body = module.log(Body::new(), &[vec![1], vec![2, 3]]);
+    let body = module.call(body, callee, "log");
+    module.entrypoint("call", body.clone(), 0);
+    module.entrypoint("reject", body, -1);
+    let mut chain = module.deploy(2);
+
+    let outcome = InvocationOutcome::from(update(&mut chain, ContractAddress::new(0, 0), "call"));
+    assert!(outcome.is_success());
+    assert_eq!(outcome.logs(), &[vec![1], vec![2, 3], vec![4]]);
+
+    let outcome = InvocationOutcome::from(update(&mut chain, ContractAddress::new(0, 0), "reject"));
+    assert!(!outcome.is_success());
+    assert!(outcome.logs().is_empty());
+    assert!(matches!(
+        outcome,
+        InvocationOutcome::Reject(ContractInvokeErrorKind::ExecutionError {
+            failure_kind: InvokeFailure::ContractReject {
+                code: -1,
+                ..
+            },
+        })
+    ));
+}
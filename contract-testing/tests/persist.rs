@@ -0,0 +1,107 @@
+//! This module tests persisting a chain to disk with `Chain::persist` and
+//! loading it again with `Chain::load_from`. It uses the recorder contract,
+//! which records each `u64` it is given under its own key in the state.
+
+use concordium_smart_contract_testing::*;
+mod helpers;
+
+/// Test that a persisted chain can be loaded and used, and that the contract
+/// state is preserved exactly.
+#[test]
+fn test_persist_and_load() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("record-parameters.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_recorder".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::from_ccd(5),
+            },
+        )
+        .expect("Initializing valid contract should work");
+    let address = res_init.contract_address;
+    record(&mut chain, address, 20);
+    chain.warp_to_block_height(7).expect("Greater height");
+
+    let path = std::env::temp_dir().join(format!("persist-and-load-{}.chain", std::process::id()));
+    chain.persist(&path).expect("Persisting the chain should work");
+    let mut loaded = Chain::load_from(&path).expect("Loading the chain should work");
+    std::fs::remove_file(&path).expect("The file exists");
+
+    assert_eq!(loaded.block_time(), chain.block_time());
+    assert_eq!(loaded.block_height(), 7);
+    assert_eq!(loaded.account_balance(helpers::ACC_0), chain.account_balance(helpers::ACC_0));
+    assert_eq!(loaded.contract_balance(address), Some(Amount::from_ccd(5)));
+    assert_eq!(loaded.contract_info(address), chain.contract_info(address));
+    assert_eq!(loaded.next_contract_address(), chain.next_contract_address());
+    // The keys of the state, and their order, are preserved.
+    assert_eq!(
+        loaded.contract_state_keys(address, usize::MAX),
+        chain.contract_state_keys(address, usize::MAX)
+    );
+    for key in 0..20u64 {
+        assert_eq!(
+            loaded.contract_state_lookup(address, &key.to_le_bytes()),
+            chain.contract_state_lookup(address, &key.to_le_bytes())
+        );
+    }
+
+    // The loaded module can still be run.
+    record(&mut loaded, address, 10);
+    assert!(loaded.contract_state_lookup(address, &29u64.to_le_bytes()).is_some());
+}
+
+/// Test that files which are not persisted chains, or which were written
+/// with another version of the format, are rejected.
+#[test]
+fn test_load_incompatible_file() {
+    let path = std::env::temp_dir().join(format!("incompatible-{}.chain", std::process::id()));
+
+    std::fs::write(&path, b"this is not a chain").expect("Writing the file should work");
+    assert!(matches!(Chain::load_from(&path), Err(ChainPersistError::NotAChainFile)));
+
+    let mut bytes = PERSIST_MAGIC.to_vec();
+    bytes.extend_from_slice(&to_bytes(&(PERSIST_FORMAT_VERSION + 1)));
+    std::fs::write(&path, bytes).expect("Writing the file should work");
+    let error = Chain::load_from(&path).expect_err("Loading another version should fail");
+    std::fs::remove_file(&path).expect("The file exists");
+    assert!(
+        matches!(error, ChainPersistError::UnsupportedVersion { found, expected: PERSIST_FORMAT_VERSION } if found == PERSIST_FORMAT_VERSION + 1)
+    );
+
+    assert!(matches!(
+        Chain::load_from(std::env::temp_dir().join("does-not-exist.chain")),
+        Err(ChainPersistError::Io(_))
+    ));
+}
+
+/// Record `count` values in the recorder contract.
+fn record(chain: &mut Chain, address: ContractAddress, count: u64) {
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(100000),
+            UpdateContractPayload {
+                address,
+                receive_name: OwnedReceiveName::new_unchecked("recorder.record_u64".into()),
+                message: OwnedParameter::from_serial(&count).expect("Parameter has valid size"),
+                amount: Amount::zero(),
+            },
+        )
+        .expect("Update failed");
+}
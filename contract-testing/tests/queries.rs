@@ -75,6 +75,25 @@ mod query_account_balance {
         assert!(matches!(res_update.effective_trace_elements_cloned()[..], [
             ContractTraceElement::Updated { .. }
         ]));
+
+        // The contract received the serialized balance of helpers::ACC_1.
+        let expected_balance = AccountBalance {
+            total:  initial_balance,
+            staked: Amount::zero(),
+            locked: Amount::zero(),
+        };
+        let responses: Vec<_> = res_update
+            .trace_elements
+            .iter()
+            .filter_map(|element| match element {
+                DebugTraceElement::Debug {
+                    response,
+                    ..
+                } => Some(response.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(responses, vec![Some(to_bytes(&expected_balance))]);
     }
 
     /// Queries the balance of the invoker account, which will have have the
@@ -358,6 +377,159 @@ mod query_account_balance {
             ContractTraceElement::Updated { .. }
         ]));
     }
+
+    /// Queries the balance of an account which only has a mocked balance, and
+    /// checks that the actual balance takes precedence once the account
+    /// exists.
+    #[test]
+    fn mocked_balance_test() {
+        let mut chain = Chain::new();
+        let initial_balance = Amount::from_ccd(1000000);
+        chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+
+        let res_deploy = chain
+            .module_deploy_v1(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                module_load_v1_raw(helpers::wasm_test_file("queries-account-balance.wasm"))
+                    .expect("module should exist"),
+            )
+            .expect("Deploying valid module should work");
+
+        let res_init = chain
+            .contract_init(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Energy::from(10000),
+                InitContractPayload {
+                    mod_ref:   res_deploy.module_reference,
+                    init_name: OwnedContractName::new_unchecked("init_contract".into()),
+                    param:     OwnedParameter::empty(),
+                    amount:    Amount::zero(),
+                },
+            )
+            .expect("Initializing valid contract should work");
+
+        let mocked_balance = AccountBalance::new(
+            Amount::from_ccd(500),
+            Amount::from_ccd(200),
+            Amount::from_ccd(100),
+        )
+        .expect("Balance is valid");
+        assert_eq!(chain.mock_account_balance(helpers::ACC_1, mocked_balance), None);
+
+        // The contract will query the balance of helpers::ACC_1 and assert that the
+        // three balances match this input.
+        let query = |chain: &mut Chain, total: Amount, staked: Amount, locked: Amount| {
+            let input_param = (helpers::ACC_1, total, staked, locked);
+            chain.contract_update(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Address::Account(helpers::ACC_0),
+                Energy::from(100000),
+                UpdateContractPayload {
+                    address:      res_init.contract_address,
+                    receive_name: OwnedReceiveName::new_unchecked("contract.query".into()),
+                    message:      OwnedParameter::from_serial(&input_param)
+                        .expect("Parameter has valid size"),
+                    amount:       Amount::zero(),
+                },
+            )
+        };
+        query(&mut chain, Amount::from_ccd(500), Amount::from_ccd(200), Amount::from_ccd(100))
+            .expect("Querying the mocked balance should work");
+
+        // The mocked balance cannot be spent.
+        chain
+            .transfer(Signer::with_one_key(), helpers::ACC_1, helpers::ACC_0, Amount::from_ccd(1))
+            .expect_err("Transferring from a mocked account should fail");
+
+        // Once the account exists, its actual balance is used.
+        chain.create_account(Account::new(helpers::ACC_1, initial_balance));
+        query(&mut chain, initial_balance, Amount::zero(), Amount::zero())
+            .expect("Querying the actual balance should work");
+    }
+
+    /// Queries the balance of an account with a stake, which is included in
+    /// the total balance but cannot be transferred.
+    #[test]
+    fn staked_balance_test() {
+        let mut chain = Chain::new();
+        let initial_balance = Amount::from_ccd(1000000);
+        let staked = Amount::from_ccd(300000);
+        chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+        chain.create_account(
+            Account::new_with_stake(helpers::ACC_1, initial_balance, staked)
+                .expect("The stake is less than the total balance"),
+        );
+        assert!(Account::new_with_stake(helpers::ACC_1, staked, initial_balance).is_none());
+
+        let res_deploy = chain
+            .module_deploy_v1(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                module_load_v1_raw(helpers::wasm_test_file("queries-account-balance.wasm"))
+                    .expect("module should exist"),
+            )
+            .expect("Deploying valid module should work");
+
+        let res_init = chain
+            .contract_init(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Energy::from(10000),
+                InitContractPayload {
+                    mod_ref:   res_deploy.module_reference,
+                    init_name: OwnedContractName::new_unchecked("init_contract".into()),
+                    param:     OwnedParameter::empty(),
+                    amount:    Amount::zero(),
+                },
+            )
+            .expect("Initializing valid contract should work");
+
+        // The contract will query the balance of helpers::ACC_1 and assert that the
+        // three balances match this input.
+        let input_param = (helpers::ACC_1, initial_balance, staked, Amount::zero());
+        chain
+            .contract_update(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Address::Account(helpers::ACC_0),
+                Energy::from(100000),
+                UpdateContractPayload {
+                    address:      res_init.contract_address,
+                    receive_name: OwnedReceiveName::new_unchecked("contract.query".into()),
+                    message:      OwnedParameter::from_serial(&input_param)
+                        .expect("Parameter has valid size"),
+                    amount:       Amount::zero(),
+                },
+            )
+            .expect("Querying the staked balance should work");
+
+        // Only the balance which is not staked can pay for the amount and the fee,
+        // but both are deducted from the total balance.
+        assert_eq!(chain.account_balance_available(helpers::ACC_1), Some(initial_balance - staked));
+        let available = initial_balance - staked;
+        chain
+            .transfer(Signer::with_one_key(), helpers::ACC_1, helpers::ACC_0, available)
+            .expect_err("The fee cannot be paid on top of the available balance");
+        let res_transfer = chain
+            .transfer(
+                Signer::with_one_key(),
+                helpers::ACC_1,
+                helpers::ACC_0,
+                Amount::from_ccd(1000),
+            )
+            .expect("Transferring part of the available balance should work");
+        assert_eq!(
+            chain.account_balance(helpers::ACC_1),
+            AccountBalance::new(
+                initial_balance - Amount::from_ccd(1000) - res_transfer.transaction_fee,
+                staked,
+                Amount::zero()
+            )
+        );
+    }
 }
 
 mod query_contract_balance {
@@ -69,3 +69,245 @@ fn test_recorder() {
             .is_some());
     }
 }
+
+/// Test that updates writing state keys longer than the configured maximum
+/// fail, and that keys of exactly the maximum length are accepted.
+#[test]
+fn test_recorder_max_state_key_length() {
+    // The recorder contract uses 8 byte keys, so this limit is one byte too
+    // small.
+    let mut chain = Chain::builder().max_state_key_length(7).build().expect("Chain should build");
+    let contract_address = deploy_and_init_recorder(&mut chain);
+    let res_update = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(100000),
+            UpdateContractPayload {
+                address:      contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("recorder.record_u64".into()),
+                message:      OwnedParameter::from_serial(&1u64).expect("Parameter has valid size"),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect_err("Writing a too long key should fail");
+    assert!(matches!(
+        res_update.kind,
+        ContractInvokeErrorKind::StateKeyTooLarge(StateKeyTooLarge {
+            key_length: 8,
+            max_length: 7,
+        })
+    ));
+    // Nothing was written to the state.
+    assert!(chain.contract_state_lookup(contract_address, &u64::to_le_bytes(0)).is_none());
+
+    // With the limit set to exactly the key length, the update succeeds.
+    let mut chain = Chain::builder().max_state_key_length(8).build().expect("Chain should build");
+    let contract_address = deploy_and_init_recorder(&mut chain);
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(100000),
+            UpdateContractPayload {
+                address:      contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("recorder.record_u64".into()),
+                message:      OwnedParameter::from_serial(&1u64).expect("Parameter has valid size"),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Writing a key of the maximum length should work");
+    assert!(chain.contract_state_lookup(contract_address, &u64::to_le_bytes(0)).is_some());
+}
+
+/// Test that the default maximum key length is used when none is configured.
+#[test]
+fn test_default_max_state_key_length() {
+    assert_eq!(Chain::new().max_state_key_length(), MAX_STATE_KEY_LENGTH);
+}
+
+/// Test that `contract_state_keys` returns the smallest keys of the state in
+/// sorted order, up to the limit.
+#[test]
+fn test_recorder_state_keys() {
+    let mut chain = Chain::new();
+    let contract_address = deploy_and_init_recorder(&mut chain);
+    // Record 60 values, which are inserted with the keys `0..60` as little
+    // endian `u64`s.
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(100000),
+            UpdateContractPayload {
+                address:      contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("recorder.record_u64".into()),
+                message:      OwnedParameter::from_serial(&60u64)
+                    .expect("Parameter has valid size"),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Update failed");
+
+    // Since the keys are below 256, their order is the order of the first byte.
+    let expected: Vec<Vec<u8>> = (0..10u64).map(|key| u64::to_le_bytes(key).to_vec()).collect();
+    assert_eq!(chain.contract_state_keys(contract_address, 10), Some(expected));
+
+    let keys = chain.contract_state_keys(contract_address, 100).expect("Contract exists");
+    assert_eq!(keys.len(), 60);
+    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+
+    assert_eq!(chain.contract_state_keys(contract_address, 0), Some(Vec::new()));
+    assert_eq!(chain.contract_state_keys(ContractAddress::new(42, 0), 10), None);
+}
+
+/// Create an account, and deploy and initialize the recorder contract.
+fn deploy_and_init_recorder(chain: &mut Chain) -> ContractAddress {
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("record-parameters.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+
+    chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_recorder".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address
+}
+
+/// Test that the state size counts the entries written by the recorder, and
+/// stops counting them when they are deleted.
+#[test]
+fn test_recorder_state_size() {
+    let mut chain = Chain::new();
+    let contract_address = deploy_and_init_recorder(&mut chain);
+    let initial = chain.contract_state_size(contract_address).expect("Contract exists");
+
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(100000),
+            UpdateContractPayload {
+                address:      contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("recorder.record_u64".into()),
+                message:      OwnedParameter::from_serial(&20u64)
+                    .expect("Parameter has valid size"),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Update failed");
+    let size = chain.contract_state_size(contract_address).expect("Contract exists");
+    // Each recorded value gets its own entry with an 8 byte key.
+    assert_eq!(size.entries, initial.entries + 20);
+    assert_eq!(size.key_bytes, initial.key_bytes + 20 * 8);
+    assert!(size.total_bytes() >= size.key_bytes);
+
+    // Deleted entries no longer count.
+    for index in 0..5u64 {
+        assert!(chain
+            .delete_contract_state_entry_unchecked(contract_address, &index.to_le_bytes())
+            .expect("Contract exists"));
+    }
+    let after_delete = chain.contract_state_size(contract_address).expect("Contract exists");
+    assert_eq!(after_delete.entries, size.entries - 5);
+    assert_eq!(after_delete.key_bytes, size.key_bytes - 5 * 8);
+    assert!(after_delete.total_bytes() < size.total_bytes());
+    assert_eq!(chain.contract_state_size(ContractAddress::new(100, 0)), None);
+}
+
+/// Initialize another instance of the recorder contract at `address`.
+fn init_another_recorder(chain: &mut Chain, address: ContractAddress) -> ContractAddress {
+    let mod_ref = chain.get_contract(address).expect("Contract exists").module_reference;
+    chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref,
+                init_name: OwnedContractName::new_unchecked("init_recorder".into()),
+                param: OwnedParameter::empty(),
+                amount: Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address
+}
+
+/// Record `count` values in the recorder contract at `address`.
+fn record_values(chain: &mut Chain, address: ContractAddress, count: u64) {
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(100000),
+            UpdateContractPayload {
+                address,
+                receive_name: OwnedReceiveName::new_unchecked("recorder.record_u64".into()),
+                message: OwnedParameter::from_serial(&count).expect("Parameter has valid size"),
+                amount: Amount::zero(),
+            },
+        )
+        .expect("Update failed");
+}
+
+/// Test that contracts which recorded the same values have equal states.
+#[test]
+fn test_recorder_states_equal() {
+    let mut chain = Chain::new();
+    let address_a = deploy_and_init_recorder(&mut chain);
+    let address_b = init_another_recorder(&mut chain, address_a);
+    chain.assert_states_equal(address_a, address_b);
+
+    record_values(&mut chain, address_a, 20);
+    record_values(&mut chain, address_b, 10);
+    record_values(&mut chain, address_b, 10);
+    chain.assert_states_equal(address_a, address_b);
+    chain.assert_states_equal(address_b, address_a);
+}
+
+/// Test that keys only present in one of the states are reported as missing
+/// from the other.
+#[test]
+#[should_panic(
+    expected = "differ in 5 key(s). The first differing key 0x0a00000000000000 has the value"
+)]
+fn test_recorder_states_missing_keys() {
+    let mut chain = Chain::new();
+    let address_a = deploy_and_init_recorder(&mut chain);
+    let address_b = init_another_recorder(&mut chain, address_a);
+    record_values(&mut chain, address_a, 15);
+    record_values(&mut chain, address_b, 10);
+    chain.assert_states_equal(address_a, address_b);
+}
+
+/// Test that comparing with a contract which does not exist panics with a
+/// clear message.
+#[test]
+#[should_panic(expected = "The contract <42,0> does not exist.")]
+fn test_recorder_states_unknown_contract() {
+    let mut chain = Chain::new();
+    let address = deploy_and_init_recorder(&mut chain);
+    chain.assert_states_equal(address, ContractAddress::new(42, 0));
+}
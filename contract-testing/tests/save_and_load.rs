@@ -0,0 +1,62 @@
+//! This module tests saving and loading labelled checkpoints of the chain with
+//! `Chain::save` and `Chain::load`. It uses the counter contract, which
+//! maintains a 64-bit counter in its state.
+
+use concordium_smart_contract_testing::*;
+mod helpers;
+
+#[test]
+fn test_save_and_load() {
+    let mut chain = Chain::new();
+    let initial_balance = Amount::from_ccd(1000000);
+    chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+
+    let address = helpers::deploy_and_init_counter(&mut chain).contract_address;
+
+    assert!(chain.save("after-setup").is_none(), "The label is not in use");
+    let balance_after_setup = chain.account_balance_available(helpers::ACC_0);
+
+    helpers::increment(&mut chain, address);
+    helpers::increment(&mut chain, address);
+    helpers::assert_counter_state(&chain, address, 2);
+    assert!(chain.save("after-increments").is_none(), "The label is not in use");
+
+    // Loading the checkpoint resets both the contract state and the balance of
+    // the invoker, which paid for the updates.
+    chain.load("after-setup").expect("Checkpoint exists");
+    helpers::assert_counter_state(&chain, address, 0);
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), balance_after_setup);
+
+    // The checkpoint is kept, so the chain can move between the checkpoints.
+    helpers::increment(&mut chain, address);
+    helpers::assert_counter_state(&chain, address, 1);
+    chain.load("after-increments").expect("Checkpoint exists");
+    helpers::assert_counter_state(&chain, address, 2);
+    chain.load("after-setup").expect("Checkpoint exists");
+    helpers::assert_counter_state(&chain, address, 0);
+
+    // Saving over a label returns the previous checkpoint.
+    helpers::increment(&mut chain, address);
+    let previous = chain.save("after-setup").expect("The label is in use");
+    chain.load("after-setup").expect("Checkpoint exists");
+    helpers::assert_counter_state(&chain, address, 1);
+    chain.restore(&previous);
+    helpers::assert_counter_state(&chain, address, 0);
+}
+
+/// Test that loading a label that was never saved fails and leaves the chain
+/// unchanged.
+#[test]
+fn test_load_unknown_checkpoint() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    chain.save("after-setup");
+
+    assert_eq!(
+        chain.load("before-setup"),
+        Err(UnknownCheckpoint {
+            label: "before-setup".into(),
+        })
+    );
+    assert!(chain.account_exists(helpers::ACC_0));
+}
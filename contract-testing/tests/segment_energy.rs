@@ -0,0 +1,83 @@
+//! This module tests `ContractInvokeSuccess::peak_segment_energy`, the energy
+//! of the most expensive stretch of contract code between two interrupts. It
+//! uses a small module whose entrypoint loops a given number of times before
+//! and after querying the balance of an account, which interrupts it.
+
+use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder, I32, I64};
+mod helpers;
+
+/// A loop decrementing the local `1` from `iterations` to 0.
+fn count_down(body: Body, iterations: i32) -> Body {
+    body.i32_const(iterations)
+        .local_set(1)
+        .loop_block()
+        .local_get(1)
+        .i32_const(1)
+        .i32_sub()
+        .local_tee(1)
+        .br_if(0)
+        .end()
+}
+
+/// Test that the peak segment energy of an update is the energy of its most
+/// expensive segment, rather than of all the contract code it ran.
+#[test]
+fn test_peak_segment_energy() {
+    let mut module = ModuleBuilder::new();
+    let invoke = module.import("invoke", &[I32; 3], &[I64]);
+    module.memory(1);
+    module.data(0, &helpers::ACC_0.0);
+    module.entrypoint("init_segments", &[], Body::new().i32_const(0));
+    // The segment after the query does three times as much work as the one
+    // before it.
+    let body = count_down(Body::new(), 100_000)
+        .i32_const(2) // The tag of a query of an account balance.
+        .i32_const(0)
+        .i32_const(32)
+        .call(invoke)
+        .drop_value();
+    module.entrypoint("segments.run", &[I32], count_down(body, 300_000).i32_const(0));
+
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    let res_deploy = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &module.build())
+        .expect("Deploying valid module should work");
+    let contract_address = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_segments".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address;
+    let res_update = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(1_000_000),
+            UpdateContractPayload {
+                address:      contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("segments.run".into()),
+                message:      OwnedParameter::empty(),
+                amount:       Amount::zero(),
+            },
+        )
+        .expect("Updating valid contract should work");
+
+    // The energy of both segments, together with the energy of the query.
+    let code_energy =
+        res_update.energy_used - res_update.storage_energy - res_update.module_load_energy;
+    // The peak is the second segment, which is about three quarters of the
+    // code energy, so it is neither the first segment nor both of them.
+    assert!(res_update.peak_segment_energy < code_energy);
+    assert!(res_update.peak_segment_energy.energy * 2 > code_energy.energy);
+}
@@ -0,0 +1,146 @@
+//! This module tests counting the state entries written by an update, and
+//! limiting them with `Chain::set_max_state_writes_per_update`. It uses a small
+//! module whose entrypoints create, write and delete fixed entries, so that the
+//! tests control the exact number of writes.
+
+use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder, I32, I64};
+mod helpers;
+
+/// Deploy a module with the contract `writes` and initialize it. Its
+/// entrypoint `writes.create{n}` creates the entries with the one byte keys
+/// `0` to `n - 1` for `n` in `counts`, and `writes.churn` creates the entry
+/// `0`, writes to it twice and deletes it again.
+fn deploy_and_init(chain: &mut Chain, counts: &[u8]) -> ContractAddress {
+    let mut module = ModuleBuilder::new();
+    let create = module.import("state_create_entry", &[I32; 2], &[I64]);
+    let write = module.import("state_entry_write", &[I64, I32, I32, I32], &[I32]);
+    let delete = module.import("state_delete_entry", &[I32; 2], &[I32]);
+    module.memory(1);
+    // The key `i` is the byte at address `i`.
+    module.data(0, &(0..=u8::MAX).collect::<Vec<_>>());
+    module.entrypoint("init_writes", &[], Body::new().i32_const(0));
+    for &count in counts {
+        let mut body = Body::new();
+        for key in 0..count {
+            body = body.i32_const(key.into()).i32_const(1).call(create).drop_value();
+        }
+        module.entrypoint(&format!("writes.create{count}"), &[], body.i32_const(0));
+    }
+    let mut body = Body::new().i32_const(0).i32_const(1).call(create).local_set(1);
+    for _ in 0..2 {
+        // Write the byte at address 0 to the start of the entry.
+        body = body.local_get(1).i32_const(0).i32_const(1).i32_const(0).call(write).drop_value();
+    }
+    let body = body.i32_const(0).i32_const(1).call(delete).drop_value();
+    module.entrypoint("writes.churn", &[I64], body.i32_const(0));
+
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    let res_deploy = chain
+        .module_deploy_v1_from_bytes(Signer::with_one_key(), helpers::ACC_0, &module.build())
+        .expect("Deploying valid module should work");
+    chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_writes".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address
+}
+
+/// Update the entrypoint `writes.{entrypoint}` of the contract at `address`.
+fn update(
+    chain: &mut Chain,
+    address: ContractAddress,
+    entrypoint: &str,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    chain.contract_update(
+        Signer::with_one_key(),
+        helpers::ACC_0,
+        Address::Account(helpers::ACC_0),
+        Energy::from(100000),
+        UpdateContractPayload {
+            address,
+            receive_name: OwnedReceiveName::new_unchecked(format!("writes.{entrypoint}")),
+            message: OwnedParameter::empty(),
+            amount: Amount::zero(),
+        },
+    )
+}
+
+/// Test that an update making exactly the maximum number of writes succeeds,
+/// and that one making a single write more fails at the maximum without
+/// changing the state.
+#[test]
+fn test_max_state_writes() {
+    let mut chain = Chain::new();
+    let address = deploy_and_init(&mut chain, &[5, 6]);
+    chain.set_max_state_writes_per_update(Some(5));
+
+    let res_update =
+        update(&mut chain, address, "create5").expect("Writing the maximum should work");
+    assert_eq!(res_update.state_writes, StateWrites {
+        created:  5,
+        modified: 0,
+        deleted:  0,
+    });
+    assert_eq!(res_update.state_diffs, vec![(address, StateDiff {
+        added: (0..5).map(|key| (vec![key], Vec::new())).collect(),
+        ..StateDiff::default()
+    })]);
+
+    let balance_before = chain.account_balance_available(helpers::ACC_0).unwrap();
+    let res_update =
+        update(&mut chain, address, "create6").expect_err("Writing above the maximum should fail");
+    assert!(matches!(
+        res_update.kind,
+        ContractInvokeErrorKind::StateWritesExceeded {
+            writes,
+            max_writes: 5,
+        } if writes.created == 5 && writes.total() == 5
+    ));
+    // The update is charged for, but nothing was written to the state.
+    assert_eq!(
+        chain.account_balance_available(helpers::ACC_0),
+        Some(balance_before - res_update.transaction_fee)
+    );
+    assert!(chain.contract_state_lookup(address, &[5]).is_none());
+}
+
+/// Test that every write counts, even if it writes an entry which was already
+/// written or deletes an entry created by the same update, and that the
+/// writes are counted without a maximum.
+#[test]
+fn test_state_writes_counted_per_write() {
+    let mut chain = Chain::new();
+    let address = deploy_and_init(&mut chain, &[]);
+
+    let res_update = update(&mut chain, address, "churn").expect("Updating should work");
+    assert_eq!(res_update.state_writes, StateWrites {
+        created:  1,
+        modified: 2,
+        deleted:  1,
+    });
+    assert!(chain.contract_state_lookup(address, &[0]).is_none());
+    // The entry was deleted again, so the state is unchanged.
+    assert!(res_update.state_diffs.is_empty());
+
+    // The fourth write exceeds the maximum.
+    chain.set_max_state_writes_per_update(Some(3));
+    let res_update = update(&mut chain, address, "churn").expect_err("Updating should fail");
+    assert!(matches!(res_update.kind, ContractInvokeErrorKind::StateWritesExceeded {
+        writes:     StateWrites {
+            created:  1,
+            modified: 2,
+            deleted:  0,
+        },
+        max_writes: 3,
+    }));
+}
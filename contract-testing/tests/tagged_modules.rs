@@ -0,0 +1,116 @@
+//! This module tests deploying modules under tags with `Chain::deploy_tagged`
+//! and initializing contracts from them with `Chain::contract_init_tagged`.
+use concordium_smart_contract_testing::*;
+mod helpers;
+
+/// Test that contracts are initialized from the module deployed with the tag,
+/// and that unknown and conflicting tags are rejected without charging a fee.
+#[test]
+fn test_tagged_modules() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let module_0 = module_load_v1_raw(helpers::wasm_test_file("upgrading_0.wasm"))
+        .expect("module should exist");
+    let module_1 = module_load_v1_raw(helpers::wasm_test_file("upgrading_1.wasm"))
+        .expect("module should exist");
+
+    let res_deploy_0 = chain
+        .deploy_tagged(Signer::with_one_key(), helpers::ACC_0, "v0", module_0.clone())
+        .expect("Deploying valid module should work");
+    let res_deploy_1 = chain
+        .deploy_tagged(Signer::with_one_key(), helpers::ACC_0, "v1", module_1.clone())
+        .expect("Deploying valid module should work");
+    assert_eq!(chain.module_tag("v0"), Some(res_deploy_0.module_reference));
+    assert_eq!(chain.module_tag("v1"), Some(res_deploy_1.module_reference));
+    assert_eq!(chain.module_tag("v2"), None);
+
+    let init_payload = |tag: &str| InitContractTaggedPayload {
+        amount:    Amount::zero(),
+        tag:       tag.into(),
+        init_name: OwnedContractName::new_unchecked("init_a".into()),
+        param:     OwnedParameter::empty(),
+    };
+    let res_init = chain
+        .contract_init_tagged(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            init_payload("v0"),
+        )
+        .expect("Initializing valid contract should work");
+    assert_eq!(
+        chain.contract_info(res_init.contract_address).map(|info| info.module_reference),
+        Some(res_deploy_0.module_reference)
+    );
+
+    // The tag of another module cannot be reused, and deploying the same
+    // module again fails like any duplicate deployment.
+    let balance = chain.account_balance_available(helpers::ACC_0);
+    let err = chain
+        .deploy_tagged(Signer::with_one_key(), helpers::ACC_0, "v0", module_1)
+        .expect_err("The tag is already used for another module");
+    assert!(matches!(
+        err.kind,
+        ModuleDeployErrorKind::TagInUse { tag, module } if tag == "v0" && module == res_deploy_0.module_reference
+    ));
+    assert_eq!(err.transaction_fee, Amount::zero());
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), balance);
+    let err = chain
+        .deploy_tagged(Signer::with_one_key(), helpers::ACC_0, "v0", module_0)
+        .expect_err("The module is already deployed");
+    assert!(matches!(err.kind, ModuleDeployErrorKind::DuplicateModule(_)));
+    assert_eq!(chain.module_tag("v0"), Some(res_deploy_0.module_reference));
+
+    // A deployed module can be given another tag, but not a tag of another
+    // module nor can a missing module be tagged.
+    chain.tag_module("latest", res_deploy_0.module_reference).expect("Tagging should work");
+    chain.tag_module("latest", res_deploy_0.module_reference).expect("Retagging should work");
+    assert_eq!(chain.module_tag("latest"), Some(res_deploy_0.module_reference));
+    let err = chain
+        .tag_module("latest", res_deploy_1.module_reference)
+        .expect_err("The tag is already used for another module");
+    assert!(matches!(
+        err,
+        TagModuleError::TagInUse { tag, module } if tag == "latest" && module == res_deploy_0.module_reference
+    ));
+    let err = chain
+        .tag_module("missing", ModuleReference::from([0; 32]))
+        .expect_err("The module does not exist");
+    assert!(matches!(err, TagModuleError::ModuleDoesNotExist(_)));
+    assert_eq!(chain.module_tag("missing"), None);
+
+    // An unknown tag is rejected without charging a fee.
+    let balance = chain.account_balance_available(helpers::ACC_0);
+    let err = chain
+        .contract_init_tagged(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            init_payload("v2"),
+        )
+        .expect_err("The tag is unknown");
+    assert!(matches!(err.kind, ContractInitErrorKind::UnknownModuleTag(tag) if tag == "v2"));
+    assert_eq!(err.transaction_fee, Amount::zero());
+    assert_eq!(chain.account_balance_available(helpers::ACC_0), balance);
+}
+
+/// Test that the tags are restored together with the modules by checkpoints.
+#[test]
+fn test_tagged_modules_restore() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let checkpoint = chain.checkpoint();
+    chain
+        .deploy_tagged(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            "v0",
+            module_load_v1_raw(helpers::wasm_test_file("upgrading_0.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    assert!(chain.module_tag("v0").is_some());
+
+    chain.restore(&checkpoint);
+    assert_eq!(chain.module_tag("v0"), None);
+}
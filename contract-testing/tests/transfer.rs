@@ -116,5 +116,237 @@ fn test_transfer() {
                 events:           Vec::new(),
             },
         }
-    ])
+    ]);
+    // The transfer is the only movement of CCD made by the contract.
+    assert_eq!(res_update.transfers(), vec![(
+        contract_address,
+        Address::Account(helpers::ACC_0),
+        Amount::from_micro_ccd(17)
+    )]);
+}
+
+/// Test that `track_balance` reports the gain of the recipient and the net loss
+/// of the sender, which includes the transaction fee, when forwarding an
+/// amount through the contract.
+#[test]
+fn test_track_balance() {
+    let mut chain = Chain::new();
+    let initial_balance = Amount::from_ccd(10000);
+    chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+    chain.create_account(Account::new(helpers::ACC_1, initial_balance));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("transfer.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_transfer".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+    let contract_address = res_init.contract_address;
+
+    let amount = Amount::from_ccd(123);
+    let forward = |chain: &mut Chain| {
+        chain
+            .contract_update(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                Address::Account(helpers::ACC_0),
+                Energy::from(10000),
+                UpdateContractPayload {
+                    address: contract_address,
+                    receive_name: OwnedReceiveName::new_unchecked("transfer.forward".into()),
+                    message: OwnedParameter::from_serial(&helpers::ACC_1)
+                        .expect("Parameter has valid size"),
+                    amount,
+                },
+            )
+            .expect("Updating contract should succeed")
+    };
+
+    // The recipient gains the amount.
+    let recipient_delta = chain.track_balance(Address::Account(helpers::ACC_1), |chain| {
+        forward(chain);
+    });
+    assert_eq!(recipient_delta, AmountDelta::Positive(amount));
+
+    // The sender loses the amount and the transaction fee.
+    let mut transaction_fee = Amount::zero();
+    let sender_delta = chain.track_balance(Address::Account(helpers::ACC_0), |chain| {
+        transaction_fee = forward(chain).transaction_fee;
+    });
+    assert_eq!(sender_delta, AmountDelta::Negative(amount + transaction_fee));
+
+    // The contract forwards everything it receives.
+    let contract_delta = chain.track_balance(Address::Contract(contract_address), |chain| {
+        forward(chain);
+    });
+    assert!(contract_delta.is_zero());
+}
+
+/// Test that a contract forwarding the amount it receives observes its
+/// balance including the amount, and the decremented balance after the
+/// transfer.
+#[test]
+fn test_self_balance_observations() {
+    use std::sync::{Arc, Mutex};
+
+    let mut chain = Chain::new();
+    let initial_balance = Amount::from_ccd(10000);
+    chain.create_account(Account::new(helpers::ACC_0, initial_balance));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("transfer.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_transfer".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+    let contract_address = res_init.contract_address;
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+    let observed_hook = Arc::clone(&observed);
+    chain.on_self_balance_query(move |address, balance| {
+        observed_hook.lock().unwrap().push((address, balance));
+    });
+
+    let amount = Amount::from_micro_ccd(123);
+    chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address: contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("transfer.forward".into()),
+                message: OwnedParameter::from_serial(&helpers::ACC_0)
+                    .expect("Parameter has valid size"),
+                amount,
+            },
+        )
+        .expect("Updating valid contract should work");
+
+    assert_eq!(*observed.lock().unwrap(), vec![
+        (contract_address, amount),
+        (contract_address, Amount::zero())
+    ]);
+}
+
+/// Test that CCD moved between accounts and contracts is conserved, and that
+/// the only CCD leaving the supply is the transaction fees, including those
+/// of failed transactions.
+#[test]
+fn test_supply_conserved() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(10000)));
+    chain.create_account(Account::new(helpers::ACC_1, Amount::from_ccd(10000)));
+    let initial_supply = chain.total_ccd() + chain.fees_collected();
+    assert_eq!(initial_supply, Amount::from_ccd(20000));
+
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("transfer.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    let res_init = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                mod_ref:   res_deploy.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_transfer".into()),
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work");
+    let update = |chain: &mut Chain, entrypoint: &str, message: OwnedParameter, amount| {
+        chain.contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(10000),
+            UpdateContractPayload {
+                address: res_init.contract_address,
+                receive_name: OwnedReceiveName::new_unchecked(format!("transfer.{entrypoint}")),
+                message,
+                amount,
+            },
+        )
+    };
+    let res_deposit =
+        update(&mut chain, "deposit", OwnedParameter::empty(), Amount::from_micro_ccd(1000))
+            .expect("Depositing should succeed");
+    let res_send = update(
+        &mut chain,
+        "send",
+        OwnedParameter::from_serial(&(helpers::ACC_1, Amount::from_micro_ccd(17)))
+            .expect("Parameter has valid size"),
+        Amount::zero(),
+    )
+    .expect("Sending less than the balance should succeed");
+    // The update fails after being charged.
+    let err_update = update(&mut chain, "missing", OwnedParameter::empty(), Amount::zero())
+        .expect_err("Updating a missing entrypoint should fail");
+    let res_transfer = chain
+        .transfer(Signer::with_one_key(), helpers::ACC_1, helpers::ACC_0, Amount::from_ccd(5))
+        .expect("Transferring between accounts should succeed");
+
+    let fees = [
+        res_deploy.transaction_fee,
+        res_init.transaction_fee,
+        res_deposit.transaction_fee,
+        res_send.transaction_fee,
+        err_update.transaction_fee,
+        res_transfer.transaction_fee,
+    ];
+    assert!(err_update.transaction_fee > Amount::zero());
+    assert_eq!(chain.fees_collected(), fees.into_iter().fold(Amount::zero(), |sum, fee| sum + fee));
+    assert_eq!(chain.total_ccd(), initial_supply - chain.fees_collected());
+    chain.assert_supply_conserved(initial_supply);
+}
+
+/// Test that `assert_supply_conserved` panics when CCD is created.
+#[test]
+#[should_panic(expected = "The supply of CCD was not conserved")]
+fn test_supply_not_conserved() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(10)));
+    let initial_supply = chain.total_ccd() + chain.fees_collected();
+    chain.create_account(Account::new(helpers::ACC_1, Amount::from_ccd(1)));
+    chain.assert_supply_conserved(initial_supply);
 }
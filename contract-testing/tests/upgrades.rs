@@ -1,6 +1,7 @@
 //! This module contains tests for the native smart contract upgrade
 //! functionality.
 use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder, I32, I64};
 mod helpers;
 
 /// Test a basic upgrade, ensuring that the new module is in place by
@@ -46,6 +47,16 @@ fn test() {
         )
         .expect("Initializing valid contract should work");
 
+    assert_eq!(
+        chain.contract_info(res_init.contract_address),
+        Some(ContractInfo {
+            module_reference: res_deploy_0.module_reference,
+            contract_name:    OwnedContractName::new_unchecked("init_a".into()),
+            owner:            helpers::ACC_0,
+            amount:           Amount::zero(),
+        })
+    );
+
     // Upgrade the contract to the `upgrading_1` module by calling the `bump`
     // entrypoint.
     let res_update_upgrade = chain
@@ -90,6 +101,16 @@ fn test() {
     assert!(matches!(res_update_new.effective_trace_elements_cloned()[..], [
         ContractTraceElement::Updated { .. }
     ]));
+    // The contract info reflects the upgrade.
+    assert_eq!(
+        chain.contract_info(res_init.contract_address).map(|info| info.module_reference),
+        Some(res_deploy_1.module_reference)
+    );
+    // The contract is only listed under the module it was upgraded to.
+    assert_eq!(chain.contracts_by_module(res_deploy_0.module_reference), Vec::new());
+    assert_eq!(chain.contracts_by_module(res_deploy_1.module_reference), vec![
+        res_init.contract_address
+    ]);
 }
 
 /// The contract in this test, triggers an upgrade and then in the same
@@ -677,3 +698,148 @@ fn test_changing_entrypoint() {
         ContractTraceElement::Updated { .. }
     ]));
 }
+
+/// A v1 module with the contract `a`, including the prefix of 4 version bytes
+/// and 4 module length bytes.
+///
+/// If `migrating`, the entrypoint `a.upgrade` upgrades the contract to the
+/// module whose reference is its parameter, and then migrates the state from
+/// the layout of the old module, with the counter as one byte under the key
+/// `v1:count`, to the layout of the new module, with the counter as two bytes
+/// under the key `v2:count`. Otherwise the module only has the contract.
+fn migrating_module(migrating: bool) -> Vec<u8> {
+    let mut module = ModuleBuilder::new();
+    if migrating {
+        let get_parameter_section = module.import("get_parameter_section", &[I32; 4], &[I32]);
+        let upgrade = module.import("upgrade", &[I32], &[I64]);
+        let lookup = module.import("state_lookup_entry", &[I32; 2], &[I64]);
+        let read = module.import("state_entry_read", &[I64, I32, I32, I32], &[I32]);
+        let create = module.import("state_create_entry", &[I32; 2], &[I64]);
+        let write = module.import("state_entry_write", &[I64, I32, I32, I32], &[I32]);
+        let delete = module.import("state_delete_entry", &[I32; 2], &[I32]);
+        module.memory(1);
+        // The module reference is read to address 0, the keys are at 32 and 40,
+        // and the counter is copied to address 48.
+        module.data(32, b"v1:countv2:count");
+        // The local holds the state entry being copied.
+        let body = Body::new()
+            .i32_const(0)
+            .i32_const(0)
+            .i32_const(32)
+            .i32_const(0)
+            .call(get_parameter_section)
+            .drop_value()
+            .i32_const(0)
+            .call(upgrade)
+            .drop_value()
+            // Read the old counter.
+            .i32_const(32)
+            .i32_const(8)
+            .call(lookup)
+            .local_set(1)
+            .local_get(1)
+            .i32_const(48)
+            .i32_const(1)
+            .i32_const(0)
+            .call(read)
+            .drop_value()
+            // Write it as two bytes under the new key and delete the old one.
+            .i32_const(40)
+            .i32_const(8)
+            .call(create)
+            .local_set(1)
+            .local_get(1)
+            .i32_const(48)
+            .i32_const(2)
+            .i32_const(0)
+            .call(write)
+            .drop_value()
+            .i32_const(32)
+            .i32_const(8)
+            .call(delete)
+            .drop_value()
+            .i32_const(0);
+        module.entrypoint("a.upgrade", &[I64], body);
+    }
+    module.entrypoint("init_a", &[], Body::new().i32_const(0));
+    module.build()
+}
+
+/// Test that the callback registered with `on_upgrade` is called with the
+/// modules and a snapshot of the state at the time of the upgrade, which can
+/// be compared with the state after the migration run by the update.
+#[test]
+fn test_on_upgrade() {
+    use std::sync::{Arc, Mutex};
+
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000000)));
+    let deploy = |chain: &mut Chain, migrating: bool| {
+        chain
+            .module_deploy_v1_from_bytes(
+                Signer::with_one_key(),
+                helpers::ACC_0,
+                &migrating_module(migrating),
+            )
+            .expect("Deploying valid module should work")
+            .module_reference
+    };
+    let module_0 = deploy(&mut chain, true);
+    let module_1 = deploy(&mut chain, false);
+    let address = chain
+        .contract_init(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Energy::from(10000),
+            InitContractPayload {
+                init_name: OwnedContractName::new_unchecked("init_a".into()),
+                mod_ref:   module_0,
+                param:     OwnedParameter::empty(),
+                amount:    Amount::zero(),
+            },
+        )
+        .expect("Initializing valid contract should work")
+        .contract_address;
+    // The counter in the layout of the old module.
+    chain
+        .set_contract_state_entry_unchecked(address, b"v1:count".to_vec(), vec![7])
+        .expect("The contract exists");
+
+    let upgrades = Arc::new(Mutex::new(Vec::new()));
+    let upgrades_hook = Arc::clone(&upgrades);
+    chain.on_upgrade(move |upgrade| upgrades_hook.lock().unwrap().push(upgrade.clone()));
+    let res_update = chain
+        .contract_update(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            Address::Account(helpers::ACC_0),
+            Energy::from(100000),
+            UpdateContractPayload {
+                address,
+                receive_name: OwnedReceiveName::new_unchecked("a.upgrade".into()),
+                message: OwnedParameter::from_serial(&module_1).expect("Parameter has valid size"),
+                amount: Amount::zero(),
+            },
+        )
+        .expect("Upgrading and migrating should work");
+    assert_eq!(chain.get_contract(address).map(|c| c.module_reference), Some(module_1));
+
+    let upgrades = upgrades.lock().unwrap();
+    let [upgrade] = &upgrades[..] else {
+        panic!("Expected one upgrade, but got {}", upgrades.len());
+    };
+    assert_eq!(upgrade.address, address);
+    assert_eq!(upgrade.from, module_0);
+    assert_eq!(upgrade.to, module_1);
+    // The snapshot has the old layout, and is not affected by the migration
+    // made after the upgrade.
+    assert_eq!(upgrade.state.entries(), [(b"v1:count".to_vec(), vec![7])]);
+    // The state after the update has the new layout with the same counter.
+    assert_eq!(chain.contract_state_keys(address, usize::MAX), Some(vec![b"v2:count".to_vec()]));
+    assert_eq!(chain.contract_state_lookup(address, b"v2:count"), Some(vec![7, 0]));
+    assert_eq!(res_update.state_diffs, [(address, StateDiff {
+        added:   vec![(b"v2:count".to_vec(), vec![7, 0])],
+        removed: vec![(b"v1:count".to_vec(), vec![7])],
+        changed: Vec::new(),
+    })]);
+}
@@ -0,0 +1,131 @@
+//! This module contains tests for validating modules without deploying them.
+use concordium_smart_contract_testing::*;
+use helpers::wasm::{Body, ModuleBuilder};
+mod helpers;
+
+/// Test that a valid module passes validation, and that the report matches the
+/// module when it is deployed.
+#[test]
+fn test_validate_valid_module() {
+    let bytes =
+        std::fs::read(helpers::wasm_test_file("call-counter.wasm")).expect("module should exist");
+    let mut chain = Chain::new();
+    let report = chain.validate_module_bytes(&bytes).expect("Module is valid");
+
+    assert_eq!(report.size, bytes.len() as u64);
+    assert!(report.exports.contains(&"init_counter".to_string()));
+    assert!(report.exports.contains(&"counter.inc".to_string()));
+    assert!(report.exports.contains(&"counter.inc10".to_string()));
+    // The counter calls itself and accesses its state via the host.
+    assert!(!report.imports.is_empty());
+    assert!(report.imports.iter().all(|(module, _)| module == "concordium"));
+
+    chain.create_account(Account::new(helpers::ACC_0, Amount::from_ccd(1000)));
+    let res_deploy = chain
+        .module_deploy_v1(
+            Signer::with_one_key(),
+            helpers::ACC_0,
+            module_load_v1_raw(helpers::wasm_test_file("call-counter.wasm"))
+                .expect("module should exist"),
+        )
+        .expect("Deploying valid module should work");
+    assert_eq!(report.module_reference, res_deploy.module_reference);
+}
+
+/// Test that modules are validated with the rules of the protocol version of
+/// the chain.
+#[test]
+fn test_validate_with_protocol_version() {
+    let bytes =
+        std::fs::read(helpers::wasm_test_file("call-counter.wasm")).expect("module should exist");
+    let chain = Chain::builder().protocol_version(ProtocolVersion::P3).build().unwrap();
+    let error =
+        chain.validate_module_bytes(&bytes).expect_err("Version 1 modules are not supported");
+    assert_eq!(error.reason(), &ModuleInvalidReason::Other);
+
+    let chain = Chain::builder().protocol_version(ProtocolVersion::P4).build().unwrap();
+    chain.validate_module_bytes(&bytes).expect("Version 1 modules are supported");
+}
+
+/// Test that invalid modules are rejected.
+#[test]
+fn test_validate_invalid_module() {
+    let reason = |bytes: &[u8]| {
+        Chain::new().validate_module_bytes(bytes).expect_err("Module is invalid").reason().clone()
+    };
+
+    // Not a wasm module at all.
+    assert_eq!(reason(b"not a module"), ModuleInvalidReason::Malformed);
+
+    // A wasm module which is truncated in its last section.
+    let bytes =
+        std::fs::read(helpers::wasm_test_file("call-counter.wasm")).expect("module should exist");
+    assert_eq!(reason(&bytes[..bytes.len() - 1]), ModuleInvalidReason::Malformed);
+}
+
+/// Test that the validation rule broken by an invalid module is diagnosed.
+#[test]
+fn test_invalid_module_reason() {
+    let reason = |bytes: &[u8]| {
+        Chain::new().validate_module_bytes(bytes).expect_err("Module is invalid").reason().clone()
+    };
+    let magic = &b"\x00asm\x01\x00\x00\x00"[..];
+
+    assert_eq!(reason(b"not a module"), ModuleInvalidReason::Malformed);
+
+    // A custom section whose name is longer than the section. This is written
+    // by hand, since the builder only produces well-formed modules.
+    assert_eq!(
+        reason(&[magic, b"\x00\x03\x05ab"].concat()),
+        ModuleInvalidReason::MalformedCustomSection {
+            index: 0,
+        }
+    );
+
+    // Imports: `foo` from `env`.
+    let mut module = ModuleBuilder::new();
+    module.import_from("env", "foo", &[], &[]);
+    assert_eq!(reason(&module.wasm()), ModuleInvalidReason::DisallowedImport {
+        module: "env".into(),
+        name:   "foo".into(),
+    });
+
+    // Imports: `foo` from `concordium`, which is not a host function.
+    let mut module = ModuleBuilder::new();
+    module.import("foo", &[], &[]);
+    assert_eq!(reason(&module.wasm()), ModuleInvalidReason::DisallowedImport {
+        module: "concordium".into(),
+        name:   "foo".into(),
+    });
+
+    // Memory: 33 pages.
+    let mut module = ModuleBuilder::new();
+    module.memory(33);
+    assert_eq!(reason(&module.wasm()), ModuleInvalidReason::MemoryLimits {
+        initial: 33,
+        maximum: None,
+    });
+
+    // Functions: `init_x`, which returns 0, and one which uses a floating point
+    // instruction.
+    let illegal = |export: Option<&str>| {
+        let mut module = ModuleBuilder::new();
+        module.memory(1);
+        module.entrypoint("init_x", &[], Body::new().i32_const(0));
+        // `f32.const 0` and `drop`.
+        let index = module.function(&[], &[], &[], Body::new().raw(b"\x43\x00\x00\x00\x00\x1a"));
+        if let Some(name) = export {
+            module.export_function(name, index);
+        }
+        module.wasm()
+    };
+    assert_eq!(reason(&illegal(None)), ModuleInvalidReason::InvalidFunctionBody {
+        index:  1,
+        export: None,
+    });
+    // The other function is exported as `foo`, which is not a valid
+    // entrypoint name.
+    assert_eq!(reason(&illegal(Some("foo"))), ModuleInvalidReason::InvalidExport {
+        name: "foo".into(),
+    });
+}
@@ -148,6 +148,69 @@ fn test_account_transfer() {
     }),]);
 }
 
+/// Test that transferring 10 tokens in one batch uses less energy than
+/// transferring them one at a time.
+#[test]
+fn test_batched_transfer_energy() {
+    let (mut chain, _keypairs, contract_address, _update, _module_reference) =
+        initialize_contract_with_alice_tokens();
+
+    let single_transfer = concordium_cis2::Transfer {
+        from:     ALICE_ADDR,
+        to:       Receiver::Account(BOB),
+        token_id: TOKEN_0,
+        amount:   TokenAmountU64(1),
+        data:     AdditionalData::empty(),
+    };
+    let transfer = |chain: &mut Chain, transfers: Vec<_>| {
+        chain
+            .contract_update(
+                SIGNER,
+                ALICE,
+                ALICE_ADDR,
+                Energy::from(100000),
+                UpdateContractPayload {
+                    amount:       Amount::zero(),
+                    receive_name: OwnedReceiveName::new_unchecked(
+                        "cis2_multi.transfer".to_string(),
+                    ),
+                    address:      contract_address,
+                    message:      OwnedParameter::from_serial(&TransferParams::from(transfers))
+                        .expect("Transfer params"),
+                },
+            )
+            .expect("Transfer tokens")
+            .energy_used
+    };
+
+    let comparison = chain.compare_batch_energy(
+        10,
+        |chain| transfer(chain, vec![single_transfer.clone(); 10]),
+        |chain, _index| transfer(chain, vec![single_transfer.clone()]),
+    );
+    assert!(
+        comparison.batch_is_cheaper(),
+        "Batched transfer used {} energy, individual transfers used {} energy",
+        comparison.batched,
+        comparison.individual
+    );
+
+    // The comparison does not change the state of the chain.
+    let invoke = chain
+        .contract_invoke(ALICE, ALICE_ADDR, Energy::from(10000), UpdateContractPayload {
+            amount:       Amount::zero(),
+            receive_name: OwnedReceiveName::new_unchecked("cis2_multi.view".to_string()),
+            address:      contract_address,
+            message:      OwnedParameter::empty(),
+        })
+        .expect("Invoke view");
+    let rv: ViewState = invoke.parse_return_value().expect("ViewState return value");
+    assert_eq!(rv.state, vec![(ALICE_ADDR, ViewAddressState {
+        balances:  vec![(TOKEN_0, 100.into()), (TOKEN_1, 100.into())],
+        operators: Vec::new(),
+    }),]);
+}
+
 /// Test that you can add an operator.
 /// Initialize the contract with two tokens owned by Alice.
 /// Then add Bob as an operator for Alice.
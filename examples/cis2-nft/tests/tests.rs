@@ -194,6 +194,75 @@ fn test_add_operator() {
     assert_eq!(rv, OperatorOfQueryResponse(vec![true]));
 }
 
+/// Test that operator updates in one batch are applied in order.
+/// Initialize the contract with two tokens owned by Alice.
+/// Then add and remove Bob as an operator for Alice in the same batch.
+#[test]
+fn test_add_and_remove_operator_in_batch() {
+    let (mut chain, contract_address, _update) = initialize_contract_with_alice_tokens();
+
+    // Add and then remove Bob as an operator for Alice.
+    let params = UpdateOperatorParams(vec![
+        UpdateOperator {
+            update:   OperatorUpdate::Add,
+            operator: BOB_ADDR,
+        },
+        UpdateOperator {
+            update:   OperatorUpdate::Remove,
+            operator: BOB_ADDR,
+        },
+    ]);
+
+    let update = chain
+        .contract_update(SIGNER, ALICE, ALICE_ADDR, Energy::from(10000), UpdateContractPayload {
+            amount:       Amount::zero(),
+            receive_name: OwnedReceiveName::new_unchecked("cis2_nft.updateOperator".to_string()),
+            address:      contract_address,
+            message:      OwnedParameter::from_serial(&params).expect("UpdateOperator params"),
+        })
+        .expect("Update operator");
+
+    // Check that the operator events occurred in the order of the batch.
+    let events = update
+        .events()
+        .flat_map(|(_addr, events)| events.iter().map(|e| e.parse().expect("Deserialize event")))
+        .collect::<Vec<Cis2Event<ContractTokenId, ContractTokenAmount>>>();
+    assert_eq!(events, [
+        Cis2Event::UpdateOperator(UpdateOperatorEvent {
+            operator: BOB_ADDR,
+            owner:    ALICE_ADDR,
+            update:   OperatorUpdate::Add,
+        }),
+        Cis2Event::UpdateOperator(UpdateOperatorEvent {
+            operator: BOB_ADDR,
+            owner:    ALICE_ADDR,
+            update:   OperatorUpdate::Remove,
+        }),
+    ]);
+
+    // Construct a query parameter to check whether Bob is an operator for Alice.
+    let query_params = OperatorOfQueryParams {
+        queries: vec![OperatorOfQuery {
+            owner:   ALICE_ADDR,
+            address: BOB_ADDR,
+        }],
+    };
+
+    // Invoke the operatorOf view entrypoint and check that Bob is no longer an
+    // operator for Alice, since the removal was applied last.
+    let invoke = chain
+        .contract_invoke(ALICE, ALICE_ADDR, Energy::from(10000), UpdateContractPayload {
+            amount:       Amount::zero(),
+            receive_name: OwnedReceiveName::new_unchecked("cis2_nft.operatorOf".to_string()),
+            address:      contract_address,
+            message:      OwnedParameter::from_serial(&query_params).expect("OperatorOf params"),
+        })
+        .expect("Invoke view");
+
+    let rv: OperatorOfQueryResponse = invoke.parse_return_value().expect("OperatorOf return value");
+    assert_eq!(rv, OperatorOfQueryResponse(vec![false]));
+}
+
 /// Test that a transfer fails when the sender is neither an operator or the
 /// owner. In particular, Bob will attempt to transfer one of Alice's tokens to
 /// himself.
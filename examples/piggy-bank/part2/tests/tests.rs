@@ -164,6 +164,57 @@ fn test_smash_intact_not_owner() {
     );
 }
 
+/// Test that the owner of the piggy bank is the account that initialized it,
+/// and that only that account can smash it.
+#[test]
+fn test_smash_by_initializer() {
+    let mut chain = Chain::new();
+    chain.create_account(Account::new(ACC_ADDR_OWNER, ACC_INITIAL_BALANCE));
+    chain.create_account(Account::new(ACC_ADDR_OTHER, ACC_INITIAL_BALANCE));
+    let module =
+        module_load_v1("./concordium-out/module.wasm.v1").expect("Module exists and is valid");
+    let deployment = chain
+        .module_deploy_v1(Signer::with_one_key(), ACC_ADDR_OWNER, module)
+        .expect("Deploying valid module should succeed");
+    // The contract is initialized by `ACC_ADDR_OTHER`, which is thus its owner.
+    let initialization = chain
+        .contract_init(
+            Signer::with_one_key(),
+            ACC_ADDR_OTHER,
+            Energy::from(10000),
+            InitContractPayload {
+                amount:    Amount::zero(),
+                mod_ref:   deployment.module_reference,
+                init_name: OwnedContractName::new_unchecked("init_PiggyBank".to_string()),
+                param:     OwnedParameter::empty(),
+            },
+        )
+        .expect("Initialization should always succeed");
+    assert_eq!(chain.contract_owner(initialization.contract_address), Some(ACC_ADDR_OTHER));
+    assert_eq!(chain.contract_owner(ContractAddress::new(1, 0)), None);
+
+    let smash = |chain: &mut Chain, sender: AccountAddress| {
+        chain.contract_update(
+            Signer::with_one_key(),
+            sender,
+            Address::Account(sender),
+            Energy::from(10000),
+            UpdateContractPayload {
+                amount:       Amount::zero(),
+                address:      initialization.contract_address,
+                receive_name: OwnedReceiveName::new_unchecked("PiggyBank.smash".to_string()),
+                message:      OwnedParameter::empty(),
+            },
+        )
+    };
+    let error: SmashError = smash(&mut chain, ACC_ADDR_OWNER)
+        .expect_err("Only the initializer should be allowed to smash")
+        .parse_return_value()
+        .expect("Contract should return a `SmashError` in serialized form");
+    assert_eq!(error, SmashError::NotOwner);
+    smash(&mut chain, ACC_ADDR_OTHER).expect("The initializer should be allowed to smash");
+}
+
 /// Test that smashing an already smashed piggy bank is not allowed and thus
 /// results in a `SmashError::AlreadySmashed` error.
 #[test]